@@ -0,0 +1,38 @@
+//! Day 12's arrangement counting on single rows, without parsing. The rows are the
+//! puzzle's example rows unfolded like in part 2, plus rows of nothing but unknown springs
+//! of the longest length `count_arrangements` takes, which fill its whole cache.
+
+use aoc2023::day12::{count_arrangements, SpringStatus, MAX_SPRINGS};
+use divan::Bencher;
+
+fn main() {
+    divan::main();
+}
+
+fn springs(s: &str) -> Vec<SpringStatus> {
+    s.bytes()
+        .map(|c| match c {
+            b'.' => SpringStatus::Working,
+            b'#' => SpringStatus::Broken,
+            _ => SpringStatus::Unknown,
+        })
+        .collect()
+}
+
+#[divan::bench(args = [
+    ("???.###", &[1, 1, 3][..]),
+    (".??..??...?##.", &[1, 1, 3]),
+    ("?###????????", &[3, 2, 1]),
+])]
+fn unfolded(bencher: Bencher, (row, blocks): (&str, &[usize])) {
+    let springs = springs(&[row; 5].join("?"));
+    let blocks = blocks.repeat(5);
+    bencher.bench(|| count_arrangements(divan::black_box(&springs), &blocks).unwrap());
+}
+
+#[divan::bench(args = [1, 5, 15])]
+fn all_unknown(bencher: Bencher, blocks: usize) {
+    let springs = vec![SpringStatus::Unknown; MAX_SPRINGS];
+    let blocks = vec![3; blocks];
+    bencher.bench(|| count_arrangements(divan::black_box(&springs), &blocks).unwrap());
+}
@@ -0,0 +1,42 @@
+//! The longest-path search of day 23 on its own, without parsing the grid and contracting
+//! it to a graph. The graphs get built from `$AOC_INPUT_DIR/day23.txt` (or
+//! `input/day23.txt`) and round-tripped through their serialized form before being
+//! searched, so e.g. `cargo bench --bench day23 -- longest_path` profiles just the search.
+
+use std::{env, fs, path::PathBuf};
+
+use aoc2023::{cancel::Token, day23::Graph};
+use divan::Bencher;
+
+fn main() {
+    divan::main();
+}
+
+fn input() -> String {
+    let dir = env::var_os("AOC_INPUT_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map_or_else(|| PathBuf::from("input"), PathBuf::from);
+    let path = dir.join("day23.txt");
+    fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("failed to read {}: {}", path.display(), e))
+        .replace("\r\n", "\n")
+}
+
+fn serialized(climb_slopes: bool) -> Vec<u8> {
+    Graph::new(&input(), climb_slopes)
+        .expect("invalid day 23 input")
+        .to_bytes()
+}
+
+#[divan::bench(args = [false, true])]
+fn load(bencher: Bencher, climb_slopes: bool) {
+    let bytes = serialized(climb_slopes);
+    bencher.bench(|| Graph::from_bytes(divan::black_box(&bytes)).unwrap());
+}
+
+#[divan::bench(args = [false, true], sample_count = 10)]
+fn longest_path(bencher: Bencher, climb_slopes: bool) {
+    let graph = Graph::from_bytes(&serialized(climb_slopes)).unwrap();
+    let cancel = Token::default();
+    bencher.bench(|| divan::black_box(&graph).longest_path(&cancel).unwrap());
+}
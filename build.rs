@@ -0,0 +1,42 @@
+//! Generates `include/aoc2023.h` from the `ffi` module when the `ffi` feature is on. The
+//! header is checked in, so that it can be used without building the crate.
+//!
+//! Also records the git revision the crate gets built from in `AOC2023_GIT_REVISION`,
+//! for the runner's `run-all --summary`. Builds outside of a git checkout leave it unset.
+
+use std::{path::Path, process::Command};
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    let stdout = String::from_utf8(output.stdout).ok()?;
+    output.status.success().then(|| stdout.trim().to_string())
+}
+
+fn main() {
+    #[cfg(feature = "ffi")]
+    {
+        println!("cargo:rerun-if-changed=src/ffi.rs");
+        cbindgen::Builder::new()
+            .with_src("src/ffi.rs")
+            .with_language(cbindgen::Language::C)
+            .with_include_guard("AOC2023_H")
+            .with_documentation(true)
+            .generate()
+            .expect("failed to generate the C header")
+            .write_to_file("include/aoc2023.h");
+    }
+
+    if let Some(git_dir) = git(&["rev-parse", "--git-dir"]) {
+        // Committing or checking something out changes one of these. Paths that don't
+        // exist would make every build rerun this script, so they're left out.
+        for file in ["HEAD", "refs", "packed-refs"] {
+            let path = Path::new(&git_dir).join(file);
+            if path.exists() {
+                println!("cargo:rerun-if-changed={}", path.display());
+            }
+        }
+    }
+    if let Some(revision) = git(&["rev-parse", "HEAD"]) {
+        println!("cargo:rustc-env=AOC2023_GIT_REVISION={}", revision);
+    }
+}
@@ -0,0 +1,16 @@
+//! Day 10 on arbitrary grids. Parsing and both parts may fail on them, but never panic or
+//! loop forever, however the pipes next to the loop connect.
+
+#![no_main]
+
+use aoc2023::{cancel::Token, Day10, Puzzle};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &[u8]| {
+    let Ok(grid) = Day10::parse(input) else {
+        return;
+    };
+    let cancel = Token::default();
+    let _ = Day10::solve1(&grid, &cancel);
+    let _ = Day10::solve2(&grid, &cancel);
+});
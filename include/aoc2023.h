@@ -0,0 +1,47 @@
+#ifndef AOC2023_H
+#define AOC2023_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * The part was solved, and `*out` points at the answer.
+ */
+#define AOC2023_OK 0
+
+/**
+ * The part couldn't be solved (e.g. because the input is malformed), and `*out` points at
+ * the error message.
+ */
+#define AOC2023_FAILED 1
+
+/**
+ * The arguments were invalid (a null pointer, or a day or part that doesn't exist), and
+ * `*out` is left alone.
+ */
+#define AOC2023_INVALID 2
+
+/**
+ * Solves `part` of `day` for `input`, a NUL-terminated UTF-8 string. Returns one of the
+ * `AOC2023_*` codes. Unless the arguments were invalid, `*out` gets pointed at a string
+ * that the caller owns and must free with `aoc2023_free`.
+ *
+ * # Safety
+ *
+ * `input` must be null or point at a NUL-terminated string, and `out` must be null or
+ * point at memory a `char *` can be written to.
+ */
+int aoc2023_solve(unsigned int day, unsigned int part, const char *input, char **out);
+
+/**
+ * Frees a string returned by `aoc2023_solve`. Does nothing for null.
+ *
+ * # Safety
+ *
+ * `s` must be null or a string returned by `aoc2023_solve` that hasn't been freed yet.
+ */
+void aoc2023_free(char *s);
+
+#endif  /* AOC2023_H */
@@ -0,0 +1,26 @@
+//! Pinning the runner to a CPU core for `--pin-core`, which makes benchmark timings less
+//! noisy.
+
+/// Pins the calling thread to `core`. Threads it spawns afterwards inherit this.
+#[cfg(target_os = "linux")]
+pub fn pin_to_core(core: usize) -> anyhow::Result<()> {
+    use std::{io, mem};
+
+    // `CPU_SET` panics for cores that don't fit into the set.
+    if core >= libc::CPU_SETSIZE as usize {
+        anyhow::bail!("there is no core {}", core);
+    }
+    let mut set = unsafe { mem::zeroed::<libc::cpu_set_t>() };
+    unsafe { libc::CPU_SET(core, &mut set) };
+    // A pid of 0 means the calling thread.
+    if unsafe { libc::sched_setaffinity(0, mem::size_of_val(&set), &set) } != 0 {
+        let e = io::Error::last_os_error();
+        anyhow::bail!("couldn't pin the runner to core {}: {}", core, e);
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn pin_to_core(_core: usize) -> anyhow::Result<()> {
+    anyhow::bail!("pinning to a core is only supported on Linux")
+}
@@ -0,0 +1,127 @@
+//! What the solvers return.
+
+use alloc::string::{String, ToString};
+use core::fmt;
+
+use num_bigint::{BigInt, BigUint};
+
+/// What a part returns. Most answers are numbers, which get kept as such so that they can
+/// be compared numerically.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    U64(u64),
+    I64(i64),
+    /// A number that fits neither of the others, from the wide variants of the days that
+    /// can overflow on large inputs.
+    Big(BigInt),
+    Text(String),
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::U64(n) => n.fmt(f),
+            Answer::I64(n) => n.fmt(f),
+            Answer::Big(n) => n.fmt(f),
+            Answer::Text(s) => s.fmt(f),
+        }
+    }
+}
+
+macro_rules! impl_from_int {
+    ($variant:ident($target:ty): $($int:ty),*) => {
+        $(
+            impl From<$int> for Answer {
+                /// Panics if the number doesn't fit into the variant's type.
+                fn from(n: $int) -> Self {
+                    let n = <$target>::try_from(n).expect("answer out of range");
+                    Answer::$variant(n)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_int!(U64(u64): u8, u16, u32, u64, usize);
+impl_from_int!(I64(i64): i8, i16, i32, i64, isize);
+
+impl From<u128> for Answer {
+    /// Numbers that don't fit into a `u64` become [`Answer::Big`].
+    fn from(n: u128) -> Self {
+        match u64::try_from(n) {
+            Ok(n) => Answer::U64(n),
+            Err(_) => Answer::Big(n.into()),
+        }
+    }
+}
+
+impl From<i128> for Answer {
+    /// Numbers that don't fit into an `i64` go through [`BigInt`], so they're the same
+    /// answer as the wide variants would give for them.
+    fn from(n: i128) -> Self {
+        match i64::try_from(n) {
+            Ok(n) => Answer::I64(n),
+            Err(_) => BigInt::from(n).into(),
+        }
+    }
+}
+
+impl From<BigInt> for Answer {
+    /// Keeps numbers that fit into a `u64` or `i64` as such, so that they compare equal to
+    /// the same answers from other solvers.
+    fn from(n: BigInt) -> Self {
+        if let Ok(n) = u64::try_from(&n) {
+            Answer::U64(n)
+        } else if let Ok(n) = i64::try_from(&n) {
+            Answer::I64(n)
+        } else {
+            Answer::Big(n)
+        }
+    }
+}
+
+impl From<BigUint> for Answer {
+    fn from(n: BigUint) -> Self {
+        BigInt::from(n).into()
+    }
+}
+
+impl From<String> for Answer {
+    fn from(s: String) -> Self {
+        Answer::Text(s)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(s: &str) -> Self {
+        Answer::Text(s.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wide_ints() {
+        assert_eq!(Answer::from(u64::MAX as u128), Answer::U64(u64::MAX));
+        assert_eq!(
+            Answer::from(u128::MAX),
+            Answer::Big(BigInt::from(u128::MAX))
+        );
+        assert_eq!(Answer::from(i64::MIN as i128), Answer::I64(i64::MIN));
+        assert_eq!(
+            Answer::from(i128::MIN),
+            Answer::Big(BigInt::from(i128::MIN))
+        );
+        assert_eq!(
+            Answer::from(-(u64::MAX as i128) - 1),
+            Answer::Big(BigInt::from(-(u64::MAX as i128) - 1))
+        );
+        // Too large for an `i64` but not for a `u64`, like a wide variant's answer.
+        let n = i64::MAX as i128 + 1;
+        assert_eq!(Answer::from(n), Answer::U64(n as u64));
+        assert_eq!(Answer::from(n), Answer::from(BigInt::from(n)));
+        assert_eq!(Answer::from(u128::MAX).to_string(), u128::MAX.to_string());
+    }
+}
@@ -0,0 +1,59 @@
+//! Cooperative cancellation of running solvers. Every solver gets a [`Token`] from its
+//! caller, and the long loops of the slow days call [`Token::check`] and return the
+//! [`Cancelled`] error once the token has been cancelled. This way a timed out run
+//! actually stops instead of being left running in the background. The other days finish
+//! quickly enough that they ignore their token.
+
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+#[derive(Default)]
+struct Node {
+    cancelled: AtomicBool,
+    parent: Option<Arc<Node>>,
+}
+
+/// The error of a solver that stopped because its token was cancelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("cancelled")]
+pub struct Cancelled;
+
+/// A cancellation flag shared between the runner and a running solver. A token is also
+/// cancelled once its parent is. A token made with `Token::default()` only gets cancelled
+/// if someone holding a clone of it cancels it.
+#[derive(Clone, Default)]
+pub struct Token(Arc<Node>);
+
+impl Token {
+    /// A token that gets cancelled either on its own or together with `self`.
+    pub fn child(&self) -> Token {
+        Token(Arc::new(Node {
+            cancelled: AtomicBool::new(false),
+            parent: Some(self.0.clone()),
+        }))
+    }
+
+    pub fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        let mut node = Some(&self.0);
+        while let Some(n) = node {
+            if n.cancelled.load(Ordering::Relaxed) {
+                return true;
+            }
+            node = n.parent.as_ref();
+        }
+        false
+    }
+
+    /// Fails with [`Cancelled`] if the token was cancelled. This is cheap enough to call
+    /// once per iteration of an outer loop, but not in the innermost ones.
+    pub fn check(&self) -> Result<(), Cancelled> {
+        match self.is_cancelled() {
+            true => Err(Cancelled),
+            false => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! Talking to the Advent of Code website. Every request goes through [`Client`], which
+//! identifies the runner in its User-Agent, leaves some time between requests, and caches
+//! what it downloads on disk, as the site asks of tools that automate it.
+
+use std::{
+    env, fs,
+    path::PathBuf,
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::Context;
+
+use crate::{paths::StateFile, provenance};
+
+/// The environment variable with the session cookie, which takes precedence over the
+/// config file's `session`.
+pub const SESSION_VAR: &str = "AOC_SESSION";
+
+const BASE_URL: &str = "https://adventofcode.com/2023";
+const USER_AGENT: &str = concat!(
+    "aoc2023/",
+    env!("CARGO_PKG_VERSION"),
+    " (https://github.com/Sp00ph/aoc2023)"
+);
+/// The least time between two requests, even from different runs of the runner.
+const MIN_INTERVAL: Duration = Duration::from_secs(2);
+/// The largest response read, far more than any input.
+const MAX_BODY: u64 = 4 << 20;
+
+/// The session cookie to log in with, from `$AOC_SESSION` or else `config`.
+pub fn session(config: Option<String>) -> anyhow::Result<String> {
+    env::var(SESSION_VAR)
+        .ok()
+        .filter(|s| !s.is_empty())
+        .or(config)
+        .context(
+            "No session cookie. Set $AOC_SESSION or `session` in the config file to the value \
+             of the `session` cookie of adventofcode.com.",
+        )
+}
+
+pub struct Client {
+    agent: ureq::Agent,
+    session: String,
+    /// Where this session's responses get cached. Inputs differ between users, so every
+    /// session gets its own directory.
+    cache_dir: PathBuf,
+}
+
+impl Client {
+    pub fn new(session: String) -> Self {
+        let agent = ureq::Agent::config_builder()
+            .user_agent(USER_AGENT)
+            .timeout_global(Some(Duration::from_secs(30)))
+            .build()
+            .into();
+        let cache_dir = StateFile::Cache
+            .path()
+            .join(&provenance::hash(&session)[..12]);
+        Self {
+            agent,
+            session,
+            cache_dir,
+        }
+    }
+
+    /// The file whose modification time is when the last request was made.
+    fn last_request_file() -> PathBuf {
+        StateFile::Cache.path().join("last-request")
+    }
+
+    /// Sleeps until `MIN_INTERVAL` has passed since the last request, and records that
+    /// the next one is being made now.
+    fn wait_turn(&self) -> anyhow::Result<()> {
+        let file = Self::last_request_file();
+        let last = fs::metadata(&file).and_then(|m| m.modified()).ok();
+        if let Some(elapsed) = last.and_then(|last| SystemTime::now().duration_since(last).ok()) {
+            thread::sleep(MIN_INTERVAL.saturating_sub(elapsed));
+        }
+        fs::create_dir_all(StateFile::Cache.path())?;
+        fs::write(&file, "").with_context(|| format!("couldn't write {}", file.display()))
+    }
+
+    /// Downloads `path` below the 2023 event's URL, or reads it from the cache if it was
+    /// downloaded before. Only successful responses with a body get cached.
+    fn get(&self, path: &str) -> anyhow::Result<String> {
+        let cached = self
+            .cache_dir
+            .join(path.trim_matches('/').replace('/', "-"));
+        if let Ok(body) = fs::read_to_string(&cached) {
+            return Ok(body);
+        }
+        self.wait_turn()?;
+        let url = format!("{}{}", BASE_URL, path);
+        let response = self
+            .agent
+            .get(&url)
+            .header("Cookie", format!("session={}", self.session))
+            .call();
+        let mut response = match response {
+            Ok(response) => response,
+            // The site answers with a 400 to a missing or expired session, and with a 500
+            // to one that isn't a session at all.
+            Err(ureq::Error::StatusCode(400 | 500)) => {
+                anyhow::bail!("the session cookie was rejected, it might have expired")
+            }
+            Err(ureq::Error::StatusCode(404)) => anyhow::bail!("{} doesn't exist yet", url),
+            Err(e) => return Err(e).with_context(|| format!("couldn't download {}", url)),
+        };
+        let body = response
+            .body_mut()
+            .with_config()
+            .limit(MAX_BODY)
+            .read_to_string()
+            .with_context(|| format!("couldn't download {}", url))?;
+        if !body.trim().is_empty() {
+            fs::create_dir_all(&self.cache_dir)?;
+            fs::write(&cached, &body)
+                .with_context(|| format!("couldn't write {}", cached.display()))?;
+        }
+        Ok(body)
+    }
+
+    /// The logged in user's input of `day`.
+    pub fn input(&self, day: usize) -> anyhow::Result<String> {
+        self.get(&format!("/day/{}/input", day))
+    }
+}
@@ -0,0 +1,10 @@
+//! Putting answers on the system clipboard for `--copy`, to paste them into the answer box
+//! on the Advent of Code website.
+
+/// Replaces the clipboard's contents with `text`. On Linux the clipboard belongs to the
+/// process that set it, so the text only outlives the runner if a clipboard manager takes
+/// it over, which most desktops have.
+pub fn copy(text: &str) -> anyhow::Result<()> {
+    arboard::Clipboard::new()?.set_text(text)?;
+    Ok(())
+}
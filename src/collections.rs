@@ -0,0 +1,40 @@
+//! The hash maps and sets the days use. They come from `hashbrown` rather than `std`, so
+//! that the days don't need the standard library, and hash with `ahash`.
+//!
+//! Normally every map gets its own random keys, so iteration orders (and with them some
+//! timings) change from run to run. With the `deterministic-hash` feature, every map uses
+//! the same fixed keys instead, which makes runs reproducible for debugging.
+
+#[cfg(feature = "deterministic-hash")]
+use core::hash::BuildHasher;
+
+/// The hasher of [`HashMap`] and [`HashSet`].
+#[cfg(not(feature = "deterministic-hash"))]
+pub type HashState = ahash::RandomState;
+/// The hasher of [`HashMap`] and [`HashSet`].
+#[cfg(feature = "deterministic-hash")]
+pub type HashState = FixedState;
+
+pub type HashMap<K, V> = hashbrown::HashMap<K, V, HashState>;
+pub type HashSet<T> = hashbrown::HashSet<T, HashState>;
+
+/// Builds `ahash` hashers that all use the same keys.
+#[cfg(feature = "deterministic-hash")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FixedState;
+
+#[cfg(feature = "deterministic-hash")]
+impl BuildHasher for FixedState {
+    type Hasher = ahash::AHasher;
+
+    fn build_hasher(&self) -> ahash::AHasher {
+        // Any keys do, as long as they're always the same. These are digits of pi.
+        const STATE: ahash::RandomState = ahash::RandomState::with_seeds(
+            0x243f_6a88_85a3_08d3,
+            0x1319_8a2e_0370_7344,
+            0xa409_3822_299f_31d0,
+            0x082e_fa98_ec4e_6c89,
+        );
+        STATE.build_hasher()
+    }
+}
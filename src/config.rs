@@ -0,0 +1,41 @@
+use std::{fs, io, path::PathBuf};
+
+use anyhow::Context;
+use serde::Deserialize;
+
+use crate::paths;
+
+/// Defaults for the command line options, from `~/.config/aoc2023/config.toml` and
+/// `./aoc.toml`. Values in `./aoc.toml` take precedence, and flags take precedence over both.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    pub input_dir: Option<PathBuf>,
+    pub show_time: Option<bool>,
+    /// Number of threads for the days that solve in parallel, instead of one per core.
+    pub threads: Option<usize>,
+    /// The `session` cookie of adventofcode.com, for downloading inputs.
+    pub session: Option<String>,
+}
+
+impl Config {
+    pub fn load() -> anyhow::Result<Self> {
+        let mut config = Config::default();
+        for file in paths::config_files() {
+            let toml = match fs::read_to_string(&file) {
+                Ok(toml) => toml,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e).context(format!("Couldn't read {}", file.display())),
+            };
+            let overrides: Config = toml::from_str(&toml)
+                .with_context(|| format!("Invalid config in {}", file.display()))?;
+            config = Config {
+                input_dir: overrides.input_dir.or(config.input_dir),
+                show_time: overrides.show_time.or(config.show_time),
+                threads: overrides.threads.or(config.threads),
+                session: overrides.session.or(config.session),
+            };
+        }
+        Ok(config)
+    }
+}
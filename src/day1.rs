@@ -1,112 +1,540 @@
-pub fn part1(input: &str) -> String {
-    input
-        .trim()
-        .lines()
-        .map(|line| -> u32 {
-            let first_digit = line
-                .bytes()
-                .find(|c| c.is_ascii_digit())
-                .map(|c| (c - b'0') as u32)
-                .expect("First digit not found");
-            let last_digit = line
-                .bytes()
-                .rev()
-                .find(|c| c.is_ascii_digit())
-                .map(|c| (c - b'0') as u32)
-                .expect("Last digit not found");
-            first_digit * 10 + last_digit
-        })
-        .sum::<u32>()
-        .to_string()
-}
-
-fn first_num(b: &[u8]) -> u32 {
-    let mut it = b.iter();
-    while let Some(&b) = it.next() {
-        let s = it.as_slice();
-        match b {
-            b'1'..=b'9' => return (b - b'0') as u32,
-            b'o' if s.starts_with(b"ne") => return 1,
-            b't' => {
-                if s.starts_with(b"wo") {
-                    return 2;
-                } else if s.starts_with(b"hree") {
-                    return 3;
-                }
-            }
-            b'f' => {
-                if s.starts_with(b"our") {
-                    return 4;
-                } else if s.starts_with(b"ive") {
-                    return 5;
-                }
-            }
-            b's' => {
-                if s.starts_with(b"ix") {
-                    return 6;
-                } else if s.starts_with(b"even") {
-                    return 7;
-                }
-            }
-            b'e' if s.starts_with(b"ight") => {
-                return 8;
-            }
-            b'n' if s.starts_with(b"ine") => {
-                return 9;
-            }
-            _ => {}
+use alloc::{format, string::String};
+
+#[cfg(feature = "std")]
+use crate::stream::Lines;
+use crate::{cancel::Token, lex, parse::ParseError, text::Patterns, Answer, Day1, Puzzle};
+
+/// How spelled out digits that share letters, like the "oneight" in "twoneight", are read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overlap {
+    /// Every spelled out digit counts, even if it shares letters with another one, so
+    /// "oneight" contains a 1 and an 8. This is what the puzzle expects.
+    Allowed,
+    /// The line gets read from left to right, and letters that are part of a digit can't
+    /// start another one, so "oneight" only contains a 1.
+    Forbidden,
+}
+
+/// How calibration values get read from the lines of the input.
+#[derive(Debug, Clone, Copy)]
+pub struct Options<'a> {
+    /// The spelled out digits that count along with the numeric ones, and their values.
+    /// Part 1 doesn't have any, part 2 has [`ENGLISH`]. Empty words are ignored.
+    pub words: &'a [(&'a str, u32)],
+    pub overlap: Overlap,
+    /// Return an error for lines without any digits, instead of skipping them.
+    pub strict: bool,
+}
+
+/// The spelled out digits of the puzzle.
+pub static ENGLISH: [(&str, u32); 9] = [
+    ("one", 1),
+    ("two", 2),
+    ("three", 3),
+    ("four", 4),
+    ("five", 5),
+    ("six", 6),
+    ("seven", 7),
+    ("eight", 8),
+    ("nine", 9),
+];
+
+const PART1: Options<'static> = Options {
+    words: &[],
+    overlap: Overlap::Allowed,
+    strict: true,
+};
+
+const PART2: Options<'static> = Options {
+    words: &ENGLISH,
+    ..PART1
+};
+
+// The words of `ENGLISH`, for scanning for them with SIMD. The scan only gets used for
+// `ENGLISH` itself, as other words would need masks of their own, and only on x86_64.
+// Elsewhere, `ENGLISH` gets found with the trie like any other words.
+#[cfg(target_arch = "x86_64")]
+const WORDS: [&[u8]; 9] = [
+    b"one", b"two", b"three", b"four", b"five", b"six", b"seven", b"eight", b"nine",
+];
+
+// How many bytes of a line get compared at once.
+const CHUNK: usize = 16;
+
+// How far past the end of a chunk a spelled out digit that starts in it can reach.
+#[cfg(target_arch = "x86_64")]
+const OVERHANG: usize = 4;
+
+// A bitmask of the digits from `low` to 9 among the first `CHUNK` bytes of `window`, with
+// the first byte as the lowest bit.
+#[cfg(target_arch = "x86_64")]
+#[inline(always)]
+fn digit_mask(window: &[u8], low: u8) -> u16 {
+    use core::arch::x86_64::*;
+    let window = &window[..CHUNK];
+    // SSE2 is part of x86_64, so it doesn't have to be detected at runtime.
+    unsafe {
+        let bytes = _mm_loadu_si128(window.as_ptr().cast());
+        // Bytes below `low` wrap around to large values, so a single unsigned comparison
+        // checks both ends of the range.
+        let offsets = _mm_sub_epi8(bytes, _mm_set1_epi8(low as i8));
+        let max = _mm_set1_epi8((b'9' - low) as i8);
+        let digits = _mm_cmpeq_epi8(_mm_min_epu8(offsets, max), offsets);
+        _mm_movemask_epi8(digits) as u16
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+#[inline(always)]
+fn digit_mask(window: &[u8], low: u8) -> u16 {
+    let mut mask = 0;
+    for (i, b) in window[..CHUNK].iter().enumerate() {
+        mask |= ((low..=b'9').contains(b) as u16) << i;
+    }
+    mask
+}
+
+// For each of `WORDS`, a bitmask of where it starts among the first `CHUNK` bytes of
+// `window`, which has to have `OVERHANG` more bytes after those. With `ends`, it's where
+// the words end among the last `CHUNK` bytes, after `OVERHANG` others.
+#[cfg(target_arch = "x86_64")]
+#[inline(always)]
+fn word_masks(window: &[u8], ends: bool) -> [u16; 9] {
+    use core::arch::x86_64::*;
+
+    // `shifted[k]` holds the bytes `k` places after those of the chunk (or before them,
+    // with `ends`), so a word starts (or ends) wherever its `k`th letter (from the end) is
+    // in `shifted[k]` for every `k`.
+    #[inline(always)]
+    unsafe fn mask(shifted: &[__m128i; OVERHANG + 1], word: &[u8], ends: bool) -> u16 {
+        let mut found = _mm_set1_epi8(-1);
+        for k in 0..word.len() {
+            let letter = if ends {
+                word[word.len() - 1 - k]
+            } else {
+                word[k]
+            };
+            let matches = _mm_cmpeq_epi8(shifted[k], _mm_set1_epi8(letter as i8));
+            found = _mm_and_si128(found, matches);
         }
+        _mm_movemask_epi8(found) as u16
     }
-    panic!("First digit not found");
-}
-
-fn last_num(b: &[u8]) -> u32 {
-    let mut it = b.iter();
-    while let Some(&b) = it.next_back() {
-        let s = it.as_slice();
-        match b {
-            b'1'..=b'9' => return (b - b'0') as u32,
-            b'e' => {
-                if s.ends_with(b"on") {
-                    return 1;
-                } else if s.ends_with(b"thre") {
-                    return 3;
-                } else if s.ends_with(b"fiv") {
-                    return 5;
-                } else if s.ends_with(b"nin") {
-                    return 9;
-                }
-            }
-            b'o' if s.ends_with(b"tw") => {
-                return 2;
+
+    let window = &window[..CHUNK + OVERHANG];
+    unsafe {
+        let shifted: [__m128i; OVERHANG + 1] = core::array::from_fn(|k| {
+            let offset = if ends { OVERHANG - k } else { k };
+            _mm_loadu_si128(window[offset..].as_ptr().cast())
+        });
+        // Spelled out for every word, so that the compiler unrolls the loop over its
+        // letters.
+        [
+            mask(&shifted, WORDS[0], ends),
+            mask(&shifted, WORDS[1], ends),
+            mask(&shifted, WORDS[2], ends),
+            mask(&shifted, WORDS[3], ends),
+            mask(&shifted, WORDS[4], ends),
+            mask(&shifted, WORDS[5], ends),
+            mask(&shifted, WORDS[6], ends),
+            mask(&shifted, WORDS[7], ends),
+            mask(&shifted, WORDS[8], ends),
+        ]
+    }
+}
+
+fn digit(c: &u8) -> Option<u32> {
+    c.is_ascii_digit().then(|| (c - b'0') as u32)
+}
+
+// The bytes after the last whole chunk (all of them, for lines shorter than a chunk) get
+// looked at one by one, which is faster than padding them into a chunk.
+fn first_digit(b: &[u8]) -> Option<u32> {
+    let mut chunks = b.chunks_exact(CHUNK);
+    for chunk in chunks.by_ref() {
+        let mask = digit_mask(chunk, b'0');
+        if mask != 0 {
+            return digit(&chunk[mask.trailing_zeros() as usize]);
+        }
+    }
+    chunks.remainder().iter().find_map(digit)
+}
+
+fn last_digit(b: &[u8]) -> Option<u32> {
+    let mut chunks = b.rchunks_exact(CHUNK);
+    for chunk in chunks.by_ref() {
+        let mask = digit_mask(chunk, b'0');
+        if mask != 0 {
+            return digit(&chunk[CHUNK - 1 - mask.leading_zeros() as usize]);
+        }
+    }
+    chunks.remainder().iter().rev().find_map(digit)
+}
+
+// The digit at bit `i` of the chunk that starts at `b[start]`, which is spelled out if
+// it's in one of `words`. When scanning from the back, the first chunk of a short line
+// starts before the line, so `start` wraps around, but then the bit is one of the line's.
+#[cfg(target_arch = "x86_64")]
+fn num_at(b: &[u8], start: usize, words: [u16; 9], i: u32) -> u32 {
+    match words.iter().position(|mask| mask >> i & 1 != 0) {
+        Some(word) => word as u32 + 1,
+        None => (b[start.wrapping_add(i as usize)] - b'0') as u32,
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn first_num(b: &[u8]) -> Option<u32> {
+    let mut padded = [0; CHUNK + OVERHANG];
+    for start in (0..b.len()).step_by(CHUNK) {
+        let window = match b.get(start..start + CHUNK + OVERHANG) {
+            Some(window) => window,
+            None => {
+                // Zeros are neither digits nor letters, so they don't match anything.
+                padded.fill(0);
+                padded[..b.len() - start].copy_from_slice(&b[start..]);
+                &padded
             }
-            b'r' if s.ends_with(b"fou") => {
-                return 4;
+        };
+        let words = word_masks(window, false);
+        let starts = words
+            .iter()
+            .fold(digit_mask(window, b'1'), |all, mask| all | mask);
+        if starts != 0 {
+            return Some(num_at(b, start, words, starts.trailing_zeros()));
+        }
+    }
+    None
+}
+
+#[cfg(target_arch = "x86_64")]
+fn last_num(b: &[u8]) -> Option<u32> {
+    let mut padded = [0; CHUNK + OVERHANG];
+    let mut end = b.len();
+    while end > 0 {
+        let window = match end.checked_sub(CHUNK + OVERHANG) {
+            Some(start) => &b[start..end],
+            None => {
+                padded.fill(0);
+                padded[CHUNK + OVERHANG - end..].copy_from_slice(&b[..end]);
+                &padded
             }
-            b'x' if s.ends_with(b"six") => {
-                return 6;
+        };
+        let words = word_masks(window, true);
+        let ends = words
+            .iter()
+            .fold(digit_mask(&window[OVERHANG..], b'1'), |all, mask| {
+                all | mask
+            });
+        if ends != 0 {
+            let start = end.wrapping_sub(CHUNK);
+            return Some(num_at(b, start, words, 15 - ends.leading_zeros()));
+        }
+        end = end.saturating_sub(CHUNK);
+    }
+    None
+}
+
+// How a `Reader` finds the digits of a line.
+enum Scan {
+    // Only numeric digits, with SIMD.
+    Digits,
+    // The numeric digits and `ENGLISH` with overlaps, with SIMD.
+    #[cfg(target_arch = "x86_64")]
+    English,
+    // The numeric digits followed by the words, in a trie.
+    Words(Patterns<u32>),
+}
+
+// Reads the calibration values of lines with a set of options, putting the words into
+// a trie once rather than for every line.
+struct Reader<'a> {
+    options: &'a Options<'a>,
+    scan: Scan,
+}
+
+impl<'a> Reader<'a> {
+    fn new(options: &'a Options<'a>) -> Self {
+        let words = options.words;
+        let scan = match options.overlap {
+            _ if words.is_empty() => Scan::Digits,
+            // Only the address gets compared, which is enough for `PART2` and costs nothing.
+            #[cfg(target_arch = "x86_64")]
+            Overlap::Allowed if core::ptr::eq(words, &ENGLISH[..]) => Scan::English,
+            _ => {
+                let digits = b"123456789".chunks(1).zip(1..);
+                let words = words.iter().map(|&(word, n)| (word.as_bytes(), n));
+                Scan::Words(Patterns::new(digits.chain(words)))
             }
-            b'n' if s.ends_with(b"seve") => {
-                return 7;
+        };
+        Reader { options, scan }
+    }
+
+    fn value(&self, b: &[u8]) -> Option<u32> {
+        let (first, last) = match (&self.scan, self.options.overlap) {
+            (Scan::Digits, _) => (first_digit(b)?, last_digit(b)?),
+            #[cfg(target_arch = "x86_64")]
+            (Scan::English, _) => (first_num(b)?, last_num(b)?),
+            (Scan::Words(nums), Overlap::Allowed) => (*nums.find(b)?.1, *nums.rfind(b)?.1),
+            // The line gets read from the left, continuing after each digit found.
+            (Scan::Words(nums), Overlap::Forbidden) => {
+                let (range, &first) = nums.find(b)?;
+                let (mut rest, mut last) = (&b[range.end..], first);
+                while let Some((range, &n)) = nums.find(rest) {
+                    (rest, last) = (&rest[range.end..], n);
+                }
+                (first, last)
             }
-            b't' if s.ends_with(b"eight") => {
-                return 8;
+        };
+        Some(first * 10 + last)
+    }
+}
+
+/// The first digit of the line times 10 plus its last one, or `None` if the line doesn't
+/// contain any digits. For more than one line, [`calibration_values`] is faster, as it
+/// only prepares the `options` once.
+pub fn calibration_value(b: &[u8], options: &Options) -> Option<u32> {
+    Reader::new(options).value(b)
+}
+
+/// How long an input has to be for [`calibration_sum`] to spread its lines over several
+/// threads with the `parallel` feature. Below this, starting the threads takes longer
+/// than reading the lines.
+#[cfg(feature = "parallel")]
+pub const PARALLEL_MIN_LEN: usize = 1 << 20;
+
+/// The calibration value of each line of `input`, along with the line's index (from 0),
+/// for looking at the lines one by one rather than only at their sum.
+pub fn calibration_values<'a>(
+    input: &'a [u8],
+    options: &'a Options<'a>,
+) -> impl Iterator<Item = (usize, Option<u32>)> + 'a {
+    let reader = Reader::new(options);
+    lex::lines(input.trim_ascii())
+        .map(move |line| reader.value(line))
+        .enumerate()
+}
+
+/// The sum of the calibration values of all lines. With the `parallel` feature, the lines
+/// of inputs of at least [`PARALLEL_MIN_LEN`] bytes get read on all cores.
+pub fn calibration_sum(input: &[u8], options: &Options) -> Result<u64, ParseError> {
+    #[cfg(feature = "parallel")]
+    if input.len() >= PARALLEL_MIN_LEN {
+        use alloc::vec::Vec;
+        use rayon::prelude::*;
+        let reader = Reader::new(options);
+        let lines: Vec<_> = lex::lines(input.trim_ascii()).collect();
+        let values: Vec<_> = lines.par_iter().map(|line| reader.value(line)).collect();
+        return sum(input, values.into_iter().enumerate(), options);
+    }
+    sum(input, calibration_values(input, options), options)
+}
+
+/// The sum of the calibration values of all lines, with `words` counting as digits along
+/// with the numeric ones, like [`ENGLISH`] does in part 2. This is for the variants of the
+/// puzzle with the digits spelled out in other languages, e.g. `&[("eins", 1), ("zwei",
+/// 2), ...]`.
+pub fn spelled_calibration_sum(input: &[u8], words: &[(&str, u32)]) -> Result<u64, ParseError> {
+    calibration_sum(input, &Options { words, ..PART2 })
+}
+
+// The error for a line without any digits, which quotes the line so that it can be found
+// even where the line number alone isn't shown next to the input.
+fn no_digits(line: &[u8]) -> ParseError {
+    let line = String::from_utf8_lossy(line);
+    ParseError::malformed(format!("no digits in the line {:?}", line))
+}
+
+// Adds up the calibration values of the lines of `input`, in order, so that the error is
+// about the first line without digits.
+fn sum(
+    input: &[u8],
+    values: impl Iterator<Item = (usize, Option<u32>)>,
+    options: &Options,
+) -> Result<u64, ParseError> {
+    let mut sum = 0;
+    for (i, value) in values {
+        match value {
+            Some(value) => sum += value as u64,
+            None if options.strict => {
+                // Only the failing line gets looked up again, not every line kept around.
+                let line = lex::lines(input.trim_ascii()).nth(i).unwrap_or_default();
+                return Err(no_digits(line).at_line(i + 1));
             }
-            _ => {}
+            None => {}
         }
     }
-    panic!("First digit not found");
+    Ok(sum)
+}
+
+/// Solves `part` on a streamed input. Unlike [`calibration_sum`], this skips blank lines.
+#[cfg(feature = "std")]
+pub fn stream(part: usize, lines: &mut Lines<'_>) -> anyhow::Result<Answer> {
+    let options = match part {
+        1 => &PART1,
+        2 => &PART2,
+        _ => panic!("there is no part {}", part),
+    };
+    let reader = Reader::new(options);
+    let mut sum = 0;
+    lines.for_each(|line| {
+        match reader.value(line.as_bytes()) {
+            Some(value) => sum += value as u64,
+            None if options.strict => return Err(no_digits(line.as_bytes())),
+            None => {}
+        }
+        Ok(())
+    })?;
+    Ok(sum.into())
 }
 
-pub fn part2(input: &str) -> String {
-    input
-        .trim()
-        .lines()
-        .map(|line| {
-            let first_digit = first_num(line.as_bytes());
-            let last_digit = last_num(line.as_bytes());
-            first_digit * 10 + last_digit
+register_day!(1, Day1, "Trebuchet?!", tags: ["parsing"]);
+
+impl Puzzle for Day1 {
+    // The parts find the digits of a line differently, so there's nothing to parse up front.
+    type Parsed<'a> = &'a [u8];
+    const ALGORITHM: &'static str = "simd-chunk-scan-v1";
+
+    fn parse(input: &[u8]) -> anyhow::Result<&[u8]> {
+        Ok(input)
+    }
+
+    fn solve1(input: &&[u8], _: &Token) -> anyhow::Result<Answer> {
+        Ok(calibration_sum(input, &PART1)?.into())
+    }
+
+    fn solve2(input: &&[u8], _: &Token) -> anyhow::Result<Answer> {
+        Ok(spelled_calibration_sum(input, &ENGLISH)?.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{
+        string::{String, ToString},
+        vec::Vec,
+    };
+
+    use super::*;
+
+    const EXAMPLE: &str = "1abc2\npqr3stu8vwx\na1b2c3d4e5f\ntreb7uchet\ntwo1nine\n\
+                           4nineeightseven2\nzoneight234\n7pqrstsixteen";
+
+    // `ENGLISH` at another address, which gets read with the trie rather than the SIMD scan.
+    fn english() -> Vec<(&'static str, u32)> {
+        ENGLISH.to_vec()
+    }
+
+    #[test]
+    fn example() {
+        let input = EXAMPLE.as_bytes();
+        let cancel = Token::default();
+        assert_eq!(Day1::solve1(&input, &cancel).unwrap(), Answer::from(296u64));
+        // The last line ends in "sixteen", so its last digit is the spelled out 6.
+        assert_eq!(Day1::solve2(&input, &cancel).unwrap(), Answer::from(303u64));
+    }
+
+    #[test]
+    fn overlapping_words() {
+        let words = english();
+        let value = |line: &str, words: &[(&str, u32)], overlap| {
+            let options = Options {
+                words,
+                overlap,
+                ..PART2
+            };
+            calibration_value(line.as_bytes(), &options)
+        };
+        let cases = [
+            // The line, and its value with and without overlaps.
+            ("oneight", 18, 11),
+            ("twone", 21, 22),
+            ("oneightxx5twone", 11, 12),
+            ("twone3oneight", 28, 21),
+            ("3twone", 31, 32),
+            ("oneight7", 17, 17),
+            ("twoneight", 28, 28),
+        ];
+        for (line, allowed, forbidden) in cases {
+            assert_eq!(
+                value(line, &ENGLISH, Overlap::Allowed),
+                Some(allowed),
+                "{line}"
+            );
+            assert_eq!(
+                value(line, &words, Overlap::Allowed),
+                Some(allowed),
+                "{line}"
+            );
+            assert_eq!(
+                value(line, &ENGLISH, Overlap::Forbidden),
+                Some(forbidden),
+                "{line}"
+            );
+        }
+    }
+
+    #[test]
+    fn lines_without_digits() {
+        let input = b"a1b\nno digits here\n2c";
+        assert_eq!(calibration_value(b"no digits here", &PART1), None);
+        assert_eq!(calibration_value(b"nothing", &PART2), None);
+        let err = calibration_sum(input, &PART1).unwrap_err();
+        assert!(err.to_string().contains("no digits here"), "{err}");
+        let lenient = Options {
+            strict: false,
+            ..PART1
+        };
+        assert_eq!(calibration_sum(input, &lenient), Ok(11 + 22));
+        let lenient = Options {
+            strict: false,
+            ..PART2
+        };
+        assert_eq!(calibration_sum(input, &lenient), Ok(11 + 22));
+        assert!(calibration_sum(input, &PART2).is_err());
+    }
+
+    // Lines of every length up to a few chunks, made of digits, the words and bits of
+    // them, so that words and digits end up on both sides of every chunk boundary.
+    fn random_lines() -> impl Iterator<Item = String> {
+        const PIECES: [&str; 17] = [
+            "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "on", "e",
+            "ight", "x", "tw", "0", "5", "9",
+        ];
+        let mut state = 1u64;
+        let mut next = move || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) as usize
+        };
+        (0..5000).map(move |i| {
+            let mut line = String::new();
+            while line.len() < i % 70 {
+                line.push_str(PIECES[next() % PIECES.len()]);
+            }
+            line
         })
-        .sum::<u32>()
-        .to_string()
+    }
+
+    #[test]
+    fn simd_matches_trie() {
+        let words = english();
+        let trie = Options {
+            words: &words,
+            ..PART2
+        };
+        let scalar_digits = |line: &[u8]| {
+            let first = line.iter().find_map(digit)?;
+            let last = line.iter().rev().find_map(digit)?;
+            Some(first * 10 + last)
+        };
+        for line in random_lines() {
+            let b = line.as_bytes();
+            assert_eq!(
+                calibration_value(b, &PART2),
+                calibration_value(b, &trie),
+                "{line}"
+            );
+            assert_eq!(calibration_value(b, &PART1), scalar_digits(b), "{line}");
+        }
+    }
 }
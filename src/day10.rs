@@ -1,3 +1,16 @@
+use alloc::vec::Vec;
+
+use anyhow::Context;
+
+use crate::{
+    cancel::Token,
+    geometry::{Dir, Point},
+    grid,
+    parse::ParseError,
+    viz::{Image, Visualize},
+    Answer, Day10, Puzzle,
+};
+
 #[derive(PartialEq, Eq, Clone, Copy)]
 enum Pipe {
     Start,
@@ -13,19 +26,19 @@ enum Pipe {
 impl Pipe {
     fn connects(&self, dir: Dir) -> bool {
         match dir {
-            Dir::Up => matches!(
+            Dir::North => matches!(
                 self,
                 Pipe::Start | Pipe::TopLeft | Pipe::TopRight | Pipe::Vertical
             ),
-            Dir::Right => matches!(
+            Dir::East => matches!(
                 self,
                 Pipe::Start | Pipe::TopRight | Pipe::BottomRight | Pipe::Horizontal
             ),
-            Dir::Down => matches!(
+            Dir::South => matches!(
                 self,
                 Pipe::Start | Pipe::BottomLeft | Pipe::BottomRight | Pipe::Vertical
             ),
-            Dir::Left => matches!(
+            Dir::West => matches!(
                 self,
                 Pipe::Start | Pipe::TopLeft | Pipe::BottomLeft | Pipe::Horizontal
             ),
@@ -33,181 +46,90 @@ impl Pipe {
     }
 }
 
-struct Grid {
-    data: Vec<Pipe>,
-    start_pos: (u8, u8),
-    width: u8,
-    height: u8,
-}
-
-impl Grid {
-    fn get(&self, x: u8, y: u8) -> Option<Pipe> {
-        if x >= self.width || y >= self.height {
-            None
-        } else {
-            Some(self.data[y as usize * self.width as usize + x as usize])
-        }
-    }
+pub struct Grid {
+    pipes: grid::Grid<Pipe>,
+    start_pos: Point,
 }
-fn parse_input(input: &str) -> Grid {
-    let mut data = Vec::new();
-    let mut width = 0;
-    let mut height = 0;
-    let mut start_pos = None;
-
-    for line in input.trim().lines() {
-        width = line.len() as u8;
-        for (i, c) in line.bytes().enumerate() {
-            data.push(match c {
-                b'S' => {
-                    start_pos = Some((i as u8, height));
-                    Pipe::Start
-                }
-                b'.' => Pipe::Ground,
-                b'-' => Pipe::Horizontal,
-                b'|' => Pipe::Vertical,
-                b'L' => Pipe::TopRight,
-                b'F' => Pipe::BottomRight,
-                b'7' => Pipe::BottomLeft,
-                b'J' => Pipe::TopLeft,
-                _ => panic!("invalid character"),
-            });
-        }
-        height += 1;
-    }
 
-    Grid {
-        data,
-        width,
-        height,
-        start_pos: start_pos.expect("no start position found"),
-    }
-}
-
-#[derive(PartialEq, Eq, Clone, Copy)]
-enum Dir {
-    Up,
-    Right,
-    Down,
-    Left,
-}
-
-impl Dir {
-    fn opposite(&self) -> Dir {
-        match self {
-            Dir::Up => Dir::Down,
-            Dir::Right => Dir::Left,
-            Dir::Down => Dir::Up,
-            Dir::Left => Dir::Right,
-        }
+fn parse_input(input: &[u8]) -> Result<Grid, ParseError> {
+    let pipes = grid::Grid::parse(input.trim_ascii(), |_, c| {
+        Ok(match c {
+            b'S' => Pipe::Start,
+            b'.' => Pipe::Ground,
+            b'-' => Pipe::Horizontal,
+            b'|' => Pipe::Vertical,
+            b'L' => Pipe::TopRight,
+            b'F' => Pipe::BottomRight,
+            b'7' => Pipe::BottomLeft,
+            b'J' => Pipe::TopLeft,
+            _ => return Err(ParseError::invalid("character", c as char)),
+        })
+    })?;
+    let (x, y) = pipes
+        .position(|&pipe| pipe == Pipe::Start)
+        .ok_or_else(|| ParseError::malformed("no start position found"))?;
+    if pipes.rows().flatten().filter(|&&pipe| pipe == Pipe::Start).count() > 1 {
+        return Err(ParseError::malformed("more than one start position"));
     }
+    Ok(Grid {
+        pipes,
+        start_pos: Point::new(x as isize, y as isize),
+    })
 }
 
 #[derive(Clone, Copy)]
 struct Segment {
-    start: (u8, u8),
-    dir: Dir,
-    len: u8,
+    start: Point,
+    len: usize,
 }
 
-impl Segment {
-    fn horizontal(&self) -> bool {
-        matches!(self.dir, Dir::Left | Dir::Right)
-    }
-}
-
-fn loop_segments(grid: &Grid) -> Vec<Segment> {
+// Follows the pipes from the start in direction `dir`, returning the segments of the loop
+// if they lead back to the start. Every pipe is left through the connection it wasn't
+// entered from, so junk pipes next to the loop are never stepped onto, unless they connect
+// to the start itself.
+fn follow_loop(grid: &Grid, mut dir: Dir) -> Option<Vec<Segment>> {
     let mut cur = grid.start_pos;
     let mut segments = vec![];
-
-    fn walk(grid: &Grid, pos: (u8, u8), dir: Dir) -> u8 {
-        let pipe_type = if dir == Dir::Left || dir == Dir::Right {
-            Pipe::Horizontal
-        } else {
-            Pipe::Vertical
-        };
-        let (dx, dy) = match dir {
-            Dir::Up => (0, u8::MAX),
-            Dir::Right => (1, 0),
-            Dir::Down => (0, 1),
-            Dir::Left => (u8::MAX, 0),
-        };
-
-        let mut cur = pos;
-        let mut len = 0;
-
-        loop {
-            let next = (cur.0.wrapping_add(dx), cur.1.wrapping_add(dy));
-            let next_pipe = grid.get(next.0, next.1);
-            match next_pipe {
-                Some(pipe) if pipe == pipe_type => {}
-                Some(pipe) if pipe.connects(dir.opposite()) => return len + 1,
-                _ => return len,
-            }
-            cur = next;
-            len += 1;
-        }
-    }
+    let mut seg = Segment { start: cur, len: 0 };
 
     loop {
-        let pipe = grid.get(cur.0, cur.1).unwrap();
-        if pipe == Pipe::Start && !segments.is_empty() {
-            return segments;
+        cur = dir.step(cur);
+        // Negative coordinates wrap around to ones that are off the grid as well.
+        let pipe = *grid.pipes.get(cur.x as usize, cur.y as usize)?;
+        let came_from = dir.opposite();
+        if !pipe.connects(came_from) {
+            return None;
         }
-        let prev_seg_was_horizontal = segments.last().map_or(false, |seg| seg.horizontal());
-        let prev_seg_was_vertical = segments.last().map_or(false, |seg| !seg.horizontal());
-
-        let mut check_and_walk = |dir: Dir| -> bool {
-            let cond = match dir {
-                Dir::Up | Dir::Down => prev_seg_was_vertical,
-                Dir::Right | Dir::Left => prev_seg_was_horizontal,
-            };
-            if pipe.connects(dir) && !cond {
-                let len = walk(grid, cur, dir);
-                if len != 0 {
-                    segments.push(Segment {
-                        start: cur,
-                        dir,
-                        len,
-                    });
-                    match dir {
-                        Dir::Up => cur.1 -= len,
-                        Dir::Right => cur.0 += len,
-                        Dir::Down => cur.1 += len,
-                        Dir::Left => cur.0 -= len,
-                    }
-                    return true;
-                }
-            }
-            false
-        };
-
-        if check_and_walk(Dir::Up) {
-            continue;
-        }
-        if check_and_walk(Dir::Right) {
-            continue;
-        }
-        if check_and_walk(Dir::Down) {
-            continue;
-        }
-        if check_and_walk(Dir::Left) {
-            continue;
+        seg.len += 1;
+        if pipe == Pipe::Start {
+            segments.push(seg);
+            return Some(segments);
         }
 
-        unreachable!()
+        let next_dir = Dir::ALL
+            .into_iter()
+            .find(|&d| d != came_from && pipe.connects(d))
+            .unwrap();
+        if next_dir != dir {
+            segments.push(seg);
+            seg = Segment { start: cur, len: 0 };
+            dir = next_dir;
+        }
     }
 }
 
-fn loop_len(grid: &Grid) -> usize {
-    let segments = loop_segments(grid);
-    segments.iter().map(|seg| seg.len as usize).sum()
+fn loop_segments(grid: &Grid) -> anyhow::Result<Vec<Segment>> {
+    // The start connects in every direction, and junk pipes next to it might connect back
+    // to it, so not every direction necessarily leads around the loop.
+    Dir::ALL
+        .into_iter()
+        .find_map(|dir| follow_loop(grid, dir))
+        .context("no loop through the start")
 }
 
-pub fn part1(input: &str) -> String {
-    let grid = parse_input(input);
-    let loop_len = loop_len(&grid);
-    (loop_len / 2).to_string()
+fn loop_len(grid: &Grid) -> anyhow::Result<usize> {
+    let segments = loop_segments(grid)?;
+    Ok(segments.iter().map(|seg| seg.len).sum())
 }
 
 // calculate the area using the shoelace formula and Pick's theorem
@@ -219,22 +141,173 @@ fn area(segs: &[Segment]) -> usize {
         let seg = segs[i];
         let next_seg = segs[(i + 1) % segs.len()];
         
-        let (x_i, y_i) = seg.start;
-        let (x_j, y_j) = next_seg.start;
+        let Point { x: x_i, y: y_i } = seg.start;
+        let Point { x: x_j, y: y_j } = next_seg.start;
         
-        perimeter += seg.len as usize;
+        perimeter += seg.len;
 
-        area += x_i as isize * y_j as isize - x_j as isize * y_i as isize;
+        area += x_i * y_j - x_j * y_i;
     }
 
     // Pick's theorem: i + b = A + b/2 + 1
     // => i = A - b/2 + 1
-    (area.unsigned_abs() - perimeter) / 2 + 1
+    // (2A - b can be -2 if the loop doesn't enclose anything)
+    (area.unsigned_abs() + 2 - perimeter) / 2
 }
 
-pub fn part2(input: &str) -> String {
-    let grid = parse_input(input);
-    let segments = loop_segments(&grid);
+/// Draws the loop in white with the start in red, the tiles it encloses in green, and the
+/// pipes that aren't part of it in gray.
+impl Visualize for Day10 {
+    fn visualize(input: &[u8]) -> anyhow::Result<Image> {
+        let grid = parse_input(input)?;
+        let segments = loop_segments(&grid)?;
+        let (width, height) = (grid.pipes.width(), grid.pipes.height());
+        let at = |p: Point| (p.x as usize, p.y as usize);
 
-    area(&segments).to_string()
+        // Which tiles are part of the loop, and which of those connect to the north.
+        let mut on_loop = grid::Grid::new(width, height, false);
+        let mut connects_north = grid::Grid::new(width, height, false);
+        for (i, seg) in segments.iter().enumerate() {
+            let end = segments[(i + 1) % segments.len()].start;
+            let delta = Point::new((end.x - seg.start.x).signum(), (end.y - seg.start.y).signum());
+            let dir = Dir::ALL.into_iter().find(|d| d.delta() == delta).unwrap();
+            let mut cur = seg.start;
+            for _ in 0..seg.len {
+                on_loop[at(cur)] = true;
+                let next = dir.step(cur);
+                match dir {
+                    Dir::North => connects_north[at(cur)] = true,
+                    Dir::South => connects_north[at(next)] = true,
+                    _ => {}
+                }
+                cur = next;
+            }
+        }
+
+        let mut image = Image::from_grid(&grid.pipes, |&pipe| match pipe {
+            Pipe::Ground => [0, 0, 0],
+            _ => [96, 96, 96],
+        });
+        // A tile is enclosed if the loop crosses the row an odd number of times to its
+        // left. Counting only the loop tiles connecting to the north counts every crossing
+        // once, and every bend that turns back to where it came from twice.
+        for y in 0..height {
+            let mut inside = false;
+            for x in 0..width {
+                if on_loop[(x, y)] {
+                    let color = if grid.pipes[(x, y)] == Pipe::Start { [255, 0, 0] } else { [255, 255, 255] };
+                    image.set(x, y, color);
+                    inside ^= connects_north[(x, y)];
+                } else if inside {
+                    image.set(x, y, [0, 192, 0]);
+                }
+            }
+        }
+        Ok(image)
+    }
+}
+
+register_day!(10, Day10, "Pipe Maze", tags: ["grid", "geometry"]);
+
+impl Puzzle for Day10 {
+    type Parsed<'a> = Grid;
+    const ALGORITHM: &'static str = "shoelace-pick-v1";
+
+    fn parse(input: &[u8]) -> anyhow::Result<Grid> {
+        Ok(parse_input(input)?)
+    }
+
+    fn solve1(grid: &Grid, _: &Token) -> anyhow::Result<Answer> {
+        let loop_len = loop_len(grid)?;
+        Ok((loop_len / 2).into())
+    }
+
+    fn solve2(grid: &Grid, _: &Token) -> anyhow::Result<Answer> {
+        let segments = loop_segments(grid)?;
+
+        Ok(area(&segments).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+
+    use super::*;
+
+    fn solve(input: &str) -> (Answer, Answer) {
+        let grid = Day10::parse(input.as_bytes()).unwrap();
+        let cancel = Token::default();
+        (
+            Day10::solve1(&grid, &cancel).unwrap(),
+            Day10::solve2(&grid, &cancel).unwrap(),
+        )
+    }
+
+    #[test]
+    fn examples() {
+        let parts = |a: usize, b: usize| (Answer::from(a), Answer::from(b));
+        assert_eq!(solve("-L|F7\n7S-7|\nL|7||\n-L-J|\nL|-JF"), parts(4, 1));
+        assert_eq!(solve("7-F7-\n.FJ|7\nSJLL7\n|F--J\nLJ.LJ"), parts(8, 1));
+        let input = "FF7FSF7F7F7F7F7F---7\nL|LJ||||||||||||F--J\nFL-7LJLJ||||||LJL-77\n\
+                     F--JF--7||LJLJ7F7FJ-\nL---JF-JLJ.||-FJLJJ7\n|F|F-JF---7F7-L7L|7|\n\
+                     |FFJF7L7F-JF7|JL---7\n7-L-JL7||F7|L7F-7F7|\nL.L7LFJ|||||FJL7||LJ\n\
+                     L7JLJL-JLJLJL--JLJ.L";
+        assert_eq!(solve(input).1, Answer::from(10usize));
+    }
+
+    // A rectangular loop with its start in the top left corner, with every other tile a
+    // pseudo-random pipe, so that lots of them connect to the loop from outside and inside.
+    // Only the tiles north and west of the start are kept from connecting to it.
+    fn noisy_rect(width: usize, height: usize, seed: u64) -> String {
+        let mut state = seed;
+        let mut junk = || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            b"|-LJ7F."[(state >> 33) as usize % 7]
+        };
+        let mut rows = vec![];
+        for y in 0..height {
+            let row: Vec<u8> = (0..width)
+                .map(|x| {
+                    let on_x_edge = x == 1 || x == width - 2;
+                    let on_y_edge = y == 1 || y == height - 2;
+                    let in_x = (1..width - 1).contains(&x);
+                    let in_y = (1..height - 1).contains(&y);
+                    match (x, y) {
+                        (1, 1) => b'S',
+                        (1, 0) => b'-',
+                        (0, 1) => b'|',
+                        _ if on_x_edge && on_y_edge => match (x == 1, y == 1) {
+                            (false, true) => b'7',
+                            (true, false) => b'L',
+                            _ => b'J',
+                        },
+                        _ if on_x_edge && in_y => b'|',
+                        _ if on_y_edge && in_x => b'-',
+                        _ => junk(),
+                    }
+                })
+                .collect();
+            rows.push(String::from_utf8(row).unwrap());
+        }
+        rows.join("\n")
+    }
+
+    #[test]
+    fn loop_among_junk() {
+        for (width, height) in [(5, 5), (9, 7), (6, 13)] {
+            for seed in 0..50 {
+                let input = noisy_rect(width, height, seed);
+                let (part1, part2) = solve(&input);
+                assert_eq!(part1, Answer::from(width + height - 6), "{input}");
+                assert_eq!(part2, Answer::from((width - 4) * (height - 4)), "{input}");
+            }
+        }
+    }
+
+    #[test]
+    fn start_count() {
+        assert!(Day10::parse(b"F-7\n|.|\nL-J").is_err());
+        assert!(Day10::parse(b"\n\n-----S-------S--").is_err());
+    }
 }
@@ -1,14 +1,23 @@
+use alloc::vec::Vec;
+
 use bit_set::BitSet;
 use bit_vec::BitVec;
+use num_bigint::BigUint;
+
+use crate::{
+    cancel::Token,
+    parse::{self, ParseError},
+    Answer, Day11, Puzzle,
+};
 
-#[derive(Debug)]
-struct Grid {
+#[derive(Debug, Clone)]
+pub struct Grid {
     planets: Vec<(usize, usize)>,
     width: usize,
     height: usize,
 }
 
-fn parse_input(input: &str) -> Grid {
+fn parse_input(input: &str) -> Result<Grid, ParseError> {
     let mut planets = Vec::new();
     let mut width = 0;
     let mut height = 0;
@@ -17,16 +26,18 @@ fn parse_input(input: &str) -> Grid {
         height += 1;
         width = line.len();
         for (x, c) in line.bytes().enumerate() {
-            if c == b'#' {
-                planets.push((y, x));
+            match c {
+                b'#' => planets.push((y, x)),
+                b'.' => {}
+                _ => return Err(ParseError::invalid("character", c as char).at(y + 1, x + 1)),
             }
         }
     }
-    Grid {
+    Ok(Grid {
         planets,
         width,
         height,
-    }
+    })
 }
 
 fn empty_rows_and_cols(grid: &Grid) -> (Vec<usize>, Vec<usize>) {
@@ -68,30 +79,66 @@ fn planet_pairs(g: &Grid) -> impl Iterator<Item = [(usize, usize); 2]> + '_ {
         .flat_map(move |(i, &p1)| g.planets[i + 1..].iter().map(move |&p2| [p1, p2]))
 }
 
+/// The grid after adding `offset` rows or columns after every empty one.
+fn expand(grid: &Grid, offset: usize) -> Grid {
+    let mut grid = grid.clone();
+    let (rows, cols) = empty_rows_and_cols(&grid);
+    apply_offsets(&mut grid.planets, &rows, &cols, offset);
+    grid
+}
+
+/// The sum of the distances between all pairs of planets, after adding `offset` rows or
+/// columns after every empty one.
+fn sum_of_distances(grid: &Grid, offset: usize) -> usize {
+    planet_pairs(&expand(grid, offset))
+        .map(|[p1, p2]| dist(p1, p2))
+        .sum()
+}
+
+/// Like [`sum_of_distances`], but summed up as a big integer, since there are quadratically
+/// many pairs.
+fn sum_of_distances_wide(grid: &Grid, offset: usize) -> BigUint {
+    planet_pairs(&expand(grid, offset))
+        .map(|[p1, p2]| BigUint::from(dist(p1, p2)))
+        .sum()
+}
+
 fn dist((y1, x1): (usize, usize), (y2, x2): (usize, usize)) -> usize {
     let dx = x1.abs_diff(x2);
     let dy = y1.abs_diff(y2);
     dx + dy
 }
 
-pub fn part1(input: &str) -> String {
-    let mut grid = parse_input(input);
-    let (rows, cols) = empty_rows_and_cols(&grid);
-    apply_offsets(&mut grid.planets, &rows, &cols, 1);
+register_day!(
+    11,
+    Day11,
+    "Cosmic Expansion",
+    tags: ["grid"],
+    variants: [(1, "wide", part1_wide), (2, "wide", part2_wide)],
+);
 
-    planet_pairs(&grid)
-        .map(|[p1, p2]| dist(p1, p2))
-        .sum::<usize>()
-        .to_string()
+impl Puzzle for Day11 {
+    type Parsed<'a> = Grid;
+    const ALGORITHM: &'static str = "expansion-offsets-v1";
+
+    fn parse(input: &[u8]) -> anyhow::Result<Grid> {
+        let input = parse::utf8(input)?;
+        Ok(parse_input(input)?)
+    }
+
+    fn solve1(grid: &Grid, _: &Token) -> anyhow::Result<Answer> {
+        Ok(sum_of_distances(grid, 1).into())
+    }
+
+    fn solve2(grid: &Grid, _: &Token) -> anyhow::Result<Answer> {
+        Ok(sum_of_distances(grid, 999_999).into())
+    }
 }
 
-pub fn part2(input: &str) -> String {
-    let mut grid = parse_input(input);
-    let (rows, cols) = empty_rows_and_cols(&grid);
-    apply_offsets(&mut grid.planets, &rows, &cols, 999_999);
+pub fn part1_wide(input: &str) -> anyhow::Result<Answer> {
+    Ok(sum_of_distances_wide(&parse_input(input)?, 1).into())
+}
 
-    planet_pairs(&grid)
-        .map(|[p1, p2]| dist(p1, p2))
-        .sum::<usize>()
-        .to_string()
+pub fn part2_wide(input: &str) -> anyhow::Result<Answer> {
+    Ok(sum_of_distances_wide(&parse_input(input)?, 999_999).into())
 }
@@ -1,43 +1,54 @@
+use alloc::{format, vec::Vec};
+
+use crate::{
+    cancel::Token,
+    parse::{self, ParseError},
+    Answer, Day12, Puzzle,
+};
 
 #[derive(PartialEq, Eq, Clone, Copy, Hash)]
-enum SpringStatus {
+pub enum SpringStatus {
     Working,
     Broken,
     Unknown,
 }
 
-struct Row {
+pub struct Row {
     springs: Vec<SpringStatus>,
     blocks: Vec<usize>,
 }
 
-fn parse_row(line: &str) -> Row {
-    let (s, b) = line.trim().split_once(' ').unwrap();
+fn parse_row(line: &str) -> Result<Row, ParseError> {
+    let (s, b) = parse::split_once(line.trim(), " ")?;
 
     let springs: Vec<_> = s
         .bytes()
-        .map(|c| match c {
-            b'.' => SpringStatus::Working,
-            b'#' => SpringStatus::Broken,
-            b'?' => SpringStatus::Unknown,
-            _ => unreachable!(),
+        .enumerate()
+        .map(|(i, c)| match c {
+            b'.' => Ok(SpringStatus::Working),
+            b'#' => Ok(SpringStatus::Broken),
+            b'?' => Ok(SpringStatus::Unknown),
+            _ => Err(ParseError::invalid("spring", c as char).at_column(i + 1)),
         })
-        .collect();
+        .collect::<Result<_, _>>()?;
 
-    let blocks: Vec<_> = b.split(',').map(|s| s.parse::<usize>().unwrap()).collect();
+    let blocks: Vec<_> = b.split(',').map(parse::num).collect::<Result<_, _>>()?;
 
-    // These are the biggest lengths that our hashing scheme can handle. It seems that
-    // the input doesn't include any larger values, but this is not guaranteed by
-    // the problem statement. In the worst case we'd need to switch these to usizes
-    // and just use a hashmap.
-    assert!(springs.len() <= 24);
-    assert!(blocks.len() <= 6);
+    // Part 2 unfolds every row into 5 copies of it, joined by 4 more springs, and
+    // `count_arrangements` has to be able to count the arrangements of those.
+    const MAX_ROW_SPRINGS: usize = (MAX_SPRINGS - 4) / 5;
+    const MAX_ROW_BLOCKS: usize = MAX_BLOCKS / 5;
+    if springs.len() > MAX_ROW_SPRINGS || blocks.len() > MAX_ROW_BLOCKS {
+        return Err(ParseError::malformed(format!(
+            "rows can have at most {MAX_ROW_SPRINGS} springs and {MAX_ROW_BLOCKS} blocks"
+        )));
+    }
 
-    Row { springs, blocks }
+    Ok(Row { springs, blocks })
 }
 
-fn parse_input(input: &str) -> Vec<Row> {
-    input.lines().map(parse_row).collect()
+fn parse_input(input: &str) -> Result<Vec<Row>, ParseError> {
+    parse::lines(input, parse_row)
 }
 
 // With our hashing scheme, cache keys are always < 2^12. At that size, an array
@@ -45,8 +56,28 @@ fn parse_input(input: &str) -> Vec<Row> {
 type CacheKey = u16;
 type Cache = [usize; 1 << 12];
 
-fn count_arrangements(row: &Row, cache: &mut Cache) -> usize {
-    
+/// The most springs [`count_arrangements`] can handle, as its cache has a slot for every
+/// number of springs and blocks left.
+pub const MAX_SPRINGS: usize = (1 << 7) - 1;
+/// The most blocks [`count_arrangements`] can handle.
+pub const MAX_BLOCKS: usize = (1 << 5) - 1;
+
+/// Counts the ways to replace the unknown springs with working or broken ones, such that
+/// the runs of consecutive broken springs have exactly the lengths in `blocks`, in order.
+/// Fails if there are more than [`MAX_SPRINGS`] springs or [`MAX_BLOCKS`] blocks, or if
+/// the count doesn't fit into a `usize`.
+pub fn count_arrangements(
+    springs: &[SpringStatus],
+    blocks: &[usize],
+) -> Result<usize, ParseError> {
+    if springs.len() > MAX_SPRINGS || blocks.len() > MAX_BLOCKS {
+        return Err(ParseError::malformed(format!(
+            "rows can have at most {} springs and {} blocks",
+            MAX_SPRINGS, MAX_BLOCKS
+        )));
+    }
+    let mut cache = [usize::MAX; 1 << 12];
+
     fn cache_key(springs: &[SpringStatus], blocks: &[usize]) -> CacheKey {
         (springs.len() as u16) << 5 | blocks.len() as u16
     }
@@ -79,7 +110,8 @@ fn count_arrangements(row: &Row, cache: &mut Cache) -> usize {
         }
     }
 
-    fn rec(mut springs: &[SpringStatus], blocks: &[usize], cache: &mut Cache) -> usize {
+    // `None` if the count doesn't fit into a `usize`.
+    fn rec(mut springs: &[SpringStatus], blocks: &[usize], cache: &mut Cache) -> Option<usize> {
         // strip leading working springs.
         while let [SpringStatus::Working, rest @ ..] = springs {
             springs = rest;
@@ -87,78 +119,183 @@ fn count_arrangements(row: &Row, cache: &mut Cache) -> usize {
 
         // If there are no springs, then there is only an arrangement if there are no blocks.
         if springs.is_empty() {
-            return usize::from(blocks.is_empty());
+            return Some(usize::from(blocks.is_empty()));
         }
 
         // If there are no blocks, then there is only an arrangement if there are no broken springs.
         if blocks.is_empty() {
-            return usize::from(springs.iter().all(|s| *s != SpringStatus::Broken));
+            return Some(usize::from(springs.iter().all(|s| *s != SpringStatus::Broken)));
         }
 
         let key = cache_key(springs, blocks);
 
         if let Some(count) = get_cache(cache, key) {
-            return count;
+            return Some(count);
         }
 
         // Easy case: if there are not enough springs to cover the blocks, then there are no arrangements.
-        if springs.len() < blocks.iter().sum::<usize>() + blocks.len() - 1 {
-            return set_cache(cache, key, 0);
+        // Saturating, so that absurdly long blocks just don't fit.
+        let needed = blocks.iter().fold(blocks.len() - 1, |n, &b| n.saturating_add(b));
+        if springs.len() < needed {
+            return Some(set_cache(cache, key, 0));
         }
 
         // If the first spring is unknown, then we can either assume it is working or broken, so we
         // try both cases.
         if springs[0] == SpringStatus::Unknown {
-            let count_if_working = rec(&springs[1..], blocks, cache);
+            let count_if_working = rec(&springs[1..], blocks, cache)?;
 
             let count_if_broken = match munch_not_working(springs, blocks[0]) {
-                Some(munched) => rec(munched.get(1..).unwrap_or_default(), &blocks[1..], cache),
+                Some(munched) => rec(munched.get(1..).unwrap_or_default(), &blocks[1..], cache)?,
                 None => 0,
             };
 
-            return set_cache(cache, key, count_if_working + count_if_broken);
+            return Some(set_cache(cache, key, count_if_working.checked_add(count_if_broken)?));
         }
 
         // Now it must be that springs[0] == SpringStatus::Broken.
 
         let ret = match munch_not_working(springs, blocks[0]) {
-            Some(munched) => rec(munched.get(1..).unwrap_or_default(), &blocks[1..], cache),
+            Some(munched) => rec(munched.get(1..).unwrap_or_default(), &blocks[1..], cache)?,
             None => 0,
         };
-        set_cache(cache, key, ret)
+        Some(set_cache(cache, key, ret))
     }
 
-    rec(&row.springs, &row.blocks, cache)
+    rec(springs, blocks, &mut cache)
+        .ok_or_else(|| ParseError::malformed("the row has too many arrangements to count"))
 }
 
-pub fn part1(input: &str) -> String {
-    let rows = parse_input(input);
-    let mut cache = [usize::MAX; 1 << 12];
-    rows.iter()
-        .map(|row| {
-            cache.fill(usize::MAX);
-            count_arrangements(row, &mut cache)
-        })
-        .sum::<usize>()
-        .to_string()
+fn checked_sum(sum: usize, count: usize) -> anyhow::Result<usize> {
+    sum.checked_add(count)
+        .ok_or_else(|| anyhow::anyhow!("the sum of the arrangements doesn't fit into 64 bits"))
 }
 
-pub fn part2(input: &str) -> String {
-    let mut rows = parse_input(input);
-    for row in &mut rows {
-        let n = row.springs.len();
-        row.springs.push(SpringStatus::Unknown);
-        row.springs.extend_from_within(..);
-        row.springs.extend_from_within(..);
-        row.springs.extend_from_within(..n);
-        row.blocks = row.blocks.repeat(5);
+register_day!(12, Day12, "Hot Springs", tags: ["dynamic-programming"]);
+
+impl Puzzle for Day12 {
+    type Parsed<'a> = Vec<Row>;
+    const ALGORITHM: &'static str = "memoized-dp-v1";
+
+    fn parse(input: &[u8]) -> anyhow::Result<Vec<Row>> {
+        let input = parse::utf8(input)?;
+        Ok(parse_input(input)?)
+    }
+
+    fn solve1(rows: &Vec<Row>, cancel: &Token) -> anyhow::Result<Answer> {
+        let mut sum = 0;
+        for row in rows {
+            cancel.check()?;
+            let count = count_arrangements(&row.springs, &row.blocks)?;
+            sum = checked_sum(sum, count)?;
+        }
+        Ok(sum.into())
+    }
+
+    fn solve2(rows: &Vec<Row>, cancel: &Token) -> anyhow::Result<Answer> {
+        let mut sum = 0;
+        for row in rows {
+            cancel.check()?;
+            let mut springs = row.springs.clone();
+            let n = springs.len();
+            springs.push(SpringStatus::Unknown);
+            springs.extend_from_within(..);
+            springs.extend_from_within(..);
+            springs.extend_from_within(..n);
+            let count = count_arrangements(&springs, &row.blocks.repeat(5))?;
+            sum = checked_sum(sum, count)?;
+        }
+        Ok(sum.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+???.### 1,1,3
+.??..??...?##. 1,1,3
+?#?#?#?#?#?#?#? 1,3,1,6
+????.#...#... 4,1,1
+????.######..#####. 1,6,5
+?###???????? 3,2,1
+";
+
+    fn springs(s: &str) -> Vec<SpringStatus> {
+        s.bytes()
+            .map(|c| match c {
+                b'.' => SpringStatus::Working,
+                b'#' => SpringStatus::Broken,
+                _ => SpringStatus::Unknown,
+            })
+            .collect()
+    }
+
+    fn count(s: &str, blocks: &[usize]) -> usize {
+        count_arrangements(&springs(s), blocks).unwrap()
+    }
+
+    #[test]
+    fn example_rows() {
+        let counts: Vec<_> = parse_input(EXAMPLE)
+            .unwrap()
+            .iter()
+            .map(|row| count_arrangements(&row.springs, &row.blocks).unwrap())
+            .collect();
+        assert_eq!(counts, [1, 4, 1, 1, 4, 10]);
+    }
+
+    #[test]
+    fn example_answers() {
+        let rows = parse_input(EXAMPLE).unwrap();
+        let cancel = Token::default();
+        assert_eq!(Day12::solve1(&rows, &cancel).unwrap(), Answer::from(21usize));
+        assert_eq!(Day12::solve2(&rows, &cancel).unwrap(), Answer::from(525152usize));
+    }
+
+    #[test]
+    fn empty_springs() {
+        assert_eq!(count("", &[]), 1);
+        assert_eq!(count("", &[1]), 0);
+        assert_eq!(count("...", &[]), 1);
+    }
+
+    #[test]
+    fn empty_blocks() {
+        assert_eq!(count("???", &[]), 1);
+        assert_eq!(count("?#?", &[]), 0);
+    }
+
+    #[test]
+    fn all_unknown() {
+        assert_eq!(count("????", &[1]), 4);
+        assert_eq!(count("????", &[1, 1]), 3);
+        assert_eq!(count("????", &[4]), 1);
+        assert_eq!(count("????", &[5]), 0);
+        assert_eq!(count("????", &[usize::MAX]), 0);
+        // The 20 springs that are left over can go into any of the 4 gaps around the blocks.
+        assert_eq!(count(&"?".repeat(MAX_SPRINGS), &[50, 50, 5]), 1771);
+    }
+
+    #[test]
+    fn size_limits() {
+        let unknown = |n| vec![SpringStatus::Unknown; n];
+        assert!(count_arrangements(&unknown(MAX_SPRINGS), &[1]).is_ok());
+        assert!(count_arrangements(&unknown(MAX_SPRINGS + 1), &[1]).is_err());
+        assert!(count_arrangements(&unknown(70), &[1; MAX_BLOCKS]).is_ok());
+        assert!(count_arrangements(&unknown(70), &[1; MAX_BLOCKS + 1]).is_err());
+        // There are more than 2^64 ways to spread 31 blocks over that many springs.
+        assert!(count_arrangements(&unknown(MAX_SPRINGS), &[1; MAX_BLOCKS]).is_err());
+
+        // The largest rows that parse can still be unfolded for part 2.
+        let cancel = Token::default();
+        let row = format!("{} 3,3,3,3,3,3", "?".repeat(24));
+        let rows = Day12::parse(row.as_bytes()).unwrap();
+        // Unfolded, 30 blocks of 3 and the springs between them leave 5 springs to spare.
+        let spread = Answer::from(35 * 34 * 33 * 32 * 31 / 120usize);
+        assert_eq!(Day12::solve2(&rows, &cancel).unwrap(), spread);
+        assert!(Day12::parse(format!("?{row}").as_bytes()).is_err());
+        assert!(Day12::parse(format!("{row},1").as_bytes()).is_err());
     }
-    let mut cache = [usize::MAX; 1 << 12];
-    rows.iter()
-        .map(|row| {
-            cache.fill(usize::MAX);
-            count_arrangements(row, &mut cache)
-        })
-        .sum::<usize>()
-        .to_string()
 }
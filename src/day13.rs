@@ -1,8 +1,11 @@
-use std::str::Lines;
+use alloc::vec::Vec;
 
+use anyhow::Context;
 use smallvec::SmallVec;
 
-struct Grid {
+use crate::{cancel::Token, lex, parse::ParseError, Answer, Day13, Puzzle};
+
+pub struct Grid {
     // It looks like the largest patterns in the input are 17x17.
     // Integer comparisons are a lot faster than bit slice comparisons,
     // so we store each row/col in a 32 bit number, with 15+ bits of padding
@@ -13,12 +16,12 @@ struct Grid {
     cols: SmallVec<[u32; 20]>,
 }
 
-fn parse_grid(lines: &mut Lines) -> Grid {
+fn parse_grid<'a>(lines: &mut impl Iterator<Item = (usize, &'a [u8])>) -> Result<Grid, ParseError> {
     let mut rows = SmallVec::new();
     let mut cols = SmallVec::new();
 
-    for (y, line) in lines.enumerate() {
-        let line = line.trim();
+    for (i, line) in lines {
+        let line = line.trim_ascii();
         if line.is_empty() {
             break;
         }
@@ -26,24 +29,45 @@ fn parse_grid(lines: &mut Lines) -> Grid {
         if cols.is_empty() {
             cols.resize(line.len(), 0);
         }
+        if line.len() != cols.len() {
+            return Err(
+                ParseError::malformed("this line isn't as long as the ones before it")
+                    .at_line(i + 1),
+            );
+        }
 
+        let y = rows.len();
         rows.push(0u32);
 
-        for (x, c) in line.bytes().enumerate() {
-            if c == b'#' {
-                cols[x] |= 1u32.checked_shl(y as u32).expect("grid too tall");
-                rows[y] |= 1u32.checked_shl(x as u32).expect("grid too wide");
+        for (x, &c) in line.iter().enumerate() {
+            match c {
+                b'#' => {
+                    let too_big = |what| ParseError::malformed(what).at(i + 1, x + 1);
+                    cols[x] |= 1u32
+                        .checked_shl(y as u32)
+                        .ok_or_else(|| too_big("grid too tall"))?;
+                    rows[y] |= 1u32
+                        .checked_shl(x as u32)
+                        .ok_or_else(|| too_big("grid too wide"))?;
+                }
+                b'.' => {}
+                _ => return Err(ParseError::invalid("character", c as char).at(i + 1, x + 1)),
             }
         }
     }
 
-    Grid { rows, cols }
+    Ok(Grid { rows, cols })
 }
 
-fn parse_input(input: &str) -> impl Iterator<Item = Grid> + '_ {
-    let mut lines = input.lines();
-    std::iter::from_fn(move || {
-        if lines.clone().next().is_some() {
+// Any number of blank lines can separate the grids.
+fn parse_input(input: &[u8]) -> impl Iterator<Item = Result<Grid, ParseError>> + '_ {
+    let mut lines = lex::lines(input).enumerate().peekable();
+    core::iter::from_fn(move || {
+        while lines
+            .next_if(|(_, line)| line.trim_ascii().is_empty())
+            .is_some()
+        {}
+        if lines.peek().is_some() {
             Some(parse_grid(&mut lines))
         } else {
             None
@@ -70,35 +94,47 @@ fn search(data: &[u32], bits_to_flip: u32) -> Option<u8> {
     None
 }
 
-fn find_symmetry(g: &Grid, bits_to_flip: u32) -> Axis {
+fn find_symmetry(g: &Grid, bits_to_flip: u32) -> Option<Axis> {
     if let Some(i) = search(&g.cols, bits_to_flip) {
-        return Axis::Vertical(i);
+        return Some(Axis::Vertical(i));
     }
     if let Some(i) = search(&g.rows, bits_to_flip) {
-        return Axis::Horizontal(i);
+        return Some(Axis::Horizontal(i));
     }
 
-    unreachable!("grid without symmetry")
+    None
 }
 
-pub fn part1(input: &str) -> String {
-    let grids = parse_input(input);
+fn summarize(grids: &[Grid], bits_to_flip: u32) -> anyhow::Result<usize> {
     grids
-        .map(|g| match find_symmetry(&g, 0) {
-            Axis::Vertical(col) => col as usize,
-            Axis::Horizontal(row) => (row as usize) * 100,
+        .iter()
+        .enumerate()
+        .map(|(i, g)| {
+            let axis = find_symmetry(g, bits_to_flip)
+                .with_context(|| format!("pattern {} doesn't have a line of reflection", i + 1))?;
+            Ok(match axis {
+                Axis::Vertical(col) => col as usize,
+                Axis::Horizontal(row) => (row as usize) * 100,
+            })
         })
-        .sum::<usize>()
-        .to_string()
+        .sum()
 }
 
-pub fn part2(input: &str) -> String {
-    let grids = parse_input(input);
-    grids
-        .map(|g| match find_symmetry(&g, 1) {
-            Axis::Vertical(col) => col as usize,
-            Axis::Horizontal(row) => (row as usize) * 100,
-        })
-        .sum::<usize>()
-        .to_string()
+register_day!(13, Day13, "Point of Incidence", tags: ["grid"]);
+
+impl Puzzle for Day13 {
+    type Parsed<'a> = Vec<Grid>;
+    const ALGORITHM: &'static str = "bitmask-mirror-v1";
+
+    fn parse(input: &[u8]) -> anyhow::Result<Vec<Grid>> {
+        Ok(parse_input(input).collect::<Result<_, _>>()?)
+    }
+
+    fn solve1(grids: &Vec<Grid>, _: &Token) -> anyhow::Result<Answer> {
+        Ok(summarize(grids, 0)?.into())
+    }
+
+    fn solve2(grids: &Vec<Grid>, _: &Token) -> anyhow::Result<Answer> {
+        Ok(summarize(grids, 1)?.into())
+    }
 }
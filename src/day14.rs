@@ -1,4 +1,15 @@
-use ahash::AHashMap;
+use alloc::vec::Vec;
+use core::fmt;
+
+use serde::Serialize;
+
+use crate::{
+    cancel::Token,
+    collections::HashMap,
+    geometry::Dir,
+    parse::{self, ParseError},
+    Answer, Day14, Puzzle,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum Cell {
@@ -8,7 +19,7 @@ enum Cell {
 }
 
 #[derive(Clone, PartialEq, Eq, Hash)]
-struct Grid {
+pub struct Grid {
     // we can squeeze 4 cells into a byte by using 2 bits per cell
     cells: Vec<u8>,
     width: usize,
@@ -41,14 +52,20 @@ impl Grid {
     }
 }
 
-fn parse_grid(input: &str) -> Grid {
+fn parse_grid(input: &str) -> Result<Grid, ParseError> {
     let mut cells = Vec::new();
     let mut width = 0;
     let mut height = 0;
     let mut shift = 0;
-    for line in input.lines() {
+    for (y, line) in input.lines().enumerate() {
+        if y > 0 && line.len() != width {
+            return Err(
+                ParseError::malformed("this line isn't as long as the ones before it")
+                    .at_line(y + 1),
+            );
+        }
         width = line.len();
-        for c in line.chars() {
+        for (x, c) in line.chars().enumerate() {
             if shift == 0 {
                 cells.push(0);
             }
@@ -57,17 +74,17 @@ fn parse_grid(input: &str) -> Grid {
                 '.' => 0b00,
                 'O' => 0b01,
                 '#' => 0b10,
-                _ => panic!("Invalid cell"),
+                _ => return Err(ParseError::invalid("cell", c).at(y + 1, x + 1)),
             } << shift;
             shift = (shift + 2) % 8;
         }
         height += 1;
     }
-    Grid {
+    Ok(Grid {
         cells,
         width,
         height,
-    }
+    })
 }
 
 fn slide_north(grid: &mut Grid) {
@@ -161,40 +178,117 @@ fn spin_cycle(grid: &mut Grid) {
     slide_east(grid);
 }
 
-fn total_load(grid: &Grid) -> usize {
+// each round rock puts a load of its distance from the opposite edge (plus one)
+// on an edge, e.g. a rock in the top row puts a load of `height` on the north edge.
+fn total_load(grid: &Grid, edge: Dir) -> usize {
     let mut total = 0;
     for y in 0..grid.height {
         for x in 0..grid.width {
             if grid.get(x, y) == Cell::Round {
-                total += grid.height - y;
+                total += match edge {
+                    Dir::North => grid.height - y,
+                    Dir::South => y + 1,
+                    Dir::West => grid.width - x,
+                    Dir::East => x + 1,
+                };
             }
         }
     }
     total
 }
 
-pub fn part1(input: &str) -> String {
-    let mut grid = parse_grid(input);
-    slide_north(&mut grid);
-    total_load(&grid).to_string()
-}
-
-pub fn part2(input: &str) -> String {
-    let mut grid = parse_grid(input);
-    let mut seen = AHashMap::from([(grid.clone(), 0)]);
+fn spin_cycles(grid: &mut Grid, cycles: usize) {
+    let mut seen = HashMap::from_iter([(grid.clone(), 0)]);
 
-    for i in 1usize.. {
-        spin_cycle(&mut grid);
-        if let Some(&prev) = seen.get(&grid) {
+    for i in 1..=cycles {
+        spin_cycle(grid);
+        if let Some(&prev) = seen.get(grid) {
             let cycle_len = i - prev;
-            let remaining = (1_000_000_000 - i) % cycle_len;
+            let remaining = (cycles - i) % cycle_len;
             for _ in 0..remaining {
-                spin_cycle(&mut grid);
+                spin_cycle(grid);
             }
             break;
         }
         seen.insert(grid.clone(), i);
     }
+}
+
+register_day!(14, Day14, "Parabolic Reflector Dish", tags: ["grid", "cycles"]);
+
+impl Puzzle for Day14 {
+    type Parsed<'a> = Grid;
+    const ALGORITHM: &'static str = "cycle-detection-v1";
+
+    fn parse(input: &[u8]) -> anyhow::Result<Grid> {
+        let input = parse::utf8(input)?;
+        Ok(parse_grid(input)?)
+    }
+
+    fn solve1(grid: &Grid, _: &Token) -> anyhow::Result<Answer> {
+        let mut grid = grid.clone();
+        slide_north(&mut grid);
+        Ok(total_load(&grid, Dir::North).into())
+    }
+
+    fn solve2(grid: &Grid, _: &Token) -> anyhow::Result<Answer> {
+        let mut grid = grid.clone();
+        spin_cycles(&mut grid, 1_000_000_000);
+        Ok(total_load(&grid, Dir::North).into())
+    }
+}
+
+#[derive(Serialize)]
+pub struct EdgeLoads {
+    north: usize,
+    south: usize,
+    east: usize,
+    west: usize,
+}
+
+impl EdgeLoads {
+    fn new(grid: &Grid) -> Self {
+        Self {
+            north: total_load(grid, Dir::North),
+            south: total_load(grid, Dir::South),
+            east: total_load(grid, Dir::East),
+            west: total_load(grid, Dir::West),
+        }
+    }
+}
+
+impl fmt::Display for EdgeLoads {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "north {}, south {}, east {}, west {}",
+            self.north, self.south, self.east, self.west
+        )
+    }
+}
+
+/// The loads on every edge, for the grid after tilting it north (part 1) and after
+/// the spin cycles (part 2).
+#[derive(Serialize)]
+pub struct Details {
+    tilted: EdgeLoads,
+    spun: EdgeLoads,
+}
+
+impl fmt::Display for Details {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "After tilting north: {}", self.tilted)?;
+        write!(f, "After 1000000000 spin cycles: {}", self.spun)
+    }
+}
 
-    total_load(&grid).to_string()
+pub fn details(input: &str) -> anyhow::Result<Details> {
+    let mut tilted = parse_grid(input)?;
+    slide_north(&mut tilted);
+    let mut spun = parse_grid(input)?;
+    spin_cycles(&mut spun, 1_000_000_000);
+    Ok(Details {
+        tilted: EdgeLoads::new(&tilted),
+        spun: EdgeLoads::new(&spun),
+    })
 }
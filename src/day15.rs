@@ -1,27 +1,34 @@
+use alloc::{borrow::Cow, vec::Vec};
+
+use crate::{
+    cancel::Token,
+    parse::{self, ParseError},
+    Answer, Day15, Puzzle,
+};
+
 fn hash(bytes: &[u8]) -> u8 {
     bytes
         .iter()
         .fold(0u8, |acc, &b| acc.wrapping_add(b).wrapping_mul(17))
 }
 
-fn lenses(input: &str) -> impl Iterator<Item = &str> {
-    input.trim().split(',')
-}
-
-pub fn part1(input: &str) -> String {
-    lenses(input)
-        .map(|s| hash(s.as_bytes()) as usize)
-        .sum::<usize>()
-        .to_string()
+/// The puzzle says to ignore newlines, which some editors add when saving the input, or
+/// break the long line with.
+pub fn preprocess(input: &str) -> anyhow::Result<Cow<'_, str>> {
+    Ok(if input.contains('\n') {
+        Cow::Owned(input.replace('\n', ""))
+    } else {
+        Cow::Borrowed(input)
+    })
 }
 
-pub fn part2(input: &str) -> String {
+fn focusing_power(steps: &[&str]) -> Result<usize, ParseError> {
     // Asymptotically, this solution is not very efficient, as scanning a Vec or removing an
     // element is O(n), whereas with something like a linked hash map, it would be O(1). However,
     // the lists stay short enough that using a vector is over 2x faster than a linked hash map
     // for my input.
-    let mut boxes: [Vec<(&[u8], u8)>; 256] = std::array::from_fn(|_| Vec::new());
-    for lens in lenses(input) {
+    let mut boxes: [Vec<(&[u8], u8)>; 256] = core::array::from_fn(|_| Vec::new());
+    for &lens in steps {
         match lens.as_bytes() {
             [name @ .., b'-'] => {
                 let hash = hash(name);
@@ -46,7 +53,7 @@ pub fn part2(input: &str) -> String {
                     lensbox.push((name, focal_length));
                 }
             }
-            _ => unreachable!("invalid input"),
+            _ => return Err(ParseError::invalid("step", lens)),
         }
     }
 
@@ -57,5 +64,29 @@ pub fn part2(input: &str) -> String {
         }
     }
 
-    focusing_power.to_string()
+    Ok(focusing_power)
+}
+
+register_day!(15, Day15, "Lens Library", tags: ["hashing"]);
+
+impl Puzzle for Day15 {
+    type Parsed<'a> = Vec<&'a str>;
+    const ALGORITHM: &'static str = "vec-boxes-v1";
+
+    fn parse(input: &[u8]) -> anyhow::Result<Vec<&str>> {
+        let input = parse::utf8(input)?;
+        Ok(input.trim().split(',').collect())
+    }
+
+    fn solve1(steps: &Vec<&str>, _: &Token) -> anyhow::Result<Answer> {
+        Ok(steps
+            .iter()
+            .map(|s| hash(s.as_bytes()) as usize)
+            .sum::<usize>()
+            .into())
+    }
+
+    fn solve2(steps: &Vec<&str>, _: &Token) -> anyhow::Result<Answer> {
+        Ok(focusing_power(steps)?.into())
+    }
 }
@@ -1,5 +1,17 @@
+use core::fmt;
+
+use serde::Serialize;
+
+use crate::{
+    cancel::Token,
+    grid,
+    parse::ParseError,
+    viz::{Image, Visualize},
+    Answer, Day16, Puzzle,
+};
+
 #[derive(Copy, Clone, PartialEq, Eq)]
-enum Cell {
+pub enum Cell {
     Empty,
     HorizontalSplitter,
     VerticalSplitter,
@@ -7,42 +19,24 @@ enum Cell {
     Mirror135Degree,
 }
 
-struct Grid {
-    cells: Vec<Cell>,
-    width: u8,
-    height: u8,
-}
+pub type Grid = grid::Grid<Cell>;
 
-impl Grid {
-    fn get(&self, x: u8, y: u8) -> Cell {
-        let idx = (y as usize) * (self.width as usize) + (x as usize);
-        self.cells[idx]
-    }
-}
-
-fn parse_grid(input: &str) -> Grid {
-    let mut cells = Vec::new();
-    let mut width = 0;
-    let mut height = 0;
-    for line in input.lines() {
-        width = line.len() as u8;
-        height += 1;
-        for c in line.chars() {
-            cells.push(match c {
-                '.' => Cell::Empty,
-                '-' => Cell::HorizontalSplitter,
-                '|' => Cell::VerticalSplitter,
-                '/' => Cell::Mirror45Degree,
-                '\\' => Cell::Mirror135Degree,
-                _ => unreachable!("invalid input"),
-            });
-        }
-    }
-    Grid {
-        cells,
-        width,
-        height,
+fn parse_grid(input: &[u8]) -> Result<Grid, ParseError> {
+    let grid = Grid::parse(input, |_, c| {
+        Ok(match c {
+            b'.' => Cell::Empty,
+            b'-' => Cell::HorizontalSplitter,
+            b'|' => Cell::VerticalSplitter,
+            b'/' => Cell::Mirror45Degree,
+            b'\\' => Cell::Mirror135Degree,
+            _ => return Err(ParseError::invalid("character", c as char)),
+        })
+    })?;
+    // Coordinates are u8s.
+    if grid.width() > u8::MAX as usize || grid.height() > u8::MAX as usize {
+        return Err(ParseError::malformed("the grid is larger than 255x255"));
     }
+    Ok(grid)
 }
 
 const RIGHT: u8 = 0b0001;
@@ -50,31 +44,55 @@ const DOWN: u8 = 0b0010;
 const LEFT: u8 = 0b0100;
 const UP: u8 = 0b1000;
 
-fn count_energized_tiles(grid: &Grid, (start_x, start_y, from_dir): (u8, u8, u8)) -> usize {
+/// Bookkeeping about a single beam traversal, for the details output.
+#[derive(Default, Serialize)]
+pub struct BeamStats {
+    /// Number of distinct beam paths that were traced, i.e. stack entries that
+    /// started at a tile and direction that hadn't been visited yet.
+    paths: usize,
+    /// Number of times a beam hit the flat side of a splitter.
+    splits: usize,
+    /// Number of beams that ran into a tile they had already crossed in the same
+    /// direction. Such a beam has either closed a loop or merged into an earlier beam.
+    closed: usize,
+}
+
+/// Sends a beam into `grid`, returning the directions it entered every tile from as a
+/// bitmask (which is 0 for the tiles that don't get energized).
+fn energize(
+    grid: &Grid,
+    (start_x, start_y, from_dir): (u8, u8, u8),
+    stats: &mut BeamStats,
+) -> grid::Grid<u8> {
     use Cell::*;
 
     // Use the lower 4 bits of each element for one direction each.
     // TODO: Pack 2 cells into each byte?
-    let mut visited = vec![0u8; grid.cells.len()];
-    let was_visited = |visited: &[u8], x: u8, y: u8, mask: u8| {
-        let idx = (y as usize) * (grid.width as usize) + (x as usize);
-        visited[idx] & mask != 0
-    };
-    let mark_visited = |visited: &mut [u8], x: u8, y: u8, mask: u8| {
-        let idx = (y as usize) * (grid.width as usize) + (x as usize);
-        visited[idx] |= mask;
+    let mut visited = grid::Grid::new(grid.width(), grid.height(), 0u8);
+    let was_visited =
+        |visited: &grid::Grid<u8>, x: u8, y: u8, mask: u8| visited[(x, y)] & mask != 0;
+    let mark_visited = |visited: &mut grid::Grid<u8>, x: u8, y: u8, mask: u8| {
+        visited[(x, y)] |= mask;
     };
+    let (width, height) = (grid.width() as u8, grid.height() as u8);
 
     let mut stack = vec![(start_x, start_y, from_dir)];
 
     while let Some((x, y, from_dir)) = stack.pop() {
         if was_visited(&visited, x, y, from_dir) {
+            stats.closed += 1;
             continue;
         }
+        stats.paths += 1;
         mark_visited(&mut visited, x, y, from_dir);
-        let cell = grid.get(x, y);
+        let cell = grid[(x, y)];
+        if (cell == HorizontalSplitter && (from_dir == UP || from_dir == DOWN))
+            || (cell == VerticalSplitter && (from_dir == LEFT || from_dir == RIGHT))
+        {
+            stats.splits += 1;
+        }
         // all the cases to move right:
-        if x + 1 < grid.width
+        if x + 1 < width
             && ((cell == Empty && from_dir == LEFT)
                 || (cell == Mirror45Degree && from_dir == DOWN)
                 || (cell == Mirror135Degree && from_dir == UP)
@@ -84,17 +102,17 @@ fn count_energized_tiles(grid: &Grid, (start_x, start_y, from_dir): (u8, u8, u8)
             // not making a copy of x would result in more moves than necessary.
             let mut x = x;
             // greedily move right until we hit either the wall, a vertical splitter or a mirror.
-            while x + 1 < grid.width && matches!(grid.get(x + 1, y), Empty | HorizontalSplitter) {
+            while x + 1 < width && matches!(grid[(x + 1, y)], Empty | HorizontalSplitter) {
                 mark_visited(&mut visited, x + 1, y, LEFT);
                 x += 1;
             }
-            if x + 1 < grid.width {
+            if x + 1 < width {
                 stack.push((x + 1, y, LEFT));
             }
         }
 
         // all the cases to move down:
-        if y + 1 < grid.height
+        if y + 1 < height
             && ((cell == Empty && from_dir == UP)
                 || (cell == Mirror45Degree && from_dir == RIGHT)
                 || (cell == Mirror135Degree && from_dir == LEFT)
@@ -102,11 +120,11 @@ fn count_energized_tiles(grid: &Grid, (start_x, start_y, from_dir): (u8, u8, u8)
         {
             let mut y = y;
             // greedily move down until we hit either the wall, a horizontal splitter or a mirror.
-            while y + 1 < grid.height && matches!(grid.get(x, y + 1), Empty | VerticalSplitter) {
+            while y + 1 < height && matches!(grid[(x, y + 1)], Empty | VerticalSplitter) {
                 mark_visited(&mut visited, x, y + 1, UP);
                 y += 1;
             }
-            if y + 1 < grid.height {
+            if y + 1 < height {
                 stack.push((x, y + 1, UP));
             }
         }
@@ -120,7 +138,7 @@ fn count_energized_tiles(grid: &Grid, (start_x, start_y, from_dir): (u8, u8, u8)
         {
             let mut x = x;
             // greedily move left until we hit either the wall, a vertical splitter or a mirror.
-            while x > 0 && matches!(grid.get(x - 1, y), Empty | HorizontalSplitter) {
+            while x > 0 && matches!(grid[(x - 1, y)], Empty | HorizontalSplitter) {
                 mark_visited(&mut visited, x - 1, y, RIGHT);
                 x -= 1;
             }
@@ -138,7 +156,7 @@ fn count_energized_tiles(grid: &Grid, (start_x, start_y, from_dir): (u8, u8, u8)
         {
             let mut y = y;
             // greedily move up until we hit either the wall, a horizontal splitter or a mirror.
-            while y > 0 && matches!(grid.get(x, y - 1), Empty | VerticalSplitter) {
+            while y > 0 && matches!(grid[(x, y - 1)], Empty | VerticalSplitter) {
                 mark_visited(&mut visited, x, y - 1, DOWN);
                 y -= 1;
             }
@@ -148,24 +166,138 @@ fn count_energized_tiles(grid: &Grid, (start_x, start_y, from_dir): (u8, u8, u8)
         }
     }
 
-    visited.iter().filter(|&&v| v != 0).count()
+    visited
+}
+
+fn count_energized_tiles(grid: &Grid, start: (u8, u8, u8), stats: &mut BeamStats) -> usize {
+    let visited = energize(grid, start, stats);
+    visited.rows().flatten().filter(|&&v| v != 0).count()
+}
+
+// All the starting positions for part 2, one for each edge tile and
+// direction pointing into the grid.
+fn edge_starts(grid: &Grid) -> impl Iterator<Item = (u8, u8, u8)> + '_ {
+    let (width, height) = (grid.width() as u8, grid.height() as u8);
+    let vertical = (0..width).flat_map(move |x| [(x, 0, UP), (x, height - 1, DOWN)]);
+    let horizontal = (0..height).flat_map(move |y| [(0, y, RIGHT), (width - 1, y, LEFT)]);
+    vertical.chain(horizontal)
+}
+
+/// Draws the tiles that part 1's beam energizes in yellow, and the mirrors and splitters in
+/// blue, or white if the beam hits them.
+impl Visualize for Day16 {
+    fn visualize(input: &[u8]) -> anyhow::Result<Image> {
+        let grid = parse_grid(input)?;
+        let visited = energize(&grid, (0, 0, LEFT), &mut BeamStats::default());
+        let mut image = Image::new(grid.width(), grid.height(), [0, 0, 0]);
+        for y in 0..grid.height() {
+            for x in 0..grid.width() {
+                let energized = visited[(x, y)] != 0;
+                let color = match (grid[(x, y)], energized) {
+                    (Cell::Empty, false) => continue,
+                    (Cell::Empty, true) => [255, 192, 0],
+                    (_, false) => [64, 96, 255],
+                    (_, true) => [255, 255, 255],
+                };
+                image.set(x, y, color);
+            }
+        }
+        Ok(image)
+    }
+}
+
+register_day!(16, Day16, "The Floor Will Be Lava", tags: ["grid", "simulation"]);
+
+impl Puzzle for Day16 {
+    type Parsed<'a> = Grid;
+    const ALGORITHM: &'static str = "bitmask-beams-v1";
+
+    fn parse(input: &[u8]) -> anyhow::Result<Grid> {
+        Ok(parse_grid(input)?)
+    }
+
+    fn solve1(grid: &Grid, _: &Token) -> anyhow::Result<Answer> {
+        Ok(count_energized_tiles(grid, (0, 0, LEFT), &mut BeamStats::default()).into())
+    }
+
+    fn solve2(grid: &Grid, _: &Token) -> anyhow::Result<Answer> {
+        // The grid isn't empty, so there's at least one start.
+        Ok(edge_starts(grid)
+            .map(|start| count_energized_tiles(grid, start, &mut BeamStats::default()))
+            .max()
+            .unwrap()
+            .into())
+    }
 }
 
-pub fn part1(input: &str) -> String {
-    let grid = parse_grid(input);
-    count_energized_tiles(&grid, (0, 0, LEFT)).to_string()
+#[derive(Serialize)]
+pub struct StartDetails {
+    x: u8,
+    y: u8,
+    heading: &'static str,
+    energized: usize,
+    #[serde(flatten)]
+    stats: BeamStats,
 }
 
-pub fn part2(input: &str) -> String {
-    let grid = parse_grid(input);
-    let mut max_energized = 0;
-    for x in 0..grid.width {
-        max_energized = max_energized.max(count_energized_tiles(&grid, (x, 0, UP)));
-        max_energized = max_energized.max(count_energized_tiles(&grid, (x, grid.height - 1, DOWN)));
+impl StartDetails {
+    fn new(grid: &Grid, start @ (x, y, from_dir): (u8, u8, u8)) -> Self {
+        let mut stats = BeamStats::default();
+        let energized = count_energized_tiles(grid, start, &mut stats);
+        // The beam heads away from the side it came from.
+        let heading = match from_dir {
+            LEFT => "right",
+            RIGHT => "left",
+            UP => "down",
+            _ => "up",
+        };
+        Self {
+            x,
+            y,
+            heading,
+            energized,
+            stats,
+        }
     }
-    for y in 0..grid.height {
-        max_energized = max_energized.max(count_energized_tiles(&grid, (0, y, RIGHT)));
-        max_energized = max_energized.max(count_energized_tiles(&grid, (grid.width - 1, y, LEFT)));
+}
+
+impl fmt::Display for StartDetails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "({}, {}) heading {}: {} tiles energized, {} beam paths, {} splits, {} closed beams",
+            self.x,
+            self.y,
+            self.heading,
+            self.energized,
+            self.stats.paths,
+            self.stats.splits,
+            self.stats.closed
+        )
+    }
+}
+
+/// Beam statistics for the part 1 start and for the best part 2 start.
+#[derive(Serialize)]
+pub struct Details {
+    part1: StartDetails,
+    best: StartDetails,
+}
+
+impl fmt::Display for Details {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Part 1 start: {}", self.part1)?;
+        write!(f, "Best start: {}", self.best)
     }
-    max_energized.to_string()
+}
+
+pub fn details(input: &str) -> anyhow::Result<Details> {
+    let grid = parse_grid(input.as_bytes())?;
+    let best = edge_starts(&grid)
+        .max_by_key(|&start| count_energized_tiles(&grid, start, &mut BeamStats::default()))
+        .unwrap();
+    Ok(Details {
+        part1: StartDetails::new(&grid, (0, 0, LEFT)),
+        best: StartDetails::new(&grid, best),
+    })
 }
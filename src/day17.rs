@@ -1,34 +1,81 @@
-use std::{cmp::Reverse, collections::BinaryHeap};
+use alloc::{collections::BinaryHeap, vec::Vec};
+use core::cmp::Reverse;
 
-struct Grid {
-    data: Vec<u8>,
-    width: u8,
-    height: u8,
-}
+use anyhow::Context;
+
+use crate::{cancel::Token, grid, parse::ParseError, Answer, Day17, Puzzle};
+
+/// The heat loss of every block.
+pub type Grid = grid::Grid<u8>;
 
-impl Grid {
-    fn get(&self, x: u8, y: u8) -> u8 {
-        self.data[y as usize * self.width as usize + x as usize]
+fn parse_grid(input: &[u8]) -> Result<Grid, ParseError> {
+    let grid = Grid::parse(input, |_, b| {
+        if !b.is_ascii_digit() {
+            return Err(ParseError::invalid("heat loss", b as char));
+        }
+        Ok(b - b'0')
+    })?;
+    // Coordinates are u8s.
+    if grid.width() > u8::MAX as usize || grid.height() > u8::MAX as usize {
+        return Err(ParseError::malformed("the grid is larger than 255x255"));
     }
+    Ok(grid)
 }
 
-fn parse_grid(input: &str) -> Grid {
-    let mut data = vec![];
-    let mut width = 0;
-    let mut height = 0;
-    for line in input.lines() {
-        width = line.len() as u8;
-        height += 1;
-        data.extend(line.bytes().map(|b| b - b'0'));
+// (x, y, dir), where dir is the direction of the last move.
+type Node = (u8, u8, u8);
+
+const NORTH: u8 = 0;
+const SOUTH: u8 = 1;
+const EAST: u8 = 2;
+const WEST: u8 = 3;
+// the start node doesn't have a last move. It never gets stored in the DistMap.
+const START: u8 = 4;
+
+/// The distance to every node, stored in a dense array instead of a HashMap. Indexing into
+/// the array is faster than hashing, and the map would contain every possible key anyways,
+/// so there's not much space wastage by storing every distance.
+///
+/// The layout is (y, x, dir): cells are in row-major order like in `Grid`, and the four
+/// directions of each cell are adjacent.
+struct DistMap {
+    dists: Vec<usize>,
+    width: usize,
+    height: usize,
+}
+
+impl DistMap {
+    fn new(grid: &Grid) -> Self {
+        let (width, height) = (grid.width(), grid.height());
+        Self {
+            dists: vec![usize::MAX; width * height * 4],
+            width,
+            height,
+        }
     }
-    Grid {
-        data,
-        width,
-        height,
+
+    fn idx(&self, (x, y, dir): Node) -> usize {
+        debug_assert!((x as usize) < self.width && (y as usize) < self.height && dir < START);
+        (y as usize * self.width + x as usize) * 4 + dir as usize
+    }
+
+    fn get(&self, node: Node) -> usize {
+        self.dists[self.idx(node)]
+    }
+
+    fn set(&mut self, node: Node, dist: usize) {
+        let idx = self.idx(node);
+        self.dists[idx] = dist;
+    }
+
+    /// The distances to the cell at (x, y) for each direction.
+    fn cell(&self, x: u8, y: u8) -> &[usize] {
+        let idx = self.idx((x, y, NORTH));
+        &self.dists[idx..idx + 4]
     }
 }
 
-fn min_heat_loss(grid: &Grid, min_steps: u8, max_steps: u8) -> usize {
+fn min_heat_loss(grid: &Grid, min_steps: u8, max_steps: u8) -> Option<usize> {
     // conceptually we want to do a dijkstra search on the following graph:
     // the vertex set is [0..width) x [0..height) x { North, South, East, West, Start }
     // each vertex describes one grid cell as well as the direction from its predecessor
@@ -36,43 +83,24 @@ fn min_heat_loss(grid: &Grid, min_steps: u8, max_steps: u8) -> usize {
     // the edge set is the set of all possible moves from one vertex to another.
     //
     // we never fully compute this graph, we just compute the edges on the fly.
-    type Node = (u8, u8, u8);
 
     // we need to use Reverse<usize> as the priority type, because the priority queue is a max-heap.
     type Queue = BinaryHeap<(Reverse<usize>, Node)>;
 
-    // Use a dense array instead of a HashMap. Indexing into the array is faster than hashing,
-    // and the map would contain every possible key anyways, so there's not much space wastage
-    // by storing every distance.
-    type DistMap = Vec<usize>;
-
-    const NORTH: u8 = 0;
-    const SOUTH: u8 = 1;
-    const EAST: u8 = 2;
-    const WEST: u8 = 3;
-    const START: u8 = 4;
-
     // the start node gets the special Start predecessor, so it can go either down or right.
+    let (width, height) = (grid.width() as u8, grid.height() as u8);
     let mut queue = Queue::from_iter([(Reverse(0), (0, 0, START))]);
-    let mut dists = vec![usize::MAX; grid.width as usize * grid.height as usize * 4];
-
-    fn update_dists_and_queue(
-        grid: &Grid,
-        queue: &mut Queue,
-        dists: &mut DistMap,
-        node @ (x, y, dir): Node,
-        dist: usize,
-    ) {
-        let idx = (x as usize * grid.width as usize + y as usize) * 4 + dir as usize;
-        if dist < dists[idx] {
-            dists[idx] = dist;
+    let mut dists = DistMap::new(grid);
+
+    fn update_dists_and_queue(queue: &mut Queue, dists: &mut DistMap, node: Node, dist: usize) {
+        if dist < dists.get(node) {
+            dists.set(node, dist);
             queue.push((Reverse(dist), node));
         }
     }
 
     while let Some((Reverse(dist), (x, y, dir))) = queue.pop() {
-        let dist_idx = (x as usize * grid.width as usize + y as usize) * 4 + dir as usize;
-        if dist > dists[dist_idx] {
+        if dir != START && dist > dists.get((x, y, dir)) {
             continue;
         }
 
@@ -87,75 +115,181 @@ fn min_heat_loss(grid: &Grid, min_steps: u8, max_steps: u8) -> usize {
             // precompute the distances to the closest possible neighbors, so we don't have to do it
             // on each iteration of the loop. Unfortunately, this only saves a few milliseconds.
             let mut north_dist = (1..min_steps)
-                .map(|i| grid.get(x, y - i) as usize)
+                .map(|i| grid[(x, y - i)] as usize)
                 .sum::<usize>();
             for i in min_steps..=max_north {
-                north_dist += grid.get(x, y - i) as usize;
+                north_dist += grid[(x, y - i)] as usize;
                 let neighbor = (x, y - i, NORTH);
                 let neighbor_dist = dist + north_dist;
-                update_dists_and_queue(grid, &mut queue, &mut dists, neighbor, neighbor_dist);
+                update_dists_and_queue(&mut queue, &mut dists, neighbor, neighbor_dist);
             }
         }
 
-        let max_south = max_steps.min(grid.height - y - 1);
+        let max_south = max_steps.min(height - y - 1);
         if max_south >= min_steps && !was_vertical {
             let mut south_dist = (1..min_steps)
-                .map(|i| grid.get(x, y + i) as usize)
+                .map(|i| grid[(x, y + i)] as usize)
                 .sum::<usize>();
             for i in min_steps..=max_south {
-                south_dist += grid.get(x, y + i) as usize;
+                south_dist += grid[(x, y + i)] as usize;
                 let neighbor = (x, y + i, SOUTH);
                 let neighbor_dist = dist + south_dist;
-                update_dists_and_queue(grid, &mut queue, &mut dists, neighbor, neighbor_dist);
+                update_dists_and_queue(&mut queue, &mut dists, neighbor, neighbor_dist);
             }
         }
 
-        let max_east = max_steps.min(grid.width - x - 1);
+        let max_east = max_steps.min(width - x - 1);
         if max_east >= min_steps && !was_horizontal {
             let mut east_dist = (1..min_steps)
-                .map(|i| grid.get(x + i, y) as usize)
+                .map(|i| grid[(x + i, y)] as usize)
                 .sum::<usize>();
             for i in min_steps..=max_east {
-                east_dist += grid.get(x + i, y) as usize;
+                east_dist += grid[(x + i, y)] as usize;
                 let neighbor = (x + i, y, EAST);
                 let neighbor_dist = dist + east_dist;
-                update_dists_and_queue(grid, &mut queue, &mut dists, neighbor, neighbor_dist);
+                update_dists_and_queue(&mut queue, &mut dists, neighbor, neighbor_dist);
             }
         }
 
         let max_west = max_steps.min(x);
         if max_west >= min_steps && !was_horizontal {
             let mut west_dist = (1..min_steps)
-                .map(|i| grid.get(x - i, y) as usize)
+                .map(|i| grid[(x - i, y)] as usize)
                 .sum::<usize>();
             for i in min_steps..=max_west {
-                west_dist += grid.get(x - i, y) as usize;
+                west_dist += grid[(x - i, y)] as usize;
                 let neighbor = (x - i, y, WEST);
                 let neighbor_dist = dist + west_dist;
-                update_dists_and_queue(grid, &mut queue, &mut dists, neighbor, neighbor_dist);
+                update_dists_and_queue(&mut queue, &mut dists, neighbor, neighbor_dist);
             }
         }
     }
 
-    let end = (grid.width - 1, grid.height - 1);
-
     // filter through all the vertices that represent the end cell,
     // and find the one with the minimum distance.
-    let end_idx = (end.1 as usize * grid.width as usize + end.0 as usize) * 4;
-    let end_range = end_idx..end_idx + 4;
-    *dists[end_range]
+    dists
+        .cell(width - 1, height - 1)
         .iter()
         .filter(|&&dist| dist != usize::MAX)
         .min()
-        .unwrap()
+        .copied()
 }
 
-pub fn part1(input: &str) -> String {
-    let grid = parse_grid(input);
-    min_heat_loss(&grid, 1, 3).to_string()
+register_day!(17, Day17, "Clumsy Crucible", tags: ["grid", "shortest-path"]);
+
+impl Puzzle for Day17 {
+    type Parsed<'a> = Grid;
+    const ALGORITHM: &'static str = "dijkstra-v1";
+
+    fn parse(input: &[u8]) -> anyhow::Result<Grid> {
+        Ok(parse_grid(input)?)
+    }
+
+    fn solve1(grid: &Grid, _: &Token) -> anyhow::Result<Answer> {
+        let loss = min_heat_loss(grid, 1, 3).context("the crucible can't reach the end")?;
+        Ok(loss.into())
+    }
+
+    fn solve2(grid: &Grid, _: &Token) -> anyhow::Result<Answer> {
+        let loss = min_heat_loss(grid, 4, 10).context("the crucible can't reach the end")?;
+        Ok(loss.into())
+    }
 }
 
-pub fn part2(input: &str) -> String {
-    let grid = parse_grid(input);
-    min_heat_loss(&grid, 4, 10).to_string()
+#[cfg(test)]
+mod tests {
+    use alloc::{string::String, vec};
+
+    use super::*;
+
+    // A plain Dijkstra over (x, y, dir, steps in a row), one step at a time, to check
+    // `min_heat_loss` against.
+    fn reference(grid: &Grid, min_steps: usize, max_steps: usize) -> Option<usize> {
+        let (width, height) = (grid.width() as isize, grid.height() as isize);
+        let deltas = [(0, -1), (0, 1), (1, 0), (-1, 0)];
+        let mut best = vec![usize::MAX; grid.width() * grid.height() * 4 * (max_steps + 1)];
+        let idx = |x: isize, y: isize, dir: usize, steps: usize| {
+            (((y * width + x) as usize * 4 + dir) * (max_steps + 1)) + steps
+        };
+        let mut queue = BinaryHeap::new();
+        for dir in [1, 2] {
+            queue.push((Reverse(0), 0, 0, dir, 0));
+        }
+        while let Some((Reverse(dist), x, y, dir, steps)) = queue.pop() {
+            if x == width - 1 && y == height - 1 && steps >= min_steps {
+                return Some(dist);
+            }
+            for (next, &(dx, dy)) in deltas.iter().enumerate() {
+                let reverse = next ^ 1 == dir;
+                let straight = next == dir;
+                if reverse || (straight && steps == max_steps) {
+                    continue;
+                }
+                // Turning needs `min_steps` first, except from the start.
+                if !straight && steps < min_steps && steps > 0 {
+                    continue;
+                }
+                let (nx, ny) = (x + dx, y + dy);
+                if !(0..width).contains(&nx) || !(0..height).contains(&ny) {
+                    continue;
+                }
+                let next_steps = if straight { steps + 1 } else { 1 };
+                let next_dist = dist + grid[(nx as usize, ny as usize)] as usize;
+                let i = idx(nx, ny, next, next_steps);
+                if next_dist < best[i] {
+                    best[i] = next_dist;
+                    queue.push((Reverse(next_dist), nx, ny, next, next_steps));
+                }
+            }
+        }
+        None
+    }
+
+    fn check(input: &str) -> (Option<usize>, Option<usize>) {
+        let grid = parse_grid(input.as_bytes()).unwrap();
+        let part1 = min_heat_loss(&grid, 1, 3);
+        let part2 = min_heat_loss(&grid, 4, 10);
+        assert_eq!(part1, reference(&grid, 1, 3), "{input}");
+        assert_eq!(part2, reference(&grid, 4, 10), "{input}");
+        (part1, part2)
+    }
+
+    #[test]
+    fn example() {
+        let input = "2413432311323\n3215453535623\n3255245654254\n3446585845452\n\
+                     4546657867536\n1438598798454\n4457876987766\n3637877979653\n\
+                     4654967986887\n4564679986453\n1224686865563\n2546548887735\n\
+                     4322674655533";
+        assert_eq!(check(input), (Some(102), Some(94)));
+    }
+
+    // The second example of part 2, which is wider than it's tall, so mixing up the width
+    // and the height in `DistMap::idx` would put its nodes in the wrong places.
+    #[test]
+    fn non_square() {
+        let input = "111111111111\n999999999991\n999999999991\n999999999991\n999999999991";
+        let (part1, part2) = check(input);
+        assert_eq!(part2, Some(71));
+        assert_eq!(part1, Some(59));
+
+        // Its transpose, and a few pseudo-random grids of other shapes.
+        let transposed: Vec<String> = (0..12)
+            .map(|x| input.lines().map(|row| &row[x..x + 1]).collect())
+            .collect();
+        assert_eq!(check(&transposed.join("\n")), (part1, part2));
+        let mut state = 17u64;
+        for (width, height) in [(1, 9), (9, 1), (2, 30), (30, 2), (11, 4), (5, 23)] {
+            let rows: Vec<String> = (0..height)
+                .map(|_| {
+                    (0..width)
+                        .map(|_| {
+                            state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+                            char::from(b'1' + (state >> 33) as u8 % 9)
+                        })
+                        .collect()
+                })
+                .collect();
+            check(&rows.join("\n"));
+        }
+    }
 }
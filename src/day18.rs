@@ -1,13 +1,14 @@
-#[derive(Clone, Copy, PartialEq, Eq)]
-enum Dir {
-    Up,
-    Down,
-    Left,
-    Right,
-}
+use alloc::vec::Vec;
+
+use crate::{
+    cancel::Token,
+    geometry::{Dir, Point},
+    parse::{self, ParseError},
+    Answer, Day18, Puzzle,
+};
 
 #[derive(Clone, Copy)]
-struct Trench {
+pub struct Trench {
     dir: Dir,
     len: u8,
     rgb: u32,
@@ -19,28 +20,28 @@ struct Instruction {
     len: usize,
 }
 
-fn parse_trench(line: &str) -> Trench {
-    let (dir, rest) = line.split_once(' ').unwrap();
-    let (len, rest) = rest.split_once(' ').unwrap();
+fn parse_trench(line: &str) -> Result<Trench, ParseError> {
+    let (dir, rest) = parse::split_once(line, " ")?;
+    let (len, rest) = parse::split_once(rest, " ")?;
     let rgb = rest
         .strip_prefix("(#")
         .and_then(|rest| rest.strip_suffix(')'))
-        .unwrap();
+        .ok_or_else(|| ParseError::invalid("color", rest))?;
 
     let dir = match dir {
-        "U" => Dir::Up,
-        "D" => Dir::Down,
-        "L" => Dir::Left,
-        "R" => Dir::Right,
-        _ => unreachable!("Invalid direction"),
+        "U" => Dir::North,
+        "D" => Dir::South,
+        "L" => Dir::West,
+        "R" => Dir::East,
+        _ => return Err(ParseError::invalid("direction", dir)),
     };
-    let len = len.parse().unwrap();
-    let rgb = u32::from_str_radix(rgb, 16).unwrap();
-    Trench { dir, len, rgb }
+    let len = parse::num(len)?;
+    let rgb = u32::from_str_radix(rgb, 16).map_err(|_| ParseError::invalid("color", rgb))?;
+    Ok(Trench { dir, len, rgb })
 }
 
-fn parse_input(input: &str) -> impl Iterator<Item = Trench> + '_ {
-    input.lines().map(|s| parse_trench(s.trim()))
+fn parse_input(input: &str) -> Result<Vec<Trench>, ParseError> {
+    parse::lines(input, |s| parse_trench(s.trim()))
 }
 
 // Very similar area calculation to part 10, except that this time it has to include
@@ -49,20 +50,15 @@ fn parse_input(input: &str) -> impl Iterator<Item = Trench> + '_ {
 fn enclosed_area(trenches: &[Instruction]) -> usize {
     let mut area = 0isize;
     let mut perimeter = 0;
-    let mut pos = (0, 0);
+    let mut pos = Point::default();
 
     // Simple shoelace formula implementation.
     for trench in trenches {
         perimeter += trench.len;
 
-        let (x_i, y_i) = pos;
-        match trench.dir {
-            Dir::Up => pos.1 -= trench.len as isize,
-            Dir::Down => pos.1 += trench.len as isize,
-            Dir::Left => pos.0 -= trench.len as isize,
-            Dir::Right => pos.0 += trench.len as isize,
-        }
-        let (x_j, y_j) = pos;
+        let Point { x: x_i, y: y_i } = pos;
+        pos = pos.step(trench.dir, trench.len as isize);
+        let Point { x: x_j, y: y_j } = pos;
 
         area += x_i * y_j - x_j * y_i;
     }
@@ -75,24 +71,46 @@ fn enclosed_area(trenches: &[Instruction]) -> usize {
     (area.unsigned_abs() + perimeter) / 2 + 1
 }
 
-pub fn part1(input: &str) -> String {
-    let trenches = parse_input(input);
-    let insts = trenches
-        .map(|t| Instruction {
-            dir: t.dir,
-            len: t.len as usize,
-        })
-        .collect::<Vec<_>>();
-    enclosed_area(&insts).to_string()
-}
+register_day!(18, Day18, "Lavaduct Lagoon", tags: ["geometry"]);
 
-pub fn part2(input: &str) -> String {
-    let trenches = parse_input(input);
-    let insts = trenches
-        .map(|t| Instruction {
-            dir: [Dir::Up, Dir::Left, Dir::Down, Dir::Right][(t.rgb & 0x0F) as usize],
-            len: (t.rgb >> 4) as usize,
-        })
-        .collect::<Vec<_>>();
-    enclosed_area(&insts).to_string()
+impl Puzzle for Day18 {
+    type Parsed<'a> = Vec<Trench>;
+    const ALGORITHM: &'static str = "shoelace-pick-v1";
+
+    fn parse(input: &[u8]) -> anyhow::Result<Vec<Trench>> {
+        let input = parse::utf8(input)?;
+        Ok(parse_input(input)?)
+    }
+
+    fn solve1(trenches: &Vec<Trench>, _: &Token) -> anyhow::Result<Answer> {
+        let insts = trenches
+            .iter()
+            .map(|t| Instruction {
+                dir: t.dir,
+                len: t.len as usize,
+            })
+            .collect::<Vec<_>>();
+        Ok(enclosed_area(&insts).into())
+    }
+
+    fn solve2(trenches: &Vec<Trench>, _: &Token) -> anyhow::Result<Answer> {
+        let insts = trenches
+            .iter()
+            .enumerate()
+            .map(|(i, t)| {
+                let dir = match t.rgb & 0x0F {
+                    0 => Dir::East,
+                    1 => Dir::South,
+                    2 => Dir::West,
+                    3 => Dir::North,
+                    d => anyhow::bail!("invalid direction digit {} in trench {}", d, i + 1),
+                };
+                Ok(Instruction {
+                    dir,
+                    len: (t.rgb >> 4) as usize,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(enclosed_area(&insts).into())
+    }
 }
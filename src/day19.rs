@@ -1,21 +1,31 @@
-use ahash::AHashMap;
+use alloc::vec::Vec;
+
 use enum_map::{enum_map, Enum, EnumMap};
+use serde::Serialize;
+
+use crate::{
+    cancel::Token,
+    collections::HashMap,
+    hyperrect::Hyperrect,
+    parse::{self, ParseError},
+    Answer, Day19, Puzzle,
+};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
-enum Category {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum, Serialize)]
+pub enum Category {
     X,
     M,
     A,
     S,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 enum Op {
     Less,
     Greater,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 struct Rule<'a> {
     category: Category,
     op: Op,
@@ -32,84 +42,110 @@ impl Rule<'_> {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-struct Workflow<'a> {
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Workflow<'a> {
     name: &'a str,
     rules: Vec<Rule<'a>>,
     fallback: &'a str,
 }
 
-fn parse_workflow(line: &str) -> Workflow<'_> {
-    let (name, rest) = line.split_once('{').unwrap();
-    let mut rules = rest.strip_suffix('}').unwrap().split(',');
+fn parse_workflow(line: &str) -> Result<Workflow<'_>, ParseError> {
+    let (name, rest) = parse::split_once(line, "{")?;
+    let rest = parse::strip_suffix(rest, "}")?;
+    // `split` always yields at least one item.
+    let mut rules = rest.split(',');
     let fallback = rules.next_back().unwrap();
     let rules = rules
         .map(|rule| {
-            let (category, rest) = rule.split_at(1);
-            let (op, rest) = rest.split_at(1);
-            let (value, goto) = rest.split_once(':').unwrap();
-            let value: usize = value.parse().unwrap();
-            let category = match category {
-                "x" => Category::X,
-                "m" => Category::M,
-                "a" => Category::A,
-                "s" => Category::S,
-                _ => unreachable!("invalid category"),
+            let (condition, goto) = parse::split_once(rule, ":")?;
+            let mut chars = condition.chars();
+            let category = match chars.next() {
+                Some('x') => Category::X,
+                Some('m') => Category::M,
+                Some('a') => Category::A,
+                Some('s') => Category::S,
+                _ => return Err(ParseError::invalid("category in rule", rule)),
             };
-            let op = match op {
-                "<" => Op::Less,
-                ">" => Op::Greater,
-                _ => unreachable!("invalid operator"),
+            let op = match chars.next() {
+                Some('<') => Op::Less,
+                Some('>') => Op::Greater,
+                _ => return Err(ParseError::invalid("operator in rule", rule)),
             };
-            Rule {
+            let value = parse::num(chars.as_str())?;
+            Ok(Rule {
                 category,
                 op,
                 value,
                 goto,
-            }
+            })
         })
-        .collect();
+        .collect::<Result<_, ParseError>>()?;
 
-    Workflow {
+    Ok(Workflow {
         name,
         rules,
         fallback,
-    }
+    })
 }
 
-type Part = EnumMap<Category, usize>;
-
-fn parse_part(line: &str) -> Part {
-    let line = line.strip_prefix("{x=").unwrap();
-    let (x, rest) = line.split_once(",m=").unwrap();
-    let (m, rest) = rest.split_once(",a=").unwrap();
-    let (a, rest) = rest.split_once(",s=").unwrap();
-    let s = rest.strip_suffix('}').unwrap();
-    enum_map! {
-        Category::X => x.parse().unwrap(),
-        Category::M => m.parse().unwrap(),
-        Category::A => a.parse().unwrap(),
-        Category::S => s.parse().unwrap(),
-    }
+pub type Part = EnumMap<Category, usize>;
+
+fn parse_part(line: &str) -> Result<Part, ParseError> {
+    let line = parse::strip_prefix(line, "{x=")?;
+    let (x, rest) = parse::split_once(line, ",m=")?;
+    let (m, rest) = parse::split_once(rest, ",a=")?;
+    let (a, rest) = parse::split_once(rest, ",s=")?;
+    let s = parse::strip_suffix(rest, "}")?;
+    let [x, m, a, s] = [
+        parse::num(x)?,
+        parse::num(m)?,
+        parse::num(a)?,
+        parse::num(s)?,
+    ];
+    Ok(enum_map! {
+        Category::X => x,
+        Category::M => m,
+        Category::A => a,
+        Category::S => s,
+    })
 }
 
-type WorkflowMap<'a> = AHashMap<&'a str, Workflow<'a>>;
+pub type WorkflowMap<'a> = HashMap<&'a str, Workflow<'a>>;
 
-fn parse_input(input: &str) -> (WorkflowMap<'_>, Vec<Part>) {
-    let mut lines = input.lines();
+fn parse_input(input: &str) -> Result<(WorkflowMap<'_>, Vec<Part>), ParseError> {
+    let mut lines = input.lines().enumerate();
     let workflows = lines
         .by_ref()
-        .take_while(|line| !line.is_empty())
-        .map(parse_workflow)
-        .map(|workflow| (workflow.name, workflow))
-        .collect();
-    let parts = lines.map(parse_part).collect();
-    (workflows, parts)
-}
+        .take_while(|(_, line)| !line.is_empty())
+        .map(|(i, line)| {
+            let workflow = parse_workflow(line).map_err(|e| e.at_line(i + 1))?;
+            Ok((workflow.name, workflow))
+        })
+        .collect::<Result<WorkflowMap, ParseError>>()?;
+    let parts = lines
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| parse_part(line).map_err(|e| e.at_line(i + 1)))
+        .collect::<Result<_, _>>()?;
 
-pub fn part1(input: &str) -> String {
-    let (workflows, parts) = parse_input(input);
+    // Both parts look workflows up by name, so make sure that they all exist.
+    if !workflows.contains_key("in") {
+        return Err(ParseError::malformed("there's no \"in\" workflow"));
+    }
+    for workflow in workflows.values() {
+        let gotos = workflow.rules.iter().map(|rule| rule.goto);
+        for goto in gotos.chain([workflow.fallback]) {
+            if !matches!(goto, "A" | "R") && !workflows.contains_key(goto) {
+                return Err(ParseError::malformed(format!(
+                    "workflow {:?} sends parts to the unknown workflow {:?}",
+                    workflow.name, goto
+                )));
+            }
+        }
+    }
+    Ok((workflows, parts))
+}
 
+fn total_rating(workflows: &WorkflowMap, parts: &[Part]) -> usize {
     let mut total = 0;
     'outer: for part in parts {
         let mut workflow = &workflows["in"];
@@ -118,7 +154,7 @@ pub fn part1(input: &str) -> String {
             let next = workflow
                 .rules
                 .iter()
-                .find(|rule| rule.matches(&part))
+                .find(|rule| rule.matches(part))
                 .map(|rule| rule.goto)
                 .unwrap_or(workflow.fallback);
 
@@ -136,45 +172,26 @@ pub fn part1(input: &str) -> String {
         }
     }
 
-    total.to_string()
+    total
 }
 
-type Ranges = EnumMap<Category, (usize, usize)>;
-
-/// Tries to split the ranges into two parts, one that fits the rule,
-/// and one that doesn't. If either of the parts is empty, it returns None.
-fn split_ranges(ranges: Ranges, rule: Rule) -> Option<(Ranges, Ranges)> {
-    let (min, max) = ranges[rule.category];
-    if rule.op == Op::Greater && max > rule.value {
-        // Part of the ranges that fits the rule.
-        let mut inside = ranges;
-        inside[rule.category].0 = rule.value + 1;
-        // Part of the ranges that doesn't fit the rule.
-        let mut outside = ranges;
-        outside[rule.category].1 = rule.value;
-
-        Some((inside, outside))
-    } else if rule.op == Op::Less && min < rule.value {
-        let mut inside = ranges;
-        inside[rule.category].1 = rule.value - 1;
-
-        let mut outside = ranges;
-        outside[rule.category].0 = rule.value;
-
-        Some((inside, outside))
-    } else {
-        // No overlap between the ranges and the rule, so return None.
-        None
-    }
-}
+/// The possible ratings of a part, with one axis per category.
+type Ranges = Hyperrect<4>;
 
-/// Counts the number of values in the ranges.
-/// e.g. for the full range, this would be 4000**4.
-fn ranges_size(ranges: &Ranges) -> usize {
-    ranges.values().map(|&(min, max)| max + 1 - min).product()
+/// Splits the ranges into the part that fits the rule, and the part that
+/// doesn't. Either of them is None if it's empty.
+fn split_ranges(ranges: Ranges, rule: Rule) -> (Option<Ranges>, Option<Ranges>) {
+    let axis = rule.category.into_usize();
+    match rule.op {
+        Op::Less => ranges.split_at(axis, rule.value),
+        Op::Greater => {
+            let (outside, inside) = ranges.split_at(axis, rule.value + 1);
+            (inside, outside)
+        }
+    }
 }
 
-pub fn part2(input: &str) -> String {
+fn accepted_combinations(workflows: &WorkflowMap) -> usize {
     // Recursively calculate the number of valid parts for the workflow `node`,
     // This can be done using a simple DFS, because the input is just
     // a tree of rules. The `ranges` parameter is used to constrain
@@ -184,27 +201,31 @@ pub fn part2(input: &str) -> String {
         let w = &workflows[node];
 
         for &rule in &w.rules {
-            // Only process the rules that actually overlap the range.
-            if let Some((inside, outside)) = split_ranges(ranges, rule) {
-                // The current rule already processes all of `inside`,
-                // so the next rules should only process `outside` to prevent
-                // duplicates.
-                ranges = outside;
-                // If the rule goes to "A", accept the entire range.
-                // If it goes to "R", reject the entire range.
-                // Otherwise, recurse into the next workflow.
+            let (inside, outside) = split_ranges(ranges, rule);
+            // If the rule goes to "A", accept the entire range.
+            // If it goes to "R", reject the entire range.
+            // Otherwise, recurse into the next workflow.
+            if let Some(inside) = inside {
                 if rule.goto == "A" {
-                    total += ranges_size(&inside);
+                    total += inside.volume();
                 } else if rule.goto != "R" {
                     total += rec(workflows, rule.goto, inside);
                 }
             }
+            // The current rule already processes all of `inside`,
+            // so the next rules should only process `outside` to prevent
+            // duplicates. If nothing is left, neither the next rules
+            // nor the fallback get anything.
+            match outside {
+                Some(outside) => ranges = outside,
+                None => return total,
+            }
         }
         // At this point, what's left in `ranges` will all
         // be sent to the fallback, so we can handle it as
         // a sort of unconditional rule.
         if w.fallback == "A" {
-            total += ranges_size(&ranges);
+            total += ranges.volume();
         } else if w.fallback != "R" {
             total += rec(workflows, w.fallback, ranges);
         }
@@ -212,17 +233,77 @@ pub fn part2(input: &str) -> String {
         total
     }
 
-    let (workflows, _) = parse_input(input);
-
-    rec(
-        &workflows,
-        "in",
-        enum_map! {
-            Category::X => (1, 4000),
-            Category::M => (1, 4000),
-            Category::A => (1, 4000),
-            Category::S => (1, 4000),
-        },
-    )
-    .to_string()
+    rec(workflows, "in", Hyperrect::new([(1, 4000); 4]))
+}
+
+/// How many workflows, rules and parts the input has. Inputs with the same numbers can
+/// still route parts differently, but different numbers rule out a mixed-up input.
+pub fn shape(input: &str) -> anyhow::Result<Vec<(&'static str, usize)>> {
+    let (workflows, parts) = parse_input(input)?;
+    let rules = workflows.values().map(|w| w.rules.len()).sum();
+    Ok(vec![
+        ("workflows", workflows.len()),
+        ("rules", rules),
+        ("parts", parts.len()),
+    ])
+}
+
+register_day!(19, Day19, "Aplenty", tags: ["parsing", "intervals"]);
+
+impl Puzzle for Day19 {
+    type Parsed<'a> = (WorkflowMap<'a>, Vec<Part>);
+    const ALGORITHM: &'static str = "hyperrect-split-v1";
+
+    fn parse(input: &[u8]) -> anyhow::Result<(WorkflowMap<'_>, Vec<Part>)> {
+        let input = parse::utf8(input)?;
+        Ok(parse_input(input)?)
+    }
+
+    fn solve1(
+        (workflows, parts): &(WorkflowMap<'_>, Vec<Part>),
+        _: &Token,
+    ) -> anyhow::Result<Answer> {
+        Ok(total_rating(workflows, parts).into())
+    }
+
+    fn solve2((workflows, _): &(WorkflowMap<'_>, Vec<Part>), _: &Token) -> anyhow::Result<Answer> {
+        Ok(accepted_combinations(workflows).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "px{a<2006:qkq,m>2090:A,rfg}\npv{a>1716:R,A}\nlnx{m>1548:A,A}\n\
+                           rfg{s<537:gd,x>2440:R,A}\nqs{s>3448:A,lnx}\nqkq{x<1416:A,crn}\n\
+                           crn{x>2662:A,R}\nin{s<1351:px,qqz}\nqqz{s>2770:qs,m<1801:hdj,R}\n\
+                           gd{a>3333:R,R}\nhdj{m>838:A,pv}\n\n{x=787,m=2655,a=1222,s=2876}\n\
+                           {x=1679,m=44,a=2067,s=496}\n{x=2036,m=264,a=79,s=2244}\n\
+                           {x=2461,m=1339,a=466,s=291}\n{x=2127,m=1623,a=2188,s=1013}";
+
+    #[test]
+    fn example() {
+        let (workflows, parts) = parse_input(EXAMPLE).unwrap();
+        assert_eq!(total_rating(&workflows, &parts), 19114);
+        assert_eq!(accepted_combinations(&workflows), 167409079868000);
+    }
+
+    // Rules at and next to the ends of the ratings, which leave one side of a split empty.
+    #[test]
+    fn boundary_rules() {
+        let count = |workflows: &str| {
+            let input = format!("{workflows}\n\n");
+            let (workflows, _) = parse_input(&input).unwrap();
+            accepted_combinations(&workflows)
+        };
+        let all = 4000usize.pow(4);
+        assert_eq!(count("in{x<1:A,R}"), 0);
+        assert_eq!(count("in{x<2:A,R}"), all / 4000);
+        assert_eq!(count("in{x>4000:A,R}"), 0);
+        assert_eq!(count("in{x>3999:A,R}"), all / 4000);
+        assert_eq!(count("in{x>0:A,R}"), all);
+        assert_eq!(count("in{x<4001:A,R}"), all);
+        assert_eq!(count("in{x<2001:R,m>2000:R,A}"), all / 4);
+    }
 }
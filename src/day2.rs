@@ -1,41 +1,57 @@
-struct Round {
-    red: usize,
-    green: usize,
-    blue: usize,
+use alloc::vec::Vec;
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "std")]
+use crate::stream::Lines;
+use crate::{
+    cancel::Token,
+    parse::{self, ParseError},
+    Answer, Day2, Puzzle,
+};
+
+/// The cubes of each color shown in one round of a game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Round {
+    pub red: usize,
+    pub green: usize,
+    pub blue: usize,
 }
 
-struct Game {
-    num: usize,
-    rounds: Vec<Round>,
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Game {
+    /// The number after "Game", which part 1 adds up.
+    pub num: usize,
+    pub rounds: Vec<Round>,
 }
 
-fn parse_round(s: &str) -> Round {
+fn parse_round(s: &str) -> Result<Round, ParseError> {
     let (mut red, mut green, mut blue) = (0, 0, 0);
 
     for part in s.split(", ") {
-        let (num, color) = part.split_once(' ').unwrap();
-        let num: usize = num.parse().unwrap();
+        let (num, color) = parse::split_once(part, " ")?;
+        let num: usize = parse::num(num)?;
 
         match color {
             "red" => red += num,
             "green" => green += num,
             "blue" => blue += num,
-            _ => unreachable!(),
+            _ => return Err(ParseError::invalid("color", color)),
         }
     }
 
-    Round { red, green, blue }
+    Ok(Round { red, green, blue })
 }
 
-fn parse_game(line: &str) -> Game {
-    let s = line.strip_prefix("Game ").unwrap();
-    let (num, s) = s.split_once(": ").unwrap();
-    let rounds = s.split("; ").map(parse_round).collect();
+fn parse_game(line: &str) -> Result<Game, ParseError> {
+    let s = parse::strip_prefix(line, "Game ")?;
+    let (num, s) = parse::split_once(s, ": ")?;
+    let rounds = s.split("; ").map(parse_round).collect::<Result<_, _>>()?;
 
-    Game {
-        num: num.parse().unwrap(),
+    Ok(Game {
+        num: parse::num(num)?,
         rounds,
-    }
+    })
 }
 
 fn is_game_possible(game: &Game, red: usize, green: usize, blue: usize) -> bool {
@@ -44,18 +60,29 @@ fn is_game_possible(game: &Game, red: usize, green: usize, blue: usize) -> bool
         .all(|r| r.red <= red && r.green <= green && r.blue <= blue)
 }
 
-fn parse_games(input: &str) -> Vec<Game> {
-    input.trim().lines().map(parse_game).collect()
+/// The games of the input, one per line, in order. This is what `run-day --dump-parsed`
+/// writes out for day 2.
+pub fn parse_games(input: &str) -> Result<Vec<Game>, ParseError> {
+    parse::lines(input, parse_game)
 }
 
-pub fn part1(input: &str) -> String {
-    let games = parse_games(input);
+fn possible_sum(games: &[Game], red: usize, green: usize, blue: usize) -> usize {
     games
         .iter()
-        .filter(|g| is_game_possible(g, 12, 13, 14))
+        .filter(|g| is_game_possible(g, red, green, blue))
         .map(|g| g.num)
-        .sum::<usize>()
-        .to_string()
+        .sum()
+}
+
+/// The sum of the numbers of the games that would have been possible with `red` red,
+/// `green` green and `blue` blue cubes in the bag. Part 1 asks this for 12, 13 and 14.
+pub fn possible_games(
+    input: &str,
+    red: usize,
+    green: usize,
+    blue: usize,
+) -> Result<usize, ParseError> {
+    Ok(possible_sum(&parse_games(input)?, red, green, blue))
 }
 
 fn min_power(game: &Game) -> usize {
@@ -66,7 +93,51 @@ fn min_power(game: &Game) -> usize {
     red * green * blue
 }
 
-pub fn part2(input: &str) -> String {
-    let games = parse_games(input);
-    games.iter().map(min_power).sum::<usize>().to_string()
+/// Solves `part` on a streamed input, one game at a time.
+#[cfg(feature = "std")]
+pub fn stream(part: usize, lines: &mut Lines<'_>) -> anyhow::Result<Answer> {
+    let score: fn(&Game) -> usize = match part {
+        1 => |g: &Game| {
+            if is_game_possible(g, 12, 13, 14) {
+                g.num
+            } else {
+                0
+            }
+        },
+        2 => min_power,
+        _ => panic!("there is no part {}", part),
+    };
+    let mut sum = 0;
+    lines.for_each(|line| {
+        sum += score(&parse_game(line)?);
+        Ok(())
+    })?;
+    Ok(sum.into())
+}
+
+/// How many games and rounds the input has, for the runner's `diff` command.
+pub fn shape(input: &str) -> anyhow::Result<Vec<(&'static str, usize)>> {
+    let games = parse_games(input)?;
+    let rounds = games.iter().map(|g| g.rounds.len()).sum();
+    Ok(vec![("games", games.len()), ("rounds", rounds)])
+}
+
+register_day!(2, Day2, "Cube Conundrum", tags: ["parsing"]);
+
+impl Puzzle for Day2 {
+    type Parsed<'a> = Vec<Game>;
+    const ALGORITHM: &'static str = "max-cubes-per-color-v1";
+
+    fn parse(input: &[u8]) -> anyhow::Result<Vec<Game>> {
+        let input = parse::utf8(input)?;
+        Ok(parse_games(input)?)
+    }
+
+    fn solve1(games: &Vec<Game>, _: &Token) -> anyhow::Result<Answer> {
+        Ok(possible_sum(games, 12, 13, 14).into())
+    }
+
+    fn solve2(games: &Vec<Game>, _: &Token) -> anyhow::Result<Answer> {
+        Ok(games.iter().map(min_power).sum::<usize>().into())
+    }
 }
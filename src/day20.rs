@@ -1,8 +1,18 @@
-use std::collections::VecDeque;
+use alloc::{collections::VecDeque, vec::Vec};
+use core::fmt;
 
-use ahash::AHashMap;
+use anyhow::Context;
+use serde::Serialize;
 use smallvec::SmallVec;
 
+use crate::{
+    cancel::{Cancelled, Token},
+    collections::HashMap,
+    explain, math,
+    parse::{self, ParseError},
+    Answer, Day20, Puzzle,
+};
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 enum Pulse {
     Low,
@@ -20,7 +30,8 @@ enum Module {
     Output,
 }
 #[derive(Clone, PartialEq, Eq)]
-struct Network {
+pub struct Network<'a> {
+    labels: Vec<&'a str>,
     modules: Vec<Module>,
     // The max outdegree seems to be 7, so we use a SmallVec
     // to avoid heap allocations. If there was ever a node
@@ -31,36 +42,49 @@ struct Network {
     rx_idx: Option<usize>,
 }
 
-fn parse_network(input: &str) -> Network {
+fn parse_network(input: &str) -> Result<Network<'_>, ParseError> {
+    let lines = || {
+        input
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+    };
+    let mut labels = Vec::new();
     let mut modules = Vec::new();
     // We only need this map during parsing, to find
     // the index associated with a node.
-    let mut indices = AHashMap::new();
+    let mut indices = HashMap::default();
     let mut preds = Vec::new();
     let mut connections = Vec::new();
 
     // First pass: parse all nodes and create the indices.
-    for line in input.lines() {
-        let (label, _) = line.split_once(" -> ").unwrap();
+    for (i, line) in lines() {
+        let (label, _) = parse::split_once(line, " -> ").map_err(|e| e.at_line(i + 1))?;
         let (label, module) = if label == "broadcaster" {
             (label, Module::Broadcast)
         } else if let Some(label) = label.strip_prefix('%') {
             (label, Module::FlipFlop(false))
         } else {
             (
-                label.strip_prefix('&').unwrap(),
+                parse::strip_prefix(label, "&").map_err(|e| e.at_line(i + 1))?,
                 // We initialize the conjunctions with all bits set,
                 // and set its predecessors bits to 0 during the second pass.
                 Module::Conjunction(u64::MAX),
             )
         };
-        indices.insert(label, modules.len());
+        // The second pass relies on the modules being numbered in the order of the lines.
+        if indices.insert(label, modules.len()).is_some() {
+            let message = format!("module {:?} is defined twice", label);
+            return Err(ParseError::malformed(message).at_line(i + 1));
+        }
+        labels.push(label);
         modules.push(module);
         preds.push(SmallVec::new());
     }
 
     // Second pass: parse all connections and initialize conjunction bitsets.
-    for line in input.lines() {
+    for (i, line) in lines() {
+        // The first pass already checked that this works.
         let (label, out) = line.split_once(" -> ").unwrap();
         let label = label.trim_start_matches(['%', '&']);
         let idx = indices[label];
@@ -74,6 +98,7 @@ fn parse_network(input: &str) -> Network {
                 None => {
                     let idx = modules.len();
                     indices.insert(out_edge, idx);
+                    labels.push(out_edge);
                     modules.push(Module::Output);
                     preds.push(SmallVec::new());
                     idx
@@ -84,32 +109,49 @@ fn parse_network(input: &str) -> Network {
             // If the dest node is a conjunction, then we need to clear
             // its bit corresponding to the source node.
             if let Module::Conjunction(mask) = &mut modules[out_idx] {
-                *mask &= !(1u64.checked_shl(idx as u32).expect("too many nodes"));
+                let bit = 1u64.checked_shl(idx as u32).ok_or_else(|| {
+                    let message = format!(
+                        "conjunction {:?} has input {:?}, but only the first 64 modules can be inputs of conjunctions",
+                        labels[out_idx], label
+                    );
+                    ParseError::malformed(message).at_line(i + 1)
+                })?;
+                *mask &= !bit;
             }
         }
         connections.push(out_indices);
     }
 
-    Network {
+    let broadcast_idx = *indices
+        .get("broadcaster")
+        .ok_or_else(|| ParseError::malformed("there's no broadcaster module"))?;
+    Ok(Network {
+        labels,
         modules,
         connections,
         preds,
-        broadcast_idx: indices["broadcaster"],
+        broadcast_idx,
         rx_idx: indices.get("rx").copied(),
-    }
+    })
 }
 
-pub fn part1(input: &str) -> String {
-    let mut network = parse_network(input);
-    let mut queue = VecDeque::new();
-    let mut low_pulses = 0;
-    let mut high_pulses = 0;
+// The button gets a dummy index, since the modules don't care about their predecessor
+// unless they're conjunctions.
+const BUTTON: usize = usize::MAX;
 
-    for _ in 0..1000 {
-        queue.push_back((usize::MAX, network.broadcast_idx, Pulse::Low));
-        low_pulses += 1;
+impl Network<'_> {
+    /// Presses the button once, calling `observe(from, to, pulse)` for every pulse
+    /// that gets sent, including the one from the button to the broadcaster.
+    fn press(
+        &mut self,
+        queue: &mut VecDeque<(usize, usize, Pulse)>,
+        mut observe: impl FnMut(usize, usize, Pulse),
+    ) {
+        // Each queue element has the form (predecessor, node, pulse).
+        queue.push_back((BUTTON, self.broadcast_idx, Pulse::Low));
         while let Some((pred, node_idx, pulse)) = queue.pop_front() {
-            let out_signal = match &mut network.modules[node_idx] {
+            observe(pred, node_idx, pulse);
+            let out_signal = match &mut self.modules[node_idx] {
                 Module::FlipFlop(b) => {
                     if pulse == Pulse::High {
                         continue;
@@ -138,22 +180,71 @@ pub fn part1(input: &str) -> String {
                 Module::Broadcast => pulse,
                 Module::Output => continue,
             };
-            for &out_idx in &network.connections[node_idx] {
+            for &out_idx in &self.connections[node_idx] {
                 queue.push_back((node_idx, out_idx, out_signal));
             }
-            if out_signal == Pulse::Low {
-                low_pulses += network.connections[node_idx].len();
-            } else {
-                high_pulses += network.connections[node_idx].len();
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct PulseCounts {
+    low: usize,
+    high: usize,
+}
+
+impl PulseCounts {
+    fn add(&mut self, pulse: Pulse) {
+        match pulse {
+            Pulse::Low => self.low += 1,
+            Pulse::High => self.high += 1,
+        }
+    }
+}
+
+/// Counts the pulses sent by each module (and the button, which comes last) over
+/// `presses` presses. If `use_period` is set and the network gets back to its initial
+/// state after `p` presses, the counts of the first `p` presses just repeat, so the
+/// remaining full cycles get extrapolated instead of simulated. Returns the counts and
+/// the period, if one was found.
+fn count_pulses(
+    network: &Network,
+    presses: usize,
+    use_period: bool,
+    cancel: &Token,
+) -> Result<(Vec<PulseCounts>, Option<usize>), Cancelled> {
+    let n = network.modules.len();
+    let mut state = network.clone();
+    let mut sent = vec![PulseCounts::default(); n + 1];
+    let mut queue = VecDeque::new();
+
+    for i in 1..=presses {
+        cancel.check()?;
+        state.press(&mut queue, |from, _, pulse| sent[from.min(n)].add(pulse));
+        if use_period && state.modules == network.modules {
+            let (cycles, rest) = (presses / i, presses % i);
+            for count in &mut sent {
+                count.low *= cycles;
+                count.high *= cycles;
+            }
+            for _ in 0..rest {
+                state.press(&mut queue, |from, _, pulse| sent[from.min(n)].add(pulse));
             }
+            return Ok((sent, Some(i)));
         }
     }
+    Ok((sent, None))
+}
 
-    (low_pulses * high_pulses).to_string()
+fn total(sent: &[PulseCounts]) -> PulseCounts {
+    sent.iter()
+        .fold(PulseCounts::default(), |acc, c| PulseCounts {
+            low: acc.low + c.low,
+            high: acc.high + c.high,
+        })
 }
 
-pub fn part2(input: &str) -> String {
-    let network = parse_network(input);
+fn presses_until_rx(network: &Network, cancel: &Token) -> anyhow::Result<usize> {
     // It seems that rx is always the child of a single
     // conjunction, which itself is the child of 4 conjunctions.
     // Each of those 4 grandparents lies on a separate cycle
@@ -161,9 +252,17 @@ pub fn part2(input: &str) -> String {
     // where each grandparent gets a low pulse, and then take the LCM
     // of those. This is not a general solution, but the inputs seem
     // to have been chosen to make this work.
-    let rx_idx = network.rx_idx.unwrap();
-    let parent = network.preds[rx_idx][0];
+    let rx_idx = network.rx_idx.context("there's no rx module")?;
+    let parent = *network.preds[rx_idx]
+        .first()
+        .context("no module sends pulses to rx")?;
     let grandparents = &network.preds[parent];
+    if let Some(&idx) = grandparents.iter().find(|&&idx| idx >= 64) {
+        anyhow::bail!(
+            "part 2 can only track the first 64 modules, but {:?} isn't one of them",
+            network.labels[idx]
+        );
+    }
 
     // Try to optimize the low iteration scanning as much as possible.
     // We use a bitset to find the grandparents, and a fixed-size array
@@ -179,54 +278,108 @@ pub fn part2(input: &str) -> String {
         if gp_bitset == 0 {
             break;
         }
-        // Each queue element has the form (predecessor, node, pulse),
-        // the broadcaster just gets a dummy predecessor, since it doesn't
-        // care about its predecessor anyways.
-        queue.push_back((usize::MAX, network.broadcast_idx, Pulse::Low));
-        while let Some((pred, node_idx, pulse)) = queue.pop_front() {
+        cancel.check()?;
+        network.press(&mut queue, |_, node_idx, pulse| {
             if pulse == Pulse::Low && gp_bitset & 1u64 << node_idx != 0 {
                 gp_bitset &= !(1u64 << node_idx);
                 low_counts[node_idx] = i;
             }
-            let out_signal = match &mut network.modules[node_idx] {
-                Module::FlipFlop(b) => {
-                    if pulse == Pulse::High {
-                        continue;
-                    }
-                    if *b {
-                        *b = false;
-                        Pulse::Low
-                    } else {
-                        *b = true;
-                        Pulse::High
-                    }
-                }
-                Module::Conjunction(mask) => {
-                    let bit = 1u64 << pred;
-                    if pulse == Pulse::Low {
-                        *mask &= !bit;
-                    } else {
-                        *mask |= bit;
-                    }
-                    if *mask == u64::MAX {
-                        Pulse::Low
-                    } else {
-                        Pulse::High
-                    }
-                }
-                Module::Broadcast => pulse,
-                Module::Output => continue,
-            };
-            for &out_idx in &network.connections[node_idx] {
-                queue.push_back((node_idx, out_idx, out_signal));
-            }
-        }
+        });
+    }
+
+    for &idx in grandparents {
+        explain::step(|| {
+            let (label, presses) = (network.labels[idx], low_counts[idx]);
+            format!("{} first sends a low pulse on press {}", label, presses)
+        });
     }
 
     // All non-grandparent nodes have a count of 1, which is the
     // identity for lcm, so we don't have to filter them out.
-    low_counts
-        .into_iter()
-        .fold(1usize, num::integer::lcm)
-        .to_string()
+    let lcm = low_counts.into_iter().fold(1, math::lcm);
+    explain::step(|| format!("The lcm of those is {}", lcm));
+    Ok(lcm)
+}
+
+#[derive(Serialize)]
+pub struct ModulePulses<'a> {
+    module: &'a str,
+    #[serde(flatten)]
+    sent: PulseCounts,
+}
+
+#[derive(Serialize)]
+pub struct Details<'a> {
+    presses: usize,
+    /// After how many presses the network first gets back to its initial state.
+    period: Option<usize>,
+    /// The totals from extrapolating over `period`, as used by part 1.
+    extrapolated: PulseCounts,
+    /// The totals from simulating every press, to cross-check `extrapolated`.
+    simulated: PulseCounts,
+    /// The pulses sent by each module over all presses.
+    modules: Vec<ModulePulses<'a>>,
+}
+
+impl fmt::Display for Details<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.period {
+            Some(period) => writeln!(f, "State repeats every {} presses", period)?,
+            None => writeln!(f, "State doesn't repeat within {} presses", self.presses)?,
+        }
+        let (e, s) = (self.extrapolated, self.simulated);
+        writeln!(f, "Extrapolated: {} low, {} high", e.low, e.high)?;
+        write!(f, "Simulated: {} low, {} high", s.low, s.high)?;
+        if e != s {
+            write!(f, " (mismatch!)")?;
+        }
+        for m in &self.modules {
+            write!(
+                f,
+                "\n{}: {} low, {} high",
+                m.module, m.sent.low, m.sent.high
+            )?;
+        }
+        Ok(())
+    }
+}
+
+pub fn details(input: &str) -> anyhow::Result<Details<'_>> {
+    let network = parse_network(input)?;
+    let never = Token::default();
+    let (sent, period) = count_pulses(&network, 1000, true, &never)?;
+    let (simulated, _) = count_pulses(&network, 1000, false, &never)?;
+    let labels = network.labels.iter().copied().chain(["button"]);
+    let modules = labels
+        .zip(&simulated)
+        .map(|(module, &sent)| ModulePulses { module, sent })
+        .collect();
+    Ok(Details {
+        presses: 1000,
+        period,
+        extrapolated: total(&sent),
+        simulated: total(&simulated),
+        modules,
+    })
+}
+
+register_day!(20, Day20, "Pulse Propagation", tags: ["simulation", "cycles"]);
+
+impl Puzzle for Day20 {
+    type Parsed<'a> = Network<'a>;
+    const ALGORITHM: &'static str = "cycle-lcm-v1";
+
+    fn parse(input: &[u8]) -> anyhow::Result<Network<'_>> {
+        let input = parse::utf8(input)?;
+        Ok(parse_network(input)?)
+    }
+
+    fn solve1(network: &Network<'_>, cancel: &Token) -> anyhow::Result<Answer> {
+        let total = total(&count_pulses(network, 1000, true, cancel)?.0);
+        Ok((total.low * total.high).into())
+    }
+
+    fn solve2(network: &Network<'_>, cancel: &Token) -> anyhow::Result<Answer> {
+        Ok(presses_until_rx(network, cancel)?.into())
+    }
 }
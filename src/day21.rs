@@ -1,4 +1,12 @@
-use ahash::AHashSet;
+use anyhow::Context;
+
+use crate::{
+    cancel::{Cancelled, Token},
+    collections::HashSet,
+    explain, grid, math,
+    parse::ParseError,
+    Answer, Day21, Puzzle,
+};
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 enum Cell {
@@ -6,56 +14,48 @@ enum Cell {
     Wall,
 }
 
-struct Grid {
-    cells: Vec<Cell>,
-    width: u8,
-    height: u8,
+pub struct Grid {
+    cells: grid::Grid<Cell>,
     start: (u8, u8),
 }
 
 impl Grid {
-    fn get(&self, x: u8, y: u8) -> Cell {
-        assert!(x < self.width && y < self.height);
-        self.cells[(y as usize) * (self.width as usize) + (x as usize)]
-    }
-
     fn get_wrapping(&self, x: i16, y: i16) -> Cell {
-        let x = x.rem_euclid(self.width as i16) as u8;
-        let y = y.rem_euclid(self.height as i16) as u8;
-        self.get(x, y)
+        let x = x.rem_euclid(self.cells.width() as i16) as u8;
+        let y = y.rem_euclid(self.cells.height() as i16) as u8;
+        self.cells[(x, y)]
     }
 }
 
-fn parse_input(input: &str) -> Grid {
-    let mut cells = vec![];
-    let mut width = 0;
-    let mut height = 0u8;
-    let mut start = (0, 0);
-    for line in input.lines() {
-        width = u8::try_from(line.len()).expect("grid too wide");
-        for (i, cell) in line.bytes().enumerate() {
-            let cell = match cell {
-                b'.' => Cell::Floor,
-                b'#' => Cell::Wall,
-                b'S' => {
-                    start = (i as u8, height);
-                    Cell::Floor
-                }
-                _ => unreachable!("Invalid cell"),
-            };
-            cells.push(cell);
-        }
-        height = height.checked_add(1).expect("grid too tall");
+fn parse_input(input: &[u8]) -> Result<Grid, ParseError> {
+    let mut start = None;
+    let cells = grid::Grid::parse(input, |(x, y), cell| {
+        Ok(match cell {
+            b'.' => Cell::Floor,
+            b'#' => Cell::Wall,
+            b'S' => {
+                start = Some((x as u8, y as u8));
+                Cell::Floor
+            }
+            _ => return Err(ParseError::invalid("cell", cell as char)),
+        })
+    })?;
+    if cells.width() > u8::MAX as usize {
+        return Err(ParseError::malformed("grid too wide"));
     }
-    Grid { cells, width, height, start }
+    if cells.height() > u8::MAX as usize {
+        return Err(ParseError::malformed("grid too tall"));
+    }
+    let start = start.ok_or_else(|| ParseError::malformed("no start position found"))?;
+    Ok(Grid { cells, start })
 }
 
-pub fn part1(input: &str) -> String {
-    let grid = parse_input(input);
+fn reachable_in_64(grid: &Grid, cancel: &Token) -> Result<usize, Cancelled> {
     let (sx, sy) = grid.start;
-    let mut accessible = AHashSet::from([(sx as i16, sy as i16)]);
-    let mut next = AHashSet::new();
+    let mut accessible = HashSet::from_iter([(sx as i16, sy as i16)]);
+    let mut next = HashSet::default();
     for _ in 0..64 {
+        cancel.check()?;
         for (x, y) in accessible.drain() {
             if grid.get_wrapping(x - 1, y) == Cell::Floor {
                 next.insert((x - 1, y));
@@ -71,37 +71,22 @@ pub fn part1(input: &str) -> String {
                 next.insert((x, y + 1));
             }
         }
-        std::mem::swap(&mut accessible, &mut next);
+        core::mem::swap(&mut accessible, &mut next);
     }
 
-    accessible.len().to_string()
-}
-
-// extrapolate the quadratic function that passes through the points
-// (x0, y0), (x1, y1), (x2, y2) and return its value at x.
-fn eval_lagrange(xs: [isize; 3], ys: [usize; 3], x: usize) -> usize {
-    // ew
-    let [x0, x1, x2] = xs.map(|x| x as i128);
-    let [y0, y1, y2] = ys.map(|y| y as i128);
-    let x = x as i128;
-
-    let result = ((x - x1) * (x - x2) * y0 / ((x0 - x1) * (x0 - x2)))
-        + ((x - x0) * (x - x2) * y1 / ((x1 - x0) * (x1 - x2)))
-        + ((x - x0) * (x - x1) * y2 / ((x2 - x0) * (x2 - x1)));
-
-    result as usize
+    Ok(accessible.len())
 }
 
-pub fn part2(input: &str) -> String {
-    let grid = parse_input(input);
+fn reachable_in_26501365(grid: &Grid, cancel: &Token) -> anyhow::Result<usize> {
     let (sx, sy) = grid.start;
-    let mut accessible = AHashSet::from([(sx as i16, sy as i16)]);
-    let mut next = AHashSet::new();
+    let mut accessible = HashSet::from_iter([(sx as i16, sy as i16)]);
+    let mut next = HashSet::default();
     // we store [f(-66), f(65), f(196)] in this array, which is
     // enough to extrapolate the quadratic function that calculates
     // f(65 + 131 * n).
     let mut values = [0; 3];
     for i in 1..=196 {
+        cancel.check()?;
         for (x, y) in accessible.drain() {
             if grid.get_wrapping(x - 1, y) == Cell::Floor {
                 next.insert((x - 1, y));
@@ -117,7 +102,7 @@ pub fn part2(input: &str) -> String {
                 next.insert((x, y + 1));
             }
         }
-        std::mem::swap(&mut accessible, &mut next);
+        core::mem::swap(&mut accessible, &mut next);
         match i {
             // Seems like f(-66) = f(64). I guess f is symmetric around -1?
             64 => values[0] = accessible.len(),
@@ -127,5 +112,31 @@ pub fn part2(input: &str) -> String {
         }
     }
 
-    eval_lagrange([-66, 65, 196], values, 26501365).to_string()
+    explain::step(|| format!("{} plots are reachable in 64 steps, standing in for -66 steps", values[0]));
+    explain::step(|| format!("{} plots are reachable in 65 steps", values[1]));
+    explain::step(|| format!("{} plots are reachable in 196 steps", values[2]));
+    let [y0, y1, y2] = values.map(|y| y as i128);
+    let reachable = math::extrapolate(&[(-66, y0), (65, y1), (196, y2)], 26501365)
+        .context("the extrapolated number of reachable plots isn't an integer")?;
+    explain::step(|| format!("The quadratic through those gives {} for 26501365 steps", reachable));
+    Ok(reachable as usize)
+}
+
+register_day!(21, Day21, "Step Counter", tags: ["grid", "math"]);
+
+impl Puzzle for Day21 {
+    type Parsed<'a> = Grid;
+    const ALGORITHM: &'static str = "quadratic-extrapolation-v1";
+
+    fn parse(input: &[u8]) -> anyhow::Result<Grid> {
+        Ok(parse_input(input)?)
+    }
+
+    fn solve1(grid: &Grid, cancel: &Token) -> anyhow::Result<Answer> {
+        Ok(reachable_in_64(grid, cancel)?.into())
+    }
+
+    fn solve2(grid: &Grid, cancel: &Token) -> anyhow::Result<Answer> {
+        Ok(reachable_in_26501365(grid, cancel)?.into())
+    }
 }
@@ -1,6 +1,16 @@
+use alloc::{string::ToString, vec::Vec};
+use core::fmt;
+
+use serde::Serialize;
 use smallvec::SmallVec;
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+use crate::{
+    cancel::Token,
+    parse::{self, ParseError},
+    Answer, Day22, Puzzle,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
 struct Brick {
     start: (u16, u16, u16),
     end: (u16, u16, u16),
@@ -16,23 +26,28 @@ impl Brick {
     }
 }
 
-fn parse_input(input: &str) -> Vec<Brick> {
-    let mut bricks: Vec<_> = input
-        .trim()
-        .lines()
-        .map(|line| {
-            let (start, end) = line.split_once('~').unwrap();
-            let (sx, syz) = start.split_once(',').unwrap();
-            let (sy, sz) = syz.split_once(',').unwrap();
-            let (ex, eyz) = end.split_once(',').unwrap();
-            let (ey, ez) = eyz.split_once(',').unwrap();
-            let start = (sx.parse().unwrap(), sy.parse().unwrap(), sz.parse().unwrap());
-            let end = (ex.parse().unwrap(), ey.parse().unwrap(), ez.parse().unwrap());
-            Brick::from_start_end(start, end)
-        })
-        .collect();
+fn parse_coords(s: &str) -> Result<(u16, u16, u16), ParseError> {
+    let (x, yz) = parse::split_once(s, ",")?;
+    let (y, z) = parse::split_once(yz, ",")?;
+    Ok((parse::num(x)?, parse::num(y)?, parse::num(z)?))
+}
+
+fn parse_input(input: &str) -> Result<Vec<Brick>, ParseError> {
+    let mut bricks = parse::lines(input, |line| {
+        let (start, end) = parse::split_once(line.trim(), "~")?;
+        let brick = Brick::from_start_end(parse_coords(start)?, parse_coords(end)?);
+        if brick.start.2 == 0 {
+            return Err(ParseError::malformed("bricks can't go below z = 1"));
+        }
+        Ok(brick)
+    })?;
+    // The supports are stored as u16 indices.
+    if bricks.len() > u16::MAX as usize {
+        let message = format!("there are more than {} bricks", u16::MAX);
+        return Err(ParseError::malformed(message));
+    }
     bricks.sort_unstable_by_key(|brick| brick.start.2);
-    bricks
+    Ok(bricks)
 }
 
 fn xy_limits(bricks: &[Brick]) -> ((u16, u16), (u16, u16)) {
@@ -45,78 +60,127 @@ fn xy_limits(bricks: &[Brick]) -> ((u16, u16), (u16, u16)) {
     ((x_min, x_max), (y_min, y_max))
 }
 
-struct State<'a> {
-    bricks: &'a mut [Brick],
-    x_lims: (u16, u16),
-    y_lims: (u16, u16),
+/// The bricks after they all fell down as far as they could, and which of them rest on
+/// which. Bricks are indexed in the order they settled in, i.e. by their lowest z before
+/// falling.
+#[derive(Serialize)]
+pub struct Stack {
+    bricks: Vec<Brick>,
     touching_above: Vec<SmallVec<[u16; 4]>>,
     touching_below: Vec<SmallVec<[u16; 4]>>,
 }
 
-fn fall(state: &mut State) {
-    let width = (state.x_lims.1 - state.x_lims.0 + 1) as usize;
-    let height = (state.y_lims.1 - state.y_lims.0 + 1) as usize;
+fn fall(stack: &mut Stack) -> Result<(), ParseError> {
+    if stack.bricks.is_empty() {
+        return Ok(());
+    }
+    let (x_lims, y_lims) = xy_limits(&stack.bricks);
+    let width = (x_lims.1 - x_lims.0 + 1) as usize;
+    let height = (y_lims.1 - y_lims.0 + 1) as usize;
 
     let mut grid = vec![usize::MAX; width * height];
     let grid_idx = |x: u16, y: u16| {
-        (y as usize - state.y_lims.0 as usize) * width + (x as usize - state.x_lims.0 as usize)
+        (y as usize - y_lims.0 as usize) * width + (x as usize - x_lims.0 as usize)
     };
 
-    for brick_idx in 0..state.bricks.len() {
+    for brick_idx in 0..stack.bricks.len() {
         let mut max_z = 0;
         // first, do a pass to find the maximum z of any brick below
         // the current one. the current brick will then be one above
         // that maximum z.
-        let brick = state.bricks[brick_idx];
+        let brick = stack.bricks[brick_idx];
         for y in brick.start.1..=brick.end.1 {
             for x in brick.start.0..=brick.end.0 {
                 let below_idx = grid[grid_idx(x, y)];
                 if below_idx != usize::MAX {
-                    let top_of_below = state.bricks[below_idx].end.2;
+                    let top_of_below = stack.bricks[below_idx].end.2;
                     max_z = max_z.max(top_of_below);
                 }
             }
         }
-        let offset = brick.start.2 - max_z - 1;
-        state.bricks[brick_idx].start.2 -= offset;
-        state.bricks[brick_idx].end.2 -= offset;
+        // Bricks can only overlap if they already did in the input, since each one lands
+        // on top of the ones below it.
+        let offset = (brick.start.2 - 1).checked_sub(max_z).ok_or_else(|| {
+            let message = format!("the brick at {:?} overlaps another one", brick.start);
+            ParseError::malformed(message)
+        })?;
+        stack.bricks[brick_idx].start.2 -= offset;
+        stack.bricks[brick_idx].end.2 -= offset;
         // in the second pass, compute all the bricks that now
         // touch the current one.
-        let brick = state.bricks[brick_idx];
+        let brick = stack.bricks[brick_idx];
         for y in brick.start.1..=brick.end.1 {
             for x in brick.start.0..=brick.end.0 {
                 let grid_idx = grid_idx(x, y);
                 let below_idx = grid[grid_idx];
                 if below_idx != usize::MAX {
-                    let top_of_below = state.bricks[below_idx].end.2;
+                    let top_of_below = stack.bricks[below_idx].end.2;
                     if top_of_below == max_z
-                        && !state.touching_below[brick_idx].contains(&(below_idx as u16))
+                        && !stack.touching_below[brick_idx].contains(&(below_idx as u16))
                     {
-                        state.touching_below[brick_idx].push(below_idx as u16);
-                        state.touching_above[below_idx].push(brick_idx as u16);
+                        stack.touching_below[brick_idx].push(below_idx as u16);
+                        stack.touching_above[below_idx].push(brick_idx as u16);
                     }
                 }
                 grid[grid_idx] = brick_idx;
             }
         }
     }
+    Ok(())
 }
 
-// counts the number of bricks that, if removed, would lead
-// to other bricks falling down.
-fn count_loadbearing(state: &State) -> usize {
-    let n = state.bricks.len();
-    let mut loadbearing = vec![false; n];
-    for below in &state.touching_below {
-        if below.len() == 1 {
-            loadbearing[below[0] as usize] = true;
-        }
+impl Stack {
+    /// Parses the bricks and lets them fall.
+    pub fn settle(input: &str) -> Result<Self, ParseError> {
+        let bricks = tracing::debug_span!("read bricks").in_scope(|| parse_input(input))?;
+        let n = bricks.len();
+        let mut stack = Stack {
+            bricks,
+            touching_above: vec![SmallVec::new(); n],
+            touching_below: vec![SmallVec::new(); n],
+        };
+        tracing::debug_span!("fall", bricks = n).in_scope(|| fall(&mut stack))?;
+        Ok(stack)
+    }
+
+    pub fn brick_count(&self) -> usize {
+        self.bricks.len()
+    }
+
+    /// The height of the topmost cube of any brick.
+    pub fn top(&self) -> u16 {
+        self.bricks
+            .iter()
+            .map(|brick| brick.end.2)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The bricks whose lowest cube is at height `z`.
+    pub fn bricks_at_height(&self, z: u16) -> impl Iterator<Item = usize> + '_ {
+        (0..self.bricks.len()).filter(move |&i| self.bricks[i].start.2 == z)
+    }
+
+    /// The bricks that brick `i` rests on directly.
+    pub fn supports_of(&self, i: usize) -> &[u16] {
+        &self.touching_below[i]
+    }
+
+    /// The bricks that rest directly on brick `i`.
+    pub fn resting_on(&self, i: usize) -> &[u16] {
+        &self.touching_above[i]
+    }
+
+    /// Whether removing brick `i` would make any other brick fall.
+    pub fn is_load_bearing(&self, i: usize) -> bool {
+        self.resting_on(i)
+            .iter()
+            .any(|&above| self.supports_of(above as usize).len() == 1)
     }
-    loadbearing.iter().filter(|&&bit| bit).count()
 }
 
-fn sum_of_falling(state: &State) -> usize {
-    let n = state.bricks.len();
+fn sum_of_falling(stack: &Stack) -> usize {
+    let n = stack.brick_count();
     let mut falling = vec![false; n];
     let mut sum = 0;
     for piece_idx in 0..n {
@@ -124,12 +188,12 @@ fn sum_of_falling(state: &State) -> usize {
         falling[piece_idx] = true;
         'outer: for falling_idx in piece_idx + 1..n {
             // in this case the piece is already on the bottom layer.
-            if state.touching_below[falling_idx].is_empty() {
+            if stack.supports_of(falling_idx).is_empty() {
                 continue;
             }
             // if there's any piece below that isn't falling, then
             // the current piece isn't falling either.
-            for &below_idx in &state.touching_below[falling_idx] {
+            for &below_idx in stack.supports_of(falling_idx) {
                 if !falling[below_idx as usize] {
                     continue 'outer;
                 }
@@ -142,33 +206,105 @@ fn sum_of_falling(state: &State) -> usize {
     sum
 }
 
-pub fn part1(input: &str) -> String {
-    let bricks = parse_input(input);
-    let (x_lims, y_lims) = xy_limits(&bricks);
-    let mut state = State {
-        bricks: &mut bricks.clone(),
-        x_lims,
-        y_lims,
-        touching_above: vec![SmallVec::new(); bricks.len()],
-        touching_below: vec![SmallVec::new(); bricks.len()],
-    };
-    fall(&mut state);
+/// How many bricks there are and how high the top one starts out.
+pub fn shape(input: &str) -> anyhow::Result<Vec<(&'static str, usize)>> {
+    let bricks = parse_input(input)?;
+    let top = bricks.iter().map(|b| b.end.2 as usize).max().unwrap_or(0);
+    Ok(vec![("bricks", bricks.len()), ("top z", top)])
+}
 
-    let non_loadbearing = state.bricks.len() - count_loadbearing(&state);
-    non_loadbearing.to_string()
+register_day!(22, Day22, "Sand Slabs", tags: ["simulation", "graph"]);
+
+impl Puzzle for Day22 {
+    type Parsed<'a> = Stack;
+    const ALGORITHM: &'static str = "settle-support-graph-v1";
+
+    fn parse(input: &[u8]) -> anyhow::Result<Stack> {
+        let input = parse::utf8(input)?;
+        Ok(Stack::settle(input)?)
+    }
+
+    fn solve1(stack: &Stack, _: &Token) -> anyhow::Result<Answer> {
+        let n = stack.brick_count();
+        let non_loadbearing = (0..n).filter(|&i| !stack.is_load_bearing(i)).count();
+        Ok(non_loadbearing.into())
+    }
+
+    fn solve2(stack: &Stack, _: &Token) -> anyhow::Result<Answer> {
+        Ok(sum_of_falling(stack).into())
+    }
 }
 
-pub fn part2(input: &str) -> String {
-    let bricks = parse_input(input);
-    let (x_lims, y_lims) = xy_limits(&bricks);
-    let mut state = State {
-        bricks: &mut bricks.clone(),
-        x_lims,
-        y_lims,
-        touching_above: vec![SmallVec::new(); bricks.len()],
-        touching_below: vec![SmallVec::new(); bricks.len()],
-    };
-    fall(&mut state);
+#[derive(Serialize)]
+pub struct BrickNode {
+    start: (u16, u16, u16),
+    end: (u16, u16, u16),
+    /// Indices of the bricks resting directly on top of this one.
+    supports: Vec<u16>,
+    /// Indices of the bricks this one rests on directly.
+    supported_by: Vec<u16>,
+    /// Whether removing this brick would make any other brick fall.
+    load_bearing: bool,
+}
+
+/// The support graph of the settled bricks. Bricks are indexed in the order they settled
+/// in (i.e. by their lowest z), and their coordinates are the ones after falling. Edges
+/// always point upwards, so the graph is a DAG.
+#[derive(Serialize)]
+pub struct SupportGraph {
+    bricks: Vec<BrickNode>,
+    /// The indices of the bricks whose lowest cube is at each height, starting at 1.
+    layers: Vec<Vec<usize>>,
+}
+
+/// The text form is a DOT graph, so it can be piped straight into graphviz. Bricks that
+/// start at the same height are drawn side by side, and load-bearing ones in bold.
+impl fmt::Display for SupportGraph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "digraph bricks {{")?;
+        writeln!(f, "    rankdir=BT;")?;
+        for (i, brick) in self.bricks.iter().enumerate() {
+            let (x1, y1, z1) = brick.start;
+            let (x2, y2, z2) = brick.end;
+            let style = if brick.load_bearing {
+                ", style=bold"
+            } else {
+                ""
+            };
+            writeln!(
+                f,
+                "    {i} [label=\"{x1},{y1},{z1}~{x2},{y2},{z2}\"{style}];"
+            )?;
+        }
+        for layer in self.layers.iter().filter(|layer| layer.len() > 1) {
+            let nodes: Vec<_> = layer.iter().map(|i| i.to_string()).collect();
+            writeln!(f, "    {{ rank=same; {}; }}", nodes.join("; "))?;
+        }
+        for (i, brick) in self.bricks.iter().enumerate() {
+            for above in &brick.supports {
+                writeln!(f, "    {i} -> {above};")?;
+            }
+        }
+        write!(f, "}}")
+    }
+}
 
-    sum_of_falling(&state).to_string()
+pub fn details(input: &str) -> anyhow::Result<SupportGraph> {
+    let stack = Stack::settle(input)?;
+    let bricks = (0..stack.brick_count())
+        .map(|i| {
+            let brick = stack.bricks[i];
+            BrickNode {
+                start: brick.start,
+                end: brick.end,
+                supports: stack.resting_on(i).to_vec(),
+                supported_by: stack.supports_of(i).to_vec(),
+                load_bearing: stack.is_load_bearing(i),
+            }
+        })
+        .collect();
+    let layers = (1..=stack.top())
+        .map(|z| stack.bricks_at_height(z).collect())
+        .collect();
+    Ok(SupportGraph { bricks, layers })
 }
@@ -1,115 +1,134 @@
-use std::collections::hash_map::Entry;
+use alloc::{string::{String, ToString}, vec::Vec};
 
-use ahash::{AHashMap, AHashSet};
-use enum_map::{Enum, EnumMap};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
 
-#[derive(Clone, Copy, PartialEq, Eq, Enum)]
-enum Dir {
-    North,
-    South,
-    East,
-    West,
-}
+use crate::{
+    cancel::{Cancelled, Token},
+    collections::HashSet,
+    geometry::Dir,
+    graph, grid,
+    parse::ParseError,
+    Answer, Day23, Puzzle,
+};
 
 #[derive(Clone, Copy, PartialEq, Eq)]
-enum Cell {
+pub enum Cell {
     Wall,
     Empty,
     Slope(Dir),
 }
 
-struct Grid {
-    cells: Vec<Cell>,
-    width: u8,
-    height: u8,
-}
-
-impl Grid {
-    fn get(&self, x: u8, y: u8) -> Cell {
-        assert!(x < self.width && y < self.height);
-        let (x, y) = (x as usize, y as usize);
-        self.cells[y * self.width as usize + x]
+pub type Grid = grid::Grid<Cell>;
+
+fn parse_grid(input: &[u8]) -> Result<Grid, ParseError> {
+    let grid = Grid::parse(input, |_, c| {
+        Ok(match c {
+            b'#' => Cell::Wall,
+            b'.' => Cell::Empty,
+            b'<' => Cell::Slope(Dir::West),
+            b'>' => Cell::Slope(Dir::East),
+            b'^' => Cell::Slope(Dir::North),
+            b'v' => Cell::Slope(Dir::South),
+            _ => return Err(ParseError::invalid("cell", c as char)),
+        })
+    })?;
+    // Coordinates are u8s.
+    if grid.width() > u8::MAX as usize {
+        return Err(ParseError::malformed("grid too wide"));
     }
-}
-
-fn parse_grid(input: &str) -> Grid {
-    let mut cells = Vec::new();
-    let mut width = 0;
-    let mut height = 0u8;
-    for line in input.lines() {
-        width = u8::try_from(line.len()).expect("grid too wide");
-        height = height.checked_add(1).expect("grid too tall");
-        for c in line.chars() {
-            cells.push(match c {
-                '#' => Cell::Wall,
-                '.' => Cell::Empty,
-                '<' => Cell::Slope(Dir::West),
-                '>' => Cell::Slope(Dir::East),
-                '^' => Cell::Slope(Dir::North),
-                'v' => Cell::Slope(Dir::South),
-                _ => panic!("invalid cell: {c}"),
-            });
-        }
+    if grid.height() > u8::MAX as usize {
+        return Err(ParseError::malformed("grid too tall"));
     }
-    Grid { cells, width, height }
+    Ok(grid)
 }
 
 type Coords = (u8, u8);
-type Vertex = (Coords, EnumMap<Dir, Option<(u8, u16)>>);
 
-struct Graph {
-    vertices: Vec<Vertex>,
-    start: u8,
-    end: u8,
+/// The maze contracted to its junctions, with the length of the trail between each pair of
+/// adjacent ones. Building it is cheap compared to searching it, so it can be saved with
+/// [`Graph::to_bytes`] and loaded with [`Graph::from_bytes`] to benchmark or profile
+/// [`Graph::longest_path`] on its own.
+#[derive(Serialize, Deserialize)]
+pub struct Graph {
+    graph: graph::Graph<u16>,
+    coords: Vec<Coords>,
+    start: usize,
+    end: usize,
 }
 
-fn grid_to_graph(grid: &Grid, climb_slopes: bool) -> Graph {
-    fn vertex_index(
-        coords: Coords,
-        indices: &mut AHashMap<Coords, u8>,
-        vertices: &mut Vec<Vertex>,
-    ) -> u8 {
-        match indices.entry(coords) {
-            Entry::Occupied(o) => *o.get(),
-            Entry::Vacant(v) => {
-                let idx = vertices.len();
-                vertices.push((coords, EnumMap::default()));
-                *v.insert(u8::try_from(idx).expect("too many vertices"))
-            }
-        }
+impl Graph {
+    /// The junction graph of `input`, for part 2 if `climb_slopes` is set.
+    pub fn new(input: &str, climb_slopes: bool) -> anyhow::Result<Self> {
+        grid_to_graph(&parse_grid(input.as_bytes())?, climb_slopes)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        postcard::to_allocvec(self).expect("serializing to a Vec can't fail")
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        let graph: Self = postcard::from_bytes(bytes).context("invalid graph")?;
+        let in_bounds = |idx: usize| idx < graph.graph.len();
+        anyhow::ensure!(
+            in_bounds(graph.start) && in_bounds(graph.end) && graph.graph.is_valid(),
+            "invalid graph: it refers to a vertex that doesn't exist"
+        );
+        anyhow::ensure!(
+            graph.coords.len() == graph.graph.len(),
+            "invalid graph: it doesn't have the coordinates of every vertex"
+        );
+        Ok(graph)
     }
 
+    /// The graph in Graphviz's DOT language, with every junction labeled by its position.
+    pub fn to_dot(&self) -> String {
+        self.graph.to_dot(
+            |v| format!("{:?}", self.coords[v]),
+            |len| Some(len.to_string()),
+        )
+    }
+
+    /// The length of the longest path from the start to the end that doesn't visit any
+    /// junction twice. The search takes a while, so it gives up once `cancel` is cancelled.
+    pub fn longest_path(&self, cancel: &Token) -> Result<usize, Cancelled> {
+        longest_path(self, self.start, self.end, cancel)
+    }
+}
+
+fn grid_to_graph(grid: &Grid, climb_slopes: bool) -> anyhow::Result<Graph> {
+    let _span = tracing::debug_span!("build graph", climb_slopes).entered();
     fn can_step_north(grid: &Grid, (x, y): Coords, climb_slopes: bool) -> bool {
         if climb_slopes {
-            y > 0 && grid.get(x, y - 1) != Cell::Wall
+            y > 0 && grid[(x, y - 1)] != Cell::Wall
         } else {
-            y > 0 && matches!(grid.get(x, y - 1), Cell::Empty | Cell::Slope(Dir::North))
+            y > 0 && matches!(grid[(x, y - 1)], Cell::Empty | Cell::Slope(Dir::North))
         }
     }
 
     fn can_step_south(grid: &Grid, (x, y): Coords, climb_slopes: bool) -> bool {
         if climb_slopes {
-            y + 1 < grid.height && grid.get(x, y + 1) != Cell::Wall
+            y + 1 < grid.height() as u8 && grid[(x, y + 1)] != Cell::Wall
         } else {
-            y + 1 < grid.height
-                && matches!(grid.get(x, y + 1), Cell::Empty | Cell::Slope(Dir::South))
+            y + 1 < grid.height() as u8
+                && matches!(grid[(x, y + 1)], Cell::Empty | Cell::Slope(Dir::South))
         }
     }
 
     fn can_step_east(grid: &Grid, (x, y): Coords, climb_slopes: bool) -> bool {
         if climb_slopes {
-            x + 1 < grid.width && grid.get(x + 1, y) != Cell::Wall
+            x + 1 < grid.width() as u8 && grid[(x + 1, y)] != Cell::Wall
         } else {
-            x + 1 < grid.width && matches!(grid.get(x + 1, y), Cell::Empty | Cell::Slope(Dir::East))
+            x + 1 < grid.width() as u8 && matches!(grid[(x + 1, y)], Cell::Empty | Cell::Slope(Dir::East))
         }
     }
 
     fn can_step_west(grid: &Grid, (x, y): Coords, climb_slopes: bool) -> bool {
         if climb_slopes {
-            x > 0 && grid.get(x - 1, y) != Cell::Wall
+            x > 0 && grid[(x - 1, y)] != Cell::Wall
         } else {
-            x > 0 && matches!(grid.get(x - 1, y), Cell::Empty | Cell::Slope(Dir::West))
+            x > 0 && matches!(grid[(x - 1, y)], Cell::Empty | Cell::Slope(Dir::West))
         }
     }
 
@@ -122,9 +141,9 @@ fn grid_to_graph(grid: &Grid, climb_slopes: bool) -> Graph {
         let mut steps = 0;
         loop {
             if (x == 0 && dir == Dir::West)
-                || (x + 1 == grid.width && dir == Dir::East)
+                || (x + 1 == grid.width() as u8 && dir == Dir::East)
                 || (y == 0 && dir == Dir::North)
-                || (y + 1 == grid.height && dir == Dir::South)
+                || (y + 1 == grid.height() as u8 && dir == Dir::South)
             {
                 return ((x, y), steps);
             }
@@ -137,19 +156,19 @@ fn grid_to_graph(grid: &Grid, climb_slopes: bool) -> Graph {
             steps += 1;
             // All the directions that we can walk to, except for the one we came from.
             let mut neighbor_dirs = SmallVec::<[Dir; 4]>::new();
-            if dir != Dir::East && can_step_west(grid, (x, y), climb_slopes) {
+            if dir.opposite() != Dir::West && can_step_west(grid, (x, y), climb_slopes) {
                 neighbor_dirs.push(Dir::West);
             }
 
-            if dir != Dir::West && can_step_east(grid, (x, y), climb_slopes) {
+            if dir.opposite() != Dir::East && can_step_east(grid, (x, y), climb_slopes) {
                 neighbor_dirs.push(Dir::East);
             }
 
-            if dir != Dir::South && can_step_north(grid, (x, y), climb_slopes) {
+            if dir.opposite() != Dir::North && can_step_north(grid, (x, y), climb_slopes) {
                 neighbor_dirs.push(Dir::North);
             }
 
-            if dir != Dir::North && can_step_south(grid, (x, y), climb_slopes) {
+            if dir.opposite() != Dir::South && can_step_south(grid, (x, y), climb_slopes) {
                 neighbor_dirs.push(Dir::South);
             }
 
@@ -166,94 +185,113 @@ fn grid_to_graph(grid: &Grid, climb_slopes: bool) -> Graph {
         }
     }
 
-    let mut indices = AHashMap::new();
-    let mut vertices = Vec::new();
+    let mut builder = graph::Builder::new();
     let start_x =
-        (0..grid.width).find(|&x| grid.get(x, 0) == Cell::Empty).expect("No start node found");
-    let start_idx = vertex_index((start_x, 0), &mut indices, &mut vertices);
-    let mut visited = AHashSet::new();
+        (0..grid.width() as u8).find(|&x| grid[(x, 0)] == Cell::Empty).context("No start node found")?;
+    let start_idx = builder.vertex((start_x, 0));
+    let mut visited = HashSet::default();
     let mut stack = vec![(start_idx)];
 
     while let Some(vertex_idx) = stack.pop() {
-        let vertex_idx = vertex_idx as usize;
         if !visited.insert(vertex_idx) {
             continue;
         }
-        let ((x, y), _) = vertices[vertex_idx];
+        let (x, y) = *builder.label(vertex_idx);
 
         if can_step_east(grid, (x, y), climb_slopes) {
             // walk east
             let (coords, dist) = walk(grid, (x, y), Dir::East, climb_slopes);
-            let neighbor_idx = vertex_index(coords, &mut indices, &mut vertices);
-            vertices[vertex_idx].1[Dir::East] = Some((neighbor_idx, dist));
+            let neighbor_idx = builder.vertex(coords);
+            builder.graph_mut().add_edge(vertex_idx, neighbor_idx, dist);
             stack.push(neighbor_idx);
         }
 
         if can_step_west(grid, (x, y), climb_slopes) {
             // walk west
             let (coords, dist) = walk(grid, (x, y), Dir::West, climb_slopes);
-            let neighbor_idx = vertex_index(coords, &mut indices, &mut vertices);
-            vertices[vertex_idx].1[Dir::West] = Some((neighbor_idx, dist));
+            let neighbor_idx = builder.vertex(coords);
+            builder.graph_mut().add_edge(vertex_idx, neighbor_idx, dist);
             stack.push(neighbor_idx);
         }
 
         if can_step_north(grid, (x, y), climb_slopes) {
             // walk north
             let (coords, dist) = walk(grid, (x, y), Dir::North, climb_slopes);
-            let neighbor_idx = vertex_index(coords, &mut indices, &mut vertices);
-            vertices[vertex_idx].1[Dir::North] = Some((neighbor_idx, dist));
+            let neighbor_idx = builder.vertex(coords);
+            builder.graph_mut().add_edge(vertex_idx, neighbor_idx, dist);
             stack.push(neighbor_idx);
         }
 
         if can_step_south(grid, (x, y), climb_slopes) {
             // walk south
             let (coords, dist) = walk(grid, (x, y), Dir::South, climb_slopes);
-            let neighbor_idx = vertex_index(coords, &mut indices, &mut vertices);
-            vertices[vertex_idx].1[Dir::South] = Some((neighbor_idx, dist));
+            let neighbor_idx = builder.vertex(coords);
+            builder.graph_mut().add_edge(vertex_idx, neighbor_idx, dist);
             stack.push(neighbor_idx);
         }
     }
 
-    let end_x = (0..grid.width).find(|&x| grid.get(x, grid.height - 1) == Cell::Empty).unwrap();
-    let end_idx = vertex_index((end_x, grid.height - 1), &mut indices, &mut vertices);
+    let end_x = (0..grid.width() as u8)
+        .find(|&x| grid[(x, grid.height() as u8 - 1)] == Cell::Empty)
+        .context("No end node found")?;
+    let end_idx = builder.vertex((end_x, grid.height() as u8 - 1));
+    let (graph, coords) = builder.build();
 
-    Graph { vertices, start: start_idx, end: end_idx }
+    Ok(Graph { graph, coords, start: start_idx, end: end_idx })
 }
 
 
-fn longest_path(graph: &Graph, start: u8, end: u8) -> usize {
-    let mut visited = vec![false; graph.vertices.len()];
-
-    fn dfs(graph: &Graph, visited: &mut [bool], start: u8, end: u8, dist: usize) -> usize {
+fn longest_path(
+    graph: &Graph,
+    start: usize,
+    end: usize,
+    cancel: &Token,
+) -> Result<usize, Cancelled> {
+    let _span = tracing::debug_span!("longest path", junctions = graph.graph.len()).entered();
+    let mut visited = vec![false; graph.graph.len()];
+
+    fn dfs(
+        graph: &Graph,
+        visited: &mut [bool],
+        start: usize,
+        end: usize,
+        dist: usize,
+        cancel: &Token,
+    ) -> Result<usize, Cancelled> {
         if start == end {
-            return dist;
+            return Ok(dist);
         }
-        visited[start as usize] = true;
+        cancel.check()?;
+        visited[start] = true;
         let mut max_dist = 0;
-        for (_, neighbor) in &graph.vertices[start as usize].1 {
-            if let Some((idx, neighbor_dist)) = neighbor {
-                if !visited[*idx as usize] {
-                    max_dist = max_dist.max(dfs(graph, visited, *idx, end, dist + *neighbor_dist as usize));
-                }
+        for &(idx, neighbor_dist) in graph.graph.neighbors(start) {
+            if !visited[idx] {
+                let dist = dist + neighbor_dist as usize;
+                max_dist = max_dist.max(dfs(graph, visited, idx, end, dist, cancel)?);
             }
         }
-        visited[start as usize] = false;
-        max_dist
+        visited[start] = false;
+        Ok(max_dist)
     }
 
-    dfs(graph, &mut visited, start, end, 0)
+    dfs(graph, &mut visited, start, end, 0, cancel)
 }
 
-pub fn part1(input: &str) -> String {
-    let grid = parse_grid(input);
-    let graph = grid_to_graph(&grid, false);
+register_day!(23, Day23, "A Long Walk", tags: ["grid", "graph"]);
 
-    longest_path(&graph, graph.start, graph.end).to_string()
-}
+impl Puzzle for Day23 {
+    type Parsed<'a> = Grid;
+    const ALGORITHM: &'static str = "junction-graph-dfs-v1";
+
+    fn parse(input: &[u8]) -> anyhow::Result<Grid> {
+        Ok(parse_grid(input)?)
+    }
 
-pub fn part2(input: &str) -> String {
-    let grid = parse_grid(input);
-    let graph = grid_to_graph(&grid, true);
+    fn solve1(grid: &Grid, cancel: &Token) -> anyhow::Result<Answer> {
+        Ok(grid_to_graph(grid, false)?.longest_path(cancel)?.into())
+    }
 
-    longest_path(&graph, graph.start, graph.end).to_string()
+    fn solve2(grid: &Grid, cancel: &Token) -> anyhow::Result<Answer> {
+        Ok(grid_to_graph(grid, true)?.longest_path(cancel)?.into())
+    }
 }
@@ -1,7 +1,20 @@
-use fraction::GenericFraction;
+use alloc::{string::ToString, vec::Vec};
+use core::fmt;
+
+use fraction::{GenericFraction, Sign};
 use num::Zero;
+use num_bigint::BigInt;
+use serde::Serialize;
+
+use crate::{
+    cancel::Token,
+    parse::{self, ParseError},
+    stream::Lines,
+    Answer, Day24, Puzzle,
+};
 
-struct Hailstone {
+#[derive(Serialize)]
+pub struct Hailstone {
     px: isize,
     py: isize,
     pz: isize,
@@ -10,37 +23,103 @@ struct Hailstone {
     vz: isize,
 }
 
-impl std::fmt::Debug for Hailstone {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}, {}, {} @ {}, {}, {}", self.px, self.py, self.pz, self.vx, self.vy, self.vz)
+impl Hailstone {
+    fn pos(&self) -> [i128; 3] {
+        [self.px, self.py, self.pz].map(|c| c as i128)
+    }
+
+    fn vel(&self) -> [i128; 3] {
+        [self.vx, self.vy, self.vz].map(|c| c as i128)
     }
 }
 
-fn parse_hailstone(line: &str) -> Hailstone {
-    let rest = line.trim();
-    let (px, rest) = rest.split_once(", ").unwrap();
-    let (py, rest) = rest.split_once(", ").unwrap();
-    let (pz, rest) = rest.split_once(" @ ").unwrap();
-    let (vx, rest) = rest.split_once(", ").unwrap();
-    let (vy, vz) = rest.split_once(", ").unwrap();
-    Hailstone {
-        px: px.trim().parse().unwrap(),
-        py: py.trim().parse().unwrap(),
-        pz: pz.trim().parse().unwrap(),
-        vx: vx.trim().parse().unwrap(),
-        vy: vy.trim().parse().unwrap(),
-        vz: vz.trim().parse().unwrap(),
+impl core::fmt::Debug for Hailstone {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{}, {}, {} @ {}, {}, {}",
+            self.px, self.py, self.pz, self.vx, self.vy, self.vz
+        )
     }
 }
 
-fn parse_input(input: &str) -> Vec<Hailstone> {
-    input.lines().map(parse_hailstone).collect()
+fn parse_hailstone(line: &str) -> Result<Hailstone, ParseError> {
+    let rest = line.trim();
+    let (px, rest) = parse::split_once(rest, ", ")?;
+    let (py, rest) = parse::split_once(rest, ", ")?;
+    let (pz, rest) = parse::split_once(rest, " @ ")?;
+    let (vx, rest) = parse::split_once(rest, ", ")?;
+    let (vy, vz) = parse::split_once(rest, ", ")?;
+    Ok(Hailstone {
+        px: parse::num(px.trim())?,
+        py: parse::num(py.trim())?,
+        pz: parse::num(pz.trim())?,
+        vx: parse::num(vx.trim())?,
+        vy: parse::num(vy.trim())?,
+        vz: parse::num(vz.trim())?,
+    })
+}
+
+fn parse_input(input: &str) -> Result<Vec<Hailstone>, ParseError> {
+    parse::lines(input, parse_hailstone)
 }
 
-fn xy_intersect_in_xy_range(a: &Hailstone, b: &Hailstone, min: usize, max: usize) -> bool {
-    let (px, py, vx, vy) = (a.px as i128, a.py as i128, a.vx as i128, a.vy as i128);
-    let (qx, qy, wx, wy) = (b.px as i128, b.py as i128, b.vx as i128, b.vy as i128);
-    let (dx, dy) = (px - qx, py - qy);
+/// Which coordinates of the hailstones are looked at when intersecting their paths. Part 1
+/// ignores the Z axis, i.e. projects the paths onto the XY plane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Projection {
+    XY,
+    XZ,
+    YZ,
+    /// The paths themselves in 3D, without projecting them.
+    Full,
+}
+
+impl Projection {
+    fn axes(self) -> &'static [usize] {
+        match self {
+            Projection::XY => &[0, 1],
+            Projection::XZ => &[0, 2],
+            Projection::YZ => &[1, 2],
+            Projection::Full => &[0, 1, 2],
+        }
+    }
+}
+
+// Whether the projected paths of two hailstones intersect at some point in the future of both
+// of them, with all the projected coordinates of that point lying in min..=max.
+fn intersect_in_range(
+    a: &Hailstone,
+    b: &Hailstone,
+    projection: Projection,
+    min: usize,
+    max: usize,
+) -> bool {
+    let (p, v) = (a.pos(), a.vel());
+    let (q, w) = (b.pos(), b.vel());
+    let d = [p[0] - q[0], p[1] - q[1], p[2] - q[2]];
+    let axes = projection.axes();
+    let det = |i: usize, j: usize| v[i] * w[j] - v[j] * w[i];
+
+    // In 3D, the paths can only intersect if they lie in a common plane, i.e. if d is
+    // orthogonal to the normal v x w. Then their intersection is the same as that of their
+    // projections onto any coordinate plane in which they aren't parallel.
+    if axes.len() == 3 && d[0] * det(1, 2) + d[1] * det(2, 0) + d[2] * det(0, 1) != 0 {
+        return false;
+    }
+    let mut planes = axes
+        .iter()
+        .enumerate()
+        .flat_map(|(n, &i)| axes[n + 1..].iter().map(move |&j| (i, j)));
+    let Some((x, y)) = planes.clone().find(|&(i, j)| det(i, j) != 0) else {
+        // the lines are either parallel or coincident.
+        // the lines are coincident if d is a multiple of v
+        // so dx/vx = dy/vy (and likewise for every other pair of axes)
+        // => dx * vy = dy * vx iff the lines are coincident
+        return planes.all(|(i, j)| d[i] * v[j] == d[j] * v[i]);
+    };
+    let (vx, vy, wx, wy) = (v[x], v[y], w[x], w[y]);
+    let (dx, dy) = (d[x], d[y]);
 
     // we want to solve the system of linear equations:
     // px + t * vx = qx + s * wx
@@ -56,14 +135,6 @@ fn xy_intersect_in_xy_range(a: &Hailstone, b: &Hailstone, min: usize, max: usize
     // which is equivalent to (s, t) = A^-1 * b = 1/det(A) (vx*dy - vy*dx, wx*dy - wy*dx)
 
     let det = vx * wy - vy * wx;
-    if det == 0 {
-        // the lines are either parallel or coincident.
-        // the lines are coincident if (dx, dy) is a multiple of (vx, vy)
-        // so dx/vx = dy/vy
-        // => dx * vy = dy * vx iff the lines are coincident
-        return dx * vy == dy * vx;
-    }
-
     let scaled_s = vx * dy - vy * dx;
     let scaled_t = wx * dy - wy * dx;
 
@@ -76,40 +147,77 @@ fn xy_intersect_in_xy_range(a: &Hailstone, b: &Hailstone, min: usize, max: usize
     // now check if min <= px + t * vx <= max
     // => min - px <= t * vx <= max - px
     // => scaled_t * vx lies between det(min - px) and det(max - px)
-    // and same for y
+    // and same for y (and z in 3D)
 
-    let mut min_x = (min as i128 - px) * det;
-    let mut max_x = (max as i128 - px) * det;
-    let mut min_y = (min as i128 - py) * det;
-    let mut max_y = (max as i128 - py) * det;
-    if det < 0 {
-        (min_x, max_x) = (max_x, min_x);
-        (min_y, max_y) = (max_y, min_y);
-    }
-    (min_x..=max_x).contains(&(scaled_t * vx)) && (min_y..=max_y).contains(&(scaled_t * vy))
+    axes.iter().all(|&k| {
+        let mut min_k = (min as i128 - p[k]) * det;
+        let mut max_k = (max as i128 - p[k]) * det;
+        if det < 0 {
+            (min_k, max_k) = (max_k, min_k);
+        }
+        (min_k..=max_k).contains(&(scaled_t * v[k]))
+    })
 }
 
-pub fn part1(input: &str) -> String {
-    let stones = parse_input(input);
+const TEST_AREA: (usize, usize) = (200000000000000, 400000000000000);
+
+/// Counts the pairs of hailstones whose paths, projected according to `projection`,
+/// intersect inside the test area.
+fn count_intersections(stones: &[Hailstone], projection: Projection) -> usize {
+    let (min, max) = TEST_AREA;
     let mut count = 0usize;
     for (i, a) in stones.iter().enumerate() {
         for b in &stones[i + 1..] {
-            count += usize::from(xy_intersect_in_xy_range(a, b, 200000000000000, 400000000000000));
+            count += usize::from(intersect_in_range(a, b, projection, min, max));
         }
     }
-    count.to_string()
+    count
+}
+
+/// The number of intersections inside the test area for every projection. Part 1 only
+/// looks at the XY plane.
+#[derive(Serialize)]
+pub struct Details {
+    xy: usize,
+    xz: usize,
+    yz: usize,
+    full: usize,
+}
+
+impl fmt::Display for Details {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Intersections in the XY plane: {}", self.xy)?;
+        writeln!(f, "Intersections in the XZ plane: {}", self.xz)?;
+        writeln!(f, "Intersections in the YZ plane: {}", self.yz)?;
+        write!(f, "Intersections in 3D: {}", self.full)
+    }
+}
+
+pub fn details(input: &str) -> anyhow::Result<Details> {
+    let stones = parse_input(input)?;
+    Ok(Details {
+        xy: count_intersections(&stones, Projection::XY),
+        xz: count_intersections(&stones, Projection::XZ),
+        yz: count_intersections(&stones, Projection::YZ),
+        full: count_intersections(&stones, Projection::Full),
+    })
 }
 
 fn cross_prod(u: [isize; 3], v: [isize; 3]) -> [isize; 3] {
-    [u[1] * v[2] - u[2] * v[1], u[2] * v[0] - u[0] * v[2], u[0] * v[1] - u[1] * v[0]]
+    [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ]
 }
 
 fn cross_matrix(v: [isize; 3]) -> [[isize; 3]; 3] {
     [[0, -v[2], v[1]], [v[2], 0, -v[0]], [-v[1], v[0], 0]]
 }
 
-// solve a system of linear equations using Gaussian elimination
-fn solve(mat: [[isize; 6]; 6], rhs: [isize; 6]) -> [GenericFraction<u128>; 6] {
+// solve a system of linear equations using Gaussian elimination.
+// returns None if the matrix is singular.
+fn solve(mat: [[isize; 6]; 6], rhs: [isize; 6]) -> Option<[GenericFraction<u128>; 6]> {
     let mut mat = mat.map(|row| row.map(GenericFraction::from));
     let mut rhs = rhs.map(GenericFraction::from);
 
@@ -124,13 +232,14 @@ fn solve(mat: [[isize; 6]; 6], rhs: [isize; 6]) -> [GenericFraction<u128>; 6] {
             }
         }
         if mat[i][i].is_zero() {
-            panic!("singular matrix")
+            return None;
         }
 
         for j in i + 1..6 {
             let factor = mat[j][i] / mat[i][i];
-            for k in i..6 {
-                mat[j][k] -= factor * mat[i][k];
+            let pivot_row = mat[i];
+            for (dst, src) in mat[j][i..].iter_mut().zip(&pivot_row[i..]) {
+                *dst -= factor * *src;
             }
             rhs[j] -= factor * rhs[i];
         }
@@ -145,12 +254,14 @@ fn solve(mat: [[isize; 6]; 6], rhs: [isize; 6]) -> [GenericFraction<u128>; 6] {
         mat[i][i] = GenericFraction::from(1i32);
     }
 
-    rhs
+    Some(rhs)
 }
 
-pub fn part2(input: &str) -> String {
-    let stones = parse_input(input);
-    let [s0, s1, s2, ..] = &*stones else { unreachable!("too few stones") };
+/// The sum of the coordinates of the position to throw the rock from.
+fn throw_rock(stones: &[Hailstone]) -> anyhow::Result<Answer> {
+    let [s0, s1, s2, ..] = stones else {
+        anyhow::bail!("part 2 needs at least 3 hailstones")
+    };
 
     // Insane black magic math
     let mut mat = [[0isize; 6]; 6];
@@ -181,7 +292,54 @@ pub fn part2(input: &str) -> String {
         }
     }
 
-    let [px, py, pz, ..] = solve(mat, rhs);
+    let Some([px, py, pz, ..]) = solve(mat, rhs) else {
+        anyhow::bail!("the first 3 hailstones don't determine the rock's path")
+    };
+
+    // The rock starts at integer coordinates, so the sum is an integer as well.
+    let sum = px + py + pz;
+    Ok(match (sum.numer(), sum.denom(), sum.sign()) {
+        (Some(&n), Some(&1), Some(Sign::Minus)) => (-BigInt::from(n)).into(),
+        (Some(&n), Some(&1), _) => n.into(),
+        _ => sum.to_string().into(),
+    })
+}
 
-    (px + py + pz).to_string()
+/// Solves `part` on a streamed input. Part 1 still needs all the hailstones, but not the
+/// text they were parsed from, and part 2 only keeps the first 3.
+pub fn stream(part: usize, lines: &mut Lines<'_>) -> anyhow::Result<Answer> {
+    let mut stones = Vec::new();
+    lines.for_each(|line| {
+        let stone = parse_hailstone(line)?;
+        if part == 1 || stones.len() < 3 {
+            stones.push(stone);
+        }
+        Ok(())
+    })?;
+    let never = Token::default();
+    match part {
+        1 => Day24::solve1(&stones, &never),
+        2 => Day24::solve2(&stones, &never),
+        _ => panic!("there is no part {}", part),
+    }
+}
+
+register_day!(24, Day24, "Never Tell Me The Odds", tags: ["geometry", "math"]);
+
+impl Puzzle for Day24 {
+    type Parsed<'a> = Vec<Hailstone>;
+    const ALGORITHM: &'static str = "cross-product-system-v1";
+
+    fn parse(input: &[u8]) -> anyhow::Result<Vec<Hailstone>> {
+        let input = parse::utf8(input)?;
+        Ok(parse_input(input)?)
+    }
+
+    fn solve1(stones: &Vec<Hailstone>, _: &Token) -> anyhow::Result<Answer> {
+        Ok(count_intersections(stones, Projection::XY).into())
+    }
+
+    fn solve2(stones: &Vec<Hailstone>, _: &Token) -> anyhow::Result<Answer> {
+        throw_rock(stones)
+    }
 }
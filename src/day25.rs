@@ -1,43 +1,40 @@
-use ahash::AHashMap;
-use smallvec::SmallVec;
+use alloc::vec::Vec;
+use core::{fmt, ops::ControlFlow};
 
-struct Graph {
-    vertices: Vec<SmallVec<[u16; 10]>>,
-}
-fn parse_input(input: &str) -> Graph {
-    fn vertex_index<'a>(
-        name: &'a str,
-        indices: &mut AHashMap<&'a str, u16>,
-        vertices: &mut Vec<SmallVec<[u16; 10]>>,
-    ) -> u16 {
-        if let Some(&index) = indices.get(name) {
-            index
-        } else {
-            let index = vertices.len() as u16;
-            indices.insert(name, index);
-            vertices.push(SmallVec::new());
-            index
-        }
-    }
+use serde::Serialize;
 
-    let mut indices = AHashMap::new();
-    let mut vertices = Vec::new();
+use crate::{
+    cancel::{Cancelled, Token},
+    graph,
+    parse::{self, ParseError},
+    Answer, Day25, Puzzle,
+};
 
-    for line in input.lines() {
-        let (node, out) = line.split_once(':').unwrap();
-        let node = vertex_index(node, &mut indices, &mut vertices);
+/// The wiring diagram, with an edge in both directions for every connection.
+pub type Graph = graph::Graph;
+
+fn parse_input(input: &str) -> Result<Graph, ParseError> {
+    let mut builder = graph::Builder::new();
+
+    for (i, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (node, out) = parse::split_once(line, ":").map_err(|e| e.at_line(i + 1))?;
+        let node = builder.vertex(node);
         for edge in out.split_ascii_whitespace() {
-            let dst = vertex_index(edge, &mut indices, &mut vertices);
-            if !vertices[node as usize].contains(&dst) {
-                vertices[node as usize].push(dst);
+            let dst = builder.vertex(edge);
+            let graph = builder.graph_mut();
+            if !graph.contains_edge(node, dst) {
+                graph.add_edge(node, dst, ());
             }
-            if !vertices[dst as usize].contains(&node) {
-                vertices[dst as usize].push(node);
+            if !graph.contains_edge(dst, node) {
+                graph.add_edge(dst, node, ());
             }
         }
     }
 
-    Graph { vertices }
+    Ok(builder.build().0)
 }
 
 struct AdjacencyMatrix {
@@ -57,25 +54,37 @@ impl AdjacencyMatrix {
 
 fn make_adj_matrix(graph: &Graph) -> AdjacencyMatrix {
     let mut matrix = AdjacencyMatrix {
-        matrix: vec![0; graph.vertices.len().pow(2)],
-        n: graph.vertices.len(),
+        matrix: vec![0; graph.len().pow(2)],
+        n: graph.len(),
     };
 
-    for (src, dsts) in graph.vertices.iter().enumerate() {
-        for &dst in dsts {
-            matrix.set(src, dst as usize, 1);
-        }
+    for (src, dst, _) in graph.edges() {
+        matrix.set(src, dst, 1);
     }
 
     matrix
 }
 
-fn stoer_wagner(mat: &mut AdjacencyMatrix) -> (i32, Vec<u16>) {
+/// How far [`stoer_wagner`] got, reported after every phase.
+pub struct Progress {
+    pub phase: usize,
+    pub phases: usize,
+    /// The weight of the smallest cut found so far.
+    pub best_cut: i32,
+}
+
+/// Finds a minimum cut, returning its weight and the vertices on one side of it. After
+/// every phase, `on_phase` gets called, and can stop the search by returning `Break`,
+/// in which case `None` is returned.
+fn stoer_wagner(
+    mat: &mut AdjacencyMatrix,
+    mut on_phase: impl FnMut(&Progress) -> ControlFlow<()>,
+) -> Option<(i32, Vec<usize>)> {
     let mut best = (i32::MAX, vec![]);
     let n = mat.n;
-    let mut co: Vec<Vec<u16>> = vec![];
+    let mut co: Vec<Vec<usize>> = vec![];
     for i in 0..n {
-        co.push(vec![i as u16]);
+        co.push(vec![i]);
     }
 
     for ph in 1..n {
@@ -92,7 +101,7 @@ fn stoer_wagner(mat: &mut AdjacencyMatrix) -> (i32, Vec<u16>) {
         if w[t] - mat.get(t, t) < best.0 {
             best = (w[t] - mat.get(t, t), co[t].clone());
         }
-        let mut tmp = std::mem::take(&mut co[s]);
+        let mut tmp = core::mem::take(&mut co[s]);
         tmp.extend_from_slice(&co[t]);
         co[s] = tmp;
         for i in 0..n {
@@ -100,17 +109,78 @@ fn stoer_wagner(mat: &mut AdjacencyMatrix) -> (i32, Vec<u16>) {
             mat.set(i, s, mat.get(s, i));
         }
         mat.set(0, t, i32::MIN);
+
+        let progress = Progress {
+            phase: ph,
+            phases: n - 1,
+            best_cut: best.0,
+        };
+        if on_phase(&progress).is_break() {
+            return None;
+        }
     }
 
-    best
+    Some(best)
 }
 
-pub fn part1(input: &str) -> String {
-    let graph = parse_input(input);
-    let result = stoer_wagner(&mut make_adj_matrix(&graph));
-    (result.1.len() * (graph.vertices.len() - result.1.len())).to_string()
+/// The minimum cut that part 1 finds, and how many phases it took.
+#[derive(Serialize)]
+pub struct Details {
+    cut_weight: i32,
+    group_sizes: [usize; 2],
+    phases: usize,
 }
 
-pub fn part2(_input: &str) -> String {
-    String::from("Day 25 has no part 2!")
+impl fmt::Display for Details {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Minimum cut: {} edges", self.cut_weight)?;
+        writeln!(
+            f,
+            "Group sizes: {} and {}",
+            self.group_sizes[0], self.group_sizes[1]
+        )?;
+        write!(f, "Stoer-Wagner phases: {}", self.phases)
+    }
+}
+
+/// Like part 1, but `on_phase` gets told about the progress after every phase, since
+/// this can take a while for big graphs.
+pub fn details(input: &str, mut on_phase: impl FnMut(&Progress)) -> anyhow::Result<Details> {
+    let graph = parse_input(input)?;
+    let n = graph.len();
+    let (cut_weight, group) = stoer_wagner(&mut make_adj_matrix(&graph), |progress| {
+        on_phase(progress);
+        ControlFlow::Continue(())
+    })
+    .expect("the search doesn't get stopped");
+    Ok(Details {
+        cut_weight,
+        group_sizes: [group.len(), n - group.len()],
+        phases: n.saturating_sub(1),
+    })
+}
+
+register_day!(25, Day25, "Snowverload", tags: ["graph"]);
+
+impl Puzzle for Day25 {
+    type Parsed<'a> = Graph;
+    const ALGORITHM: &'static str = "stoer-wagner-v1";
+
+    fn parse(input: &[u8]) -> anyhow::Result<Graph> {
+        let input = parse::utf8(input)?;
+        Ok(parse_input(input)?)
+    }
+
+    fn solve1(graph: &Graph, cancel: &Token) -> anyhow::Result<Answer> {
+        let result = stoer_wagner(&mut make_adj_matrix(graph), |_| match cancel.check() {
+            Ok(()) => ControlFlow::Continue(()),
+            Err(_) => ControlFlow::Break(()),
+        })
+        .ok_or(Cancelled)?;
+        Ok((result.1.len() * (graph.len() - result.1.len())).into())
+    }
+
+    fn solve2(_graph: &Graph, _: &Token) -> anyhow::Result<Answer> {
+        Ok("Day 25 has no part 2!".into())
+    }
 }
@@ -1,4 +1,7 @@
-use std::ops::Range;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::{cancel::Token, lex, parse::ParseError, Answer, Day3, Puzzle};
 
 #[derive(Debug)]
 struct Number {
@@ -10,58 +13,49 @@ struct Number {
 
 #[derive(Debug)]
 struct Symbol {
-    ch: char,
+    ch: u8,
     line: usize,
     column: usize,
 }
 
 #[derive(Debug)]
-struct Line {
+pub struct Line {
     numbers: Vec<Number>,
     symbols: Vec<Symbol>,
 }
 
-fn parse_line(line: &str, line_number: usize) -> Line {
-    let mut s = line;
+fn parse_line(line: &[u8], line_number: usize) -> Result<Line, ParseError> {
     let mut i = 0;
     let mut numbers = Vec::new();
     let mut symbols = Vec::new();
-    while !s.is_empty() {
-        let Some(j) = s.find(|ch| ch != '.') else {
-            break;
-        };
-        i += j;
-        s = &s[j..];
-        let ch = s.chars().next().unwrap();
-        if ch.is_numeric() {
-            let end = s.find(|ch: char| !ch.is_numeric()).unwrap_or(s.len());
-            let number = s[..end].parse().unwrap();
+    while let Some(&ch) = line.get(i) {
+        if ch == b'.' {
+            i += 1;
+        } else if ch.is_ascii_digit() {
+            let end = i + lex::digits(&line[i..]);
+            let number = lex::uint(&line[i..end]).map_err(|e| e.at(line_number + 1, i + 1))?;
             numbers.push(Number {
                 value: number,
                 line: line_number,
                 column: i,
-                length: end,
+                length: end - i,
             });
-            s = &s[end..];
-            i += end;
+            i = end;
         } else {
             symbols.push(Symbol {
                 ch,
                 line: line_number,
                 column: i,
             });
-            s = &s[1..];
             i += 1;
         }
     }
 
-    Line { numbers, symbols }
+    Ok(Line { numbers, symbols })
 }
 
-fn parse_input(input: &str) -> Vec<Line> {
-    input
-        .trim()
-        .lines()
+fn parse_input(input: &[u8]) -> Result<Vec<Line>, ParseError> {
+    lex::lines(input.trim_ascii())
         .enumerate()
         .map(|(i, line)| parse_line(line, i))
         .collect()
@@ -79,17 +73,8 @@ fn num_neighbors_symbol(grid: &[Line], number: &Number) -> bool {
         .any(|s| range.contains(&s.column))
 }
 
-pub fn part1(input: &str) -> String {
-    let grid = parse_input(input);
-    let nums = grid.iter().flat_map(|line| &line.numbers);
-    nums.filter(|num| num_neighbors_symbol(&grid, num))
-        .map(|num| num.value)
-        .sum::<usize>()
-        .to_string()
-}
-
 fn gear_ratio(grid: &[Line], symbol: &Symbol) -> Option<usize> {
-    if symbol.ch != '*' {
+    if symbol.ch != b'*' {
         return None;
     }
     
@@ -118,11 +103,28 @@ fn gear_ratio(grid: &[Line], symbol: &Symbol) -> Option<usize> {
     Some(lhs.value * rhs.value)
 }
 
-pub fn part2(input: &str) -> String {
-    let grid = parse_input(input);
+register_day!(3, Day3, "Gear Ratios", tags: ["grid"]);
+
+impl Puzzle for Day3 {
+    type Parsed<'a> = Vec<Line>;
+    const ALGORITHM: &'static str = "symbol-neighbor-scan-v1";
 
-    let symbols = grid.iter().flat_map(|line| &line.symbols);
-    symbols.filter_map(|symbol| gear_ratio(&grid, symbol))
-        .sum::<usize>()
-        .to_string()
+    fn parse(input: &[u8]) -> anyhow::Result<Vec<Line>> {
+        Ok(parse_input(input)?)
+    }
+
+    fn solve1(grid: &Vec<Line>, _: &Token) -> anyhow::Result<Answer> {
+        let nums = grid.iter().flat_map(|line| &line.numbers);
+        Ok(nums.filter(|num| num_neighbors_symbol(grid, num))
+            .map(|num| num.value)
+            .sum::<usize>()
+            .into())
+    }
+
+    fn solve2(grid: &Vec<Line>, _: &Token) -> anyhow::Result<Answer> {
+        let symbols = grid.iter().flat_map(|line| &line.symbols);
+        Ok(symbols.filter_map(|symbol| gear_ratio(grid, symbol))
+            .sum::<usize>()
+            .into())
+    }
 }
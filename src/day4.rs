@@ -1,6 +1,14 @@
-use std::collections::VecDeque;
+use alloc::{collections::VecDeque, vec::Vec};
 
-struct Card {
+use crate::{
+    cancel::Token,
+    parse::{self, ParseError},
+    Answer, Day4, Puzzle,
+};
+#[cfg(feature = "std")]
+use crate::stream::Lines;
+
+pub struct Card {
     // The input only seems to contain numbers up to 100, so we can use a
     // 128-bit integer as a bitset. This dramatically speeds up the intersection
     // counting compared to using a hashset, reducing the runtime by ~75-80%.
@@ -11,55 +19,93 @@ struct Card {
     nums: u128,
 }
 
-fn parse_card(line: &str) -> Card {
-    let s = line.strip_prefix("Card ").unwrap();
-    let (_, s) = s.split_once(':').unwrap();
-    let (winning, nums) = s.split_once('|').unwrap();
+fn parse_card(line: &str) -> Result<Card, ParseError> {
+    let s = parse::strip_prefix(line, "Card ")?;
+    let (_, s) = parse::split_once(s, ":")?;
+    let (winning, nums) = parse::split_once(s, "|")?;
 
-    fn nums_to_bits(s: &str) -> u128 {
-        s.split_whitespace()
-            .map(|n| n.parse::<u32>().unwrap())
-            .fold(0u128, |acc, n| acc | 1u128.checked_shl(n).expect("Can't handle 3-digit numbers"))
+    fn nums_to_bits(s: &str) -> Result<u128, ParseError> {
+        s.split_whitespace().try_fold(0u128, |acc, n| {
+            let n = parse::num::<u32>(n)?;
+            let bit = 1u128
+                .checked_shl(n)
+                .ok_or_else(|| ParseError::malformed("Can't handle 3-digit numbers"))?;
+            Ok(acc | bit)
+        })
     }
 
-    let winning = nums_to_bits(winning);
-    let nums = nums_to_bits(nums);
+    let winning = nums_to_bits(winning)?;
+    let nums = nums_to_bits(nums)?;
 
-    Card { winning, nums }
+    Ok(Card { winning, nums })
 }
 
-fn parse_input(input: &str) -> Vec<Card> {
-    input.lines().map(parse_card).collect()
+fn parse_input(input: &str) -> Result<Vec<Card>, ParseError> {
+    parse::lines(input, parse_card)
 }
 
-pub fn part1(input: &str) -> String {
-    let cards = parse_input(input);
-
-    cards
-        .iter()
-        .map(|card| {
-            let winning_nums = (card.winning & card.nums).count_ones();
-            if winning_nums == 0 {
-                0
-            } else {
-                1 << (winning_nums - 1)
-            }
-        })
-        .sum::<usize>()
-        .to_string()
+/// Solves `part` on a streamed input. For part 2, only the copies won for the cards
+/// ahead are kept around.
+#[cfg(feature = "std")]
+pub fn stream(part: usize, lines: &mut Lines<'_>) -> anyhow::Result<Answer> {
+    assert!(part == 1 || part == 2, "there is no part {}", part);
+    let mut total = 0usize;
+    let mut copies = VecDeque::new();
+    lines.for_each(|line| {
+        let card = parse_card(line)?;
+        let winning_nums = (card.winning & card.nums).count_ones() as usize;
+        if part == 1 {
+            total += if winning_nums == 0 { 0 } else { 1 << (winning_nums - 1) };
+            return Ok(());
+        }
+        let n = 1 + copies.pop_front().unwrap_or(0);
+        total += n;
+        if copies.len() < winning_nums {
+            copies.resize(winning_nums, 0);
+        }
+        copies.iter_mut().take(winning_nums).for_each(|c| *c += n);
+        Ok(())
+    })?;
+    Ok(total.into())
 }
 
-pub fn part2(input: &str) -> String {
-    let cards = parse_input(input);
-    let mut queue = VecDeque::from_iter(cards.into_iter().map(|card| (card, 1usize)));
+register_day!(4, Day4, "Scratchcards", tags: ["sets"]);
 
-    let mut total = 0;
+impl Puzzle for Day4 {
+    type Parsed<'a> = Vec<Card>;
+    const ALGORITHM: &'static str = "u128-bitset-v1";
 
-    while let Some((card, n)) = queue.pop_front() {
-        total += n;
-        let winning_nums = (card.winning & card.nums).count_ones() as usize;
-        queue.iter_mut().take(winning_nums).for_each(|p| p.1 += n);
+    fn parse(input: &[u8]) -> anyhow::Result<Vec<Card>> {
+        let input = parse::utf8(input)?;
+        Ok(parse_input(input)?)
     }
 
-    total.to_string()
+    fn solve1(cards: &Vec<Card>, _: &Token) -> anyhow::Result<Answer> {
+        Ok(cards
+            .iter()
+            .map(|card| {
+                let winning_nums = (card.winning & card.nums).count_ones();
+                if winning_nums == 0 {
+                    0
+                } else {
+                    1 << (winning_nums - 1)
+                }
+            })
+            .sum::<usize>()
+            .into())
+    }
+
+    fn solve2(cards: &Vec<Card>, _: &Token) -> anyhow::Result<Answer> {
+        let mut queue = VecDeque::from_iter(cards.iter().map(|card| (card, 1usize)));
+
+        let mut total = 0;
+
+        while let Some((card, n)) = queue.pop_front() {
+            total += n;
+            let winning_nums = (card.winning & card.nums).count_ones() as usize;
+            queue.iter_mut().take(winning_nums).for_each(|p| p.1 += n);
+        }
+
+        Ok(total.into())
+    }
 }
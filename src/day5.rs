@@ -1,181 +1,53 @@
+use alloc::vec::Vec;
 use core::fmt;
-use std::{ops::Range, str::Lines};
+use core::{iter::Enumerate, ops::Range, str::Lines};
 
-#[derive(Debug)]
-struct Map {
-    ranges: Vec<MapRange>,
-}
-
-#[derive(Clone, Copy)]
-struct MapRange {
-    dst: usize,
-    src: usize,
-    len: usize,
-}
+use anyhow::Context;
+use serde::Serialize;
 
-impl MapRange {
-    fn take(&mut self, len: usize) {
-        assert!(len <= self.len);
-        self.len -= len;
-        self.src += len;
-        self.dst += len;
-    }
-}
-
-impl fmt::Debug for MapRange {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}..{} -> {}..{}",
-            self.src,
-            self.src + self.len,
-            self.dst,
-            self.dst + self.len
-        )
-    }
-}
+use crate::{
+    cancel::Token,
+    intervals::{RangeMap, RangeSet, Shift},
+    parse::{self, ParseError},
+    Answer, Day5, Puzzle,
+};
 
 #[derive(Debug)]
-struct Input {
+pub struct Input {
     seeds: Vec<usize>,
-    seed_to_soil: Map,
-    soil_to_fertilizer: Map,
-    fertilizer_to_water: Map,
-    water_to_light: Map,
-    light_to_temp: Map,
-    temp_to_humidity: Map,
-    humidity_to_location: Map,
-}
-
-impl Map {
-    fn map(&self, seed: usize) -> usize {
-        let range_idx = match self.ranges.partition_point(|r| r.src <= seed) {
-            0 => return seed,
-            n => n - 1,
-        };
-        let range = &self.ranges[range_idx];
-        let offset = seed - range.src;
-        if offset < range.len {
-            range.dst + offset
-        } else {
-            seed
-        }
-    }
-
-    /// Returns a map `m` such that `m.map_seed(seed) == self.map_seed(rhs.map_seed(seed))`
-    fn compose(&self, rhs: &Map) -> Map {
-        // This works similarly to the merge step of merge sort. We sort the ranges of rhs by
-        // their destination start and the ranges of lhs by their source start. Then we iterate
-        // through both lists, merging the ranges as we go.
-
-        let mut lhs_ranges = self.ranges.clone();
-        let mut rhs_ranges = rhs.ranges.clone();
-        rhs_ranges.sort_unstable_by_key(|r| r.dst);
-
-        let mut out_ranges = Vec::new();
-
-        let (mut i, mut j) = (0, 0);
-
-        while i < lhs_ranges.len() && j < rhs_ranges.len() {
-            let lhs_range = &mut lhs_ranges[i];
-            let rhs_range = &mut rhs_ranges[j];
-
-            if lhs_range.len == 0 {
-                i += 1;
-                continue;
-            }
-
-            if rhs_range.len == 0 {
-                j += 1;
-                continue;
-            }
-
-            if lhs_range.src < rhs_range.dst {
-                let len = (rhs_range.dst - lhs_range.src).min(lhs_range.len);
-                out_ranges.push(MapRange {
-                    dst: lhs_range.dst,
-                    src: lhs_range.src,
-                    len,
-                });
-                lhs_range.take(len);
-                continue;
-            }
-
-            if rhs_range.dst < lhs_range.src {
-                let len = (lhs_range.src - rhs_range.dst).min(rhs_range.len);
-                out_ranges.push(MapRange {
-                    dst: rhs_range.dst,
-                    src: rhs_range.src,
-                    len,
-                });
-                rhs_range.take(len);
-                continue;
-            }
-            let len = lhs_range.len.min(rhs_range.len);
-            out_ranges.push(MapRange {
-                dst: lhs_range.dst,
-                src: rhs_range.src,
-                len,
-            });
-            lhs_range.take(len);
-            rhs_range.take(len);
-        }
-
-        while i < lhs_ranges.len() {
-            let lhs_range = &lhs_ranges[i];
-            if lhs_range.len == 0 {
-                i += 1;
-                continue;
-            }
-            out_ranges.push(*lhs_range);
-            i += 1;
-        }
-
-        while j < rhs_ranges.len() {
-            let rhs_range = &rhs_ranges[j];
-            if rhs_range.len == 0 {
-                j += 1;
-                continue;
-            }
-            out_ranges.push(*rhs_range);
-            j += 1;
-        }
-
-        out_ranges.sort_unstable_by_key(|r| r.src);
-
-        Map { ranges: out_ranges }
-    }
-
-    fn min_output_in_input_range(&self, range: Range<usize>) -> usize {
-        let min_in_map_range = |map_range: &MapRange| {
-            let overlaps = range.start < map_range.src + map_range.len && range.end > map_range.src;
-            if !overlaps {
-                return None;
-            }
-            let offset = range.start.saturating_sub(map_range.src);
-            Some(map_range.dst + offset)
-        };
-
-        self.ranges
-            .iter()
-            .filter_map(min_in_map_range)
-            .min()
-            .unwrap()
-    }
+    seed_to_soil: RangeMap,
+    soil_to_fertilizer: RangeMap,
+    fertilizer_to_water: RangeMap,
+    water_to_light: RangeMap,
+    light_to_temp: RangeMap,
+    temp_to_humidity: RangeMap,
+    humidity_to_location: RangeMap,
 }
 
 impl Input {
     fn map_seed(&self, seed: usize) -> usize {
-        let soil = self.seed_to_soil.map(seed);
-        let fertilizer = self.soil_to_fertilizer.map(soil);
-        let water = self.fertilizer_to_water.map(fertilizer);
-        let light = self.water_to_light.map(water);
-        let temp = self.light_to_temp.map(light);
-        let humidity = self.temp_to_humidity.map(temp);
-        self.humidity_to_location.map(humidity)
+        let soil = self.seed_to_soil.get(seed);
+        let fertilizer = self.soil_to_fertilizer.get(soil);
+        let water = self.fertilizer_to_water.get(fertilizer);
+        let light = self.water_to_light.get(water);
+        let temp = self.light_to_temp.get(light);
+        let humidity = self.temp_to_humidity.get(temp);
+        self.humidity_to_location.get(humidity)
+    }
+
+    fn maps(&self) -> [(&'static str, &RangeMap); 7] {
+        [
+            ("seed-to-soil", &self.seed_to_soil),
+            ("soil-to-fertilizer", &self.soil_to_fertilizer),
+            ("fertilizer-to-water", &self.fertilizer_to_water),
+            ("water-to-light", &self.water_to_light),
+            ("light-to-temperature", &self.light_to_temp),
+            ("temperature-to-humidity", &self.temp_to_humidity),
+            ("humidity-to-location", &self.humidity_to_location),
+        ]
     }
 
-    fn compose_all(&self) -> Map {
+    fn compose_all(&self) -> RangeMap {
         self.humidity_to_location
             .compose(&self.temp_to_humidity)
             .compose(&self.light_to_temp)
@@ -186,72 +58,173 @@ impl Input {
     }
 }
 
-fn parse_seeds(line: &str) -> Vec<usize> {
-    line.strip_prefix("seeds: ")
-        .unwrap()
+fn parse_seeds(line: &str) -> Result<Vec<usize>, ParseError> {
+    parse::strip_prefix(line, "seeds: ")?
         .split_whitespace()
-        .map(|n| n.parse().unwrap())
+        .map(parse::num)
         .collect()
 }
 
-fn parse_map(lines: &mut Lines) -> Map {
+fn parse_range(line: &str) -> Result<Shift, ParseError> {
+    let (dst, line) = parse::split_once(line, " ")?;
+    let (src, len) = parse::split_once(line, " ")?;
+    let dst = parse::num(dst.trim())?;
+    let src = parse::num(src.trim())?;
+    let len = parse::num(len.trim())?;
+    Ok(Shift { dst, src, len })
+}
+
+fn parse_map(lines: &mut Enumerate<Lines>) -> Result<RangeMap, ParseError> {
     let _name = lines.next();
-    let mut ranges = Vec::new();
+    let mut shifts = Vec::new();
 
-    for line in lines {
+    for (i, line) in lines {
         if line.is_empty() {
             break;
         }
-        let (dst, line) = line.split_once(' ').unwrap();
-        let (src, len) = line.split_once(' ').unwrap();
-        let dst = dst.trim().parse().unwrap();
-        let src = src.trim().parse().unwrap();
-        let len = len.trim().parse().unwrap();
-        ranges.push(MapRange { dst, src, len });
+        shifts.push(parse_range(line).map_err(|e| e.at_line(i + 1))?);
     }
 
-    // Maybe this will allow a nice binary search later?
-    ranges.sort_unstable_by_key(|r| r.src);
-
-    Map { ranges }
+    Ok(RangeMap::new(shifts))
 }
 
-fn parse_input(input: &str) -> Input {
-    let mut lines = input.trim().lines();
-    let seeds = parse_seeds(lines.next().unwrap());
+fn parse_input(input: &str) -> Result<Input, ParseError> {
+    let mut lines = input.trim().lines().enumerate();
+    let (_, seeds) = lines
+        .next()
+        .ok_or_else(|| ParseError::malformed("empty input"))?;
+    let seeds = parse_seeds(seeds).map_err(|e| e.at_line(1))?;
     let _ = lines.next();
 
-    Input {
+    Ok(Input {
         seeds,
-        seed_to_soil: parse_map(&mut lines),
-        soil_to_fertilizer: parse_map(&mut lines),
-        fertilizer_to_water: parse_map(&mut lines),
-        water_to_light: parse_map(&mut lines),
-        light_to_temp: parse_map(&mut lines),
-        temp_to_humidity: parse_map(&mut lines),
-        humidity_to_location: parse_map(&mut lines),
+        seed_to_soil: parse_map(&mut lines)?,
+        soil_to_fertilizer: parse_map(&mut lines)?,
+        fertilizer_to_water: parse_map(&mut lines)?,
+        water_to_light: parse_map(&mut lines)?,
+        light_to_temp: parse_map(&mut lines)?,
+        temp_to_humidity: parse_map(&mut lines)?,
+        humidity_to_location: parse_map(&mut lines)?,
+    })
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Diagnostic {
+    /// Two ranges of the same map share some source values. `RangeMap` only ever
+    /// uses the one starting first for them.
+    Overlap {
+        map: &'static str,
+        first: Range<usize>,
+        second: Range<usize>,
+    },
+    /// Source values below the last range of a map that aren't covered by any range,
+    /// so they get mapped to themselves.
+    Gap {
+        map: &'static str,
+        range: Range<usize>,
+    },
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diagnostic::Overlap { map, first, second } => {
+                write!(f, "{map}: {first:?} and {second:?} overlap")
+            }
+            Diagnostic::Gap { map, range } => {
+                write!(f, "{map}: {range:?} isn't covered and maps to itself")
+            }
+        }
     }
 }
 
-pub fn part1(input: &str) -> String {
-    let input = parse_input(input);
-    input
-        .seeds
-        .iter()
-        .map(|s| input.map_seed(*s))
-        .min()
-        .unwrap()
-        .to_string()
+#[derive(Serialize)]
+pub struct Validation {
+    diagnostics: Vec<Diagnostic>,
 }
 
-pub fn part2(input: &str) -> String {
-    let input = parse_input(input);
-    let composed = input.compose_all();
-    let seed_ranges = input.seeds.chunks(2).map(|c| c[0]..c[0] + c[1]);
+impl fmt::Display for Validation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.diagnostics.is_empty() {
+            return write!(f, "No overlaps or gaps found.");
+        }
+        for (i, diagnostic) in self.diagnostics.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{diagnostic}")?;
+        }
+        Ok(())
+    }
+}
 
-    seed_ranges
-        .map(|r| composed.min_output_in_input_range(r))
-        .min()
-        .unwrap()
-        .to_string()
+fn validate_map(name: &'static str, map: &RangeMap, diagnostics: &mut Vec<Diagnostic>) {
+    // The ranges are sorted by their source start, so a range can only overlap
+    // with the ranges directly following it.
+    let shifts = map.shifts();
+    for (i, lhs) in shifts.iter().enumerate() {
+        let lhs = lhs.src_range();
+        for rhs in shifts[i + 1..].iter().map(Shift::src_range) {
+            if rhs.start >= lhs.end {
+                break;
+            }
+            diagnostics.push(Diagnostic::Overlap {
+                map: name,
+                first: lhs.clone(),
+                second: rhs,
+            });
+        }
+    }
+
+    let mut covered_until = 0;
+    for range in shifts.iter().map(Shift::src_range) {
+        if range.start > covered_until {
+            diagnostics.push(Diagnostic::Gap {
+                map: name,
+                range: covered_until..range.start,
+            });
+        }
+        covered_until = covered_until.max(range.end);
+    }
+}
+
+pub fn details(input: &str) -> anyhow::Result<Validation> {
+    let input = parse_input(input)?;
+    let mut diagnostics = Vec::new();
+    for (name, map) in input.maps() {
+        validate_map(name, map, &mut diagnostics);
+    }
+    Ok(Validation { diagnostics })
+}
+
+register_day!(5, Day5, "If You Give A Seed A Fertilizer", tags: ["intervals"]);
+
+impl Puzzle for Day5 {
+    type Parsed<'a> = Input;
+    const ALGORITHM: &'static str = "composed-range-maps-v1";
+
+    fn parse(input: &[u8]) -> anyhow::Result<Input> {
+        let input = parse::utf8(input)?;
+        Ok(parse_input(input)?)
+    }
+
+    fn solve1(input: &Input, _: &Token) -> anyhow::Result<Answer> {
+        let location = input
+            .seeds
+            .iter()
+            .map(|s| input.map_seed(*s))
+            .min()
+            .context("no seeds")?;
+        Ok(location.into())
+    }
+
+    fn solve2(input: &Input, _: &Token) -> anyhow::Result<Answer> {
+        if !input.seeds.len().is_multiple_of(2) {
+            anyhow::bail!("the seeds don't come in pairs");
+        }
+        let seeds: RangeSet = input.seeds.chunks(2).map(|c| c[0]..c[0] + c[1]).collect();
+        let location = input.compose_all().min_image(&seeds).context("no seeds")?;
+        Ok(location.into())
+    }
 }
@@ -1,28 +1,49 @@
+#[cfg(feature = "std")]
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use num_bigint::BigUint;
+
+#[cfg(feature = "std")]
+use crate::stream::Lines;
+use crate::{
+    cancel::Token,
+    parse::{self, ParseError},
+    Answer, Day6, Puzzle,
+};
+
 #[derive(Debug, Clone, Copy)]
 struct Race {
     time: usize,
     record: usize,
 }
 
-fn parse_input_part1(input: &str) -> Vec<Race> {
+// The lines with the times and the distances, without their labels.
+fn split_input(input: &str) -> Result<(&str, &str), ParseError> {
     let mut lines = input.lines();
     let times_line = lines
         .next()
-        .and_then(|s| s.strip_prefix("Time:"))
-        .unwrap()
-        .trim();
+        .ok_or_else(|| ParseError::malformed("missing times"))?;
     let distances_line = lines
         .next()
-        .and_then(|s| s.strip_prefix("Distance:"))
-        .unwrap()
-        .trim();
+        .ok_or_else(|| ParseError::malformed("missing distances"))?;
+    Ok((
+        parse::strip_prefix(times_line, "Time:").map_err(|e| e.at_line(1))?,
+        parse::strip_prefix(distances_line, "Distance:").map_err(|e| e.at_line(2))?,
+    ))
+}
+
+fn parse_input_part1(input: &str) -> Result<Vec<Race>, ParseError> {
+    let (times_line, distances_line) = split_input(input)?;
 
     times_line
         .split_whitespace()
         .zip(distances_line.split_whitespace())
-        .map(|(time, distance)| Race {
-            time: time.parse().unwrap(),
-            record: distance.parse().unwrap(),
+        .map(|(time, distance)| {
+            Ok(Race {
+                time: parse::num(time)?,
+                record: parse::num(distance)?,
+            })
         })
         .collect()
 }
@@ -54,23 +75,14 @@ fn ways_to_win(race: Race) -> usize {
     t - 2 * lo + 1
 }
 
-pub fn part1(input: &str) -> String {
-    let races = parse_input_part1(input);
-
-    races
-        .iter()
-        .map(|&r| ways_to_win(r))
-        .product::<usize>()
-        .to_string()
+// Tries every time the button could be held for, as a check for `ways_to_win`.
+fn ways_to_win_scan(race: Race) -> usize {
+    let Race { time: t, record: r } = race;
+    (0..=t).filter(|&n| n * (t - n) > r).count()
 }
 
-fn parse_input_part2(input: &str) -> Race {
-    let mut lines = input.lines();
-    let time_line = lines.next().and_then(|s| s.strip_prefix("Time:")).unwrap();
-    let distance_line = lines
-        .next()
-        .and_then(|s| s.strip_prefix("Distance:"))
-        .unwrap();
+fn parse_input_part2(input: &str) -> Result<Race, ParseError> {
+    let (time_line, distance_line) = split_input(input)?;
 
     let time = time_line
         .chars()
@@ -82,10 +94,82 @@ fn parse_input_part2(input: &str) -> Race {
         .filter_map(|c| c.to_digit(10))
         .fold(0usize, |acc, d| acc * 10 + d as usize);
 
-    Race { time, record }
+    Ok(Race { time, record })
+}
+
+register_day!(
+    6,
+    Day6,
+    "Wait For It",
+    tags: ["math"],
+    variants: [(1, "scan", part1_scan), (2, "scan", part2_scan), (1, "wide", part1_wide)],
+);
+
+impl Puzzle for Day6 {
+    // Part 2 reads the numbers with the spaces between their digits removed, so the parts
+    // parse the input themselves.
+    type Parsed<'a> = &'a str;
+    const ALGORITHM: &'static str = "quadratic-formula-v1";
+
+    fn parse(input: &[u8]) -> anyhow::Result<&str> {
+        Ok(parse::utf8(input)?)
+    }
+
+    fn solve1(input: &&str, _: &Token) -> anyhow::Result<Answer> {
+        let races = parse_input_part1(input)?;
+
+        Ok(races
+            .iter()
+            .map(|&r| ways_to_win(r))
+            .product::<usize>()
+            .into())
+    }
+
+    fn solve2(input: &&str, _: &Token) -> anyhow::Result<Answer> {
+        let race = parse_input_part2(input)?;
+        Ok(ways_to_win(race).into())
+    }
+}
+
+/// Solves `part` on a streamed input. Only the first two lines get read, since that's all
+/// there is to the input.
+#[cfg(feature = "std")]
+pub fn stream(part: usize, lines: &mut Lines<'_>) -> anyhow::Result<Answer> {
+    let mut input = String::new();
+    for _ in 0..2 {
+        if let Some(line) = lines.next_line()? {
+            input += line;
+            input.push('\n');
+        }
+    }
+    let never = Token::default();
+    match part {
+        1 => Day6::solve1(&input.as_str(), &never),
+        2 => Day6::solve2(&input.as_str(), &never),
+        _ => panic!("there is no part {}", part),
+    }
+}
+
+pub fn part1_scan(input: &str) -> anyhow::Result<Answer> {
+    let races = parse_input_part1(input)?;
+    Ok(races
+        .iter()
+        .map(|&r| ways_to_win_scan(r))
+        .product::<usize>()
+        .into())
+}
+
+pub fn part2_scan(input: &str) -> anyhow::Result<Answer> {
+    let race = parse_input_part2(input)?;
+    Ok(ways_to_win_scan(race).into())
 }
 
-pub fn part2(input: &str) -> String {
-    let race = parse_input_part2(input);
-    ways_to_win(race).to_string()
+/// Part 1 with the product as a big integer, since it overflows with enough races.
+pub fn part1_wide(input: &str) -> anyhow::Result<Answer> {
+    let races = parse_input_part1(input)?;
+    Ok(races
+        .iter()
+        .map(|&r| BigUint::from(ways_to_win(r)))
+        .product::<BigUint>()
+        .into())
 }
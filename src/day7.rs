@@ -1,67 +1,86 @@
+use alloc::{format, vec::Vec};
+
+use crate::{
+    cancel::Token,
+    parse::{self, ParseError},
+    Answer, Day7, Puzzle,
+};
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 struct CardIdx(u8);
 
-impl CardIdx {
-    fn from_byte_part1(b: u8) -> Self {
-        match b {
-            b'2'..=b'9' => Self(b - b'2'),
-            b'T' => Self(8),
-            b'J' => Self(9),
-            b'Q' => Self(10),
-            b'K' => Self(11),
-            b'A' => Self(12),
-            _ => panic!("invalid card byte: {b}"),
-        }
-    }
+/// The rules of a Camel Cards variant: how strong each card is, and which cards are
+/// wild, i.e. count as whatever card makes the hand strongest.
+#[derive(Debug, Clone, Copy)]
+pub struct Rules<'a> {
+    // Card labels from weakest to strongest.
+    order: [u8; 13],
+    wildcards: &'a [u8],
+}
+
+const PART1: Rules<'static> = Rules {
+    order: *b"23456789TJQKA",
+    wildcards: b"",
+};
 
-    fn from_byte_part2(b: u8) -> Self {
-        match b {
-            b'J' => Self(0),
-            b'2'..=b'9' => Self(b - b'1'),
-            b'T' => Self(9),
-            b'Q' => Self(10),
-            b'K' => Self(11),
-            b'A' => Self(12),
-            _ => panic!("invalid card byte: {b}"),
+const PART2: Rules<'static> = Rules {
+    order: *b"J23456789TQKA",
+    wildcards: b"J",
+};
+
+impl<'a> Rules<'a> {
+    /// Rules with the card labels of `order`, from weakest to strongest, of which those in
+    /// `wildcards` are wild. Part 2 is `Rules::new(*b"J23456789TQKA", b"J")`. Fails if a
+    /// label is in `order` twice, or a wildcard isn't in it.
+    pub fn new(order: [u8; 13], wildcards: &'a [u8]) -> Result<Self, ParseError> {
+        if let Some(i) = (1..13).find(|&i| order[..i].contains(&order[i])) {
+            let label = order[i] as char;
+            return Err(ParseError::malformed(format!("the card {label:?} is in the order twice")));
+        }
+        if let Some(&b) = wildcards.iter().find(|b| !order.contains(b)) {
+            return Err(ParseError::invalid("wildcard", b as char));
         }
+        Ok(Rules { order, wildcards })
     }
-}
 
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
-enum HandType {
-    HighCard,
-    OnePair,
-    TwoPair,
-    ThreeOfAKind,
-    FullHouse,
-    FourOfAKind,
-    FiveOfAKind,
-}
-
-#[derive(Copy, Clone, PartialEq, PartialOrd, Eq, Ord)]
-struct Hand {
-    typ: HandType,
-    cards: [CardIdx; 5],
-}
+    /// The type of `hand`, a string of 5 card labels, with the wildcards counting as
+    /// whatever card makes it strongest.
+    pub fn hand_type(&self, hand: &str) -> Result<HandType, ParseError> {
+        Ok(self.labels_type(self.parse_hand(hand)?))
+    }
 
-impl Hand {
-    fn new(cards: [CardIdx; 5], part2: bool) -> Self {
-        let typ = if !part2 {
-            Self::determine_type_part1(cards)
-        } else {
-            Self::determine_type_part2(cards)
+    fn parse_hand(&self, hand: &str) -> Result<[u8; 5], ParseError> {
+        let Ok(labels) = <[u8; 5]>::try_from(hand.as_bytes()) else {
+            return Err(ParseError::invalid("hand of 5 cards", hand));
         };
-        Self { cards, typ }
+        if let Some(i) = labels.iter().position(|b| !self.order.contains(b)) {
+            return Err(ParseError::invalid("card", labels[i] as char).at_column(i + 1));
+        }
+        Ok(labels)
     }
 
-    fn determine_type_part1(cards: [CardIdx; 5]) -> HandType {
+    /// Panics for bytes that aren't a card label.
+    fn card(&self, b: u8) -> CardIdx {
+        match self.order.iter().position(|&c| c == b) {
+            Some(idx) => CardIdx(idx as u8),
+            None => panic!("invalid card byte: {b}"),
+        }
+    }
+
+    fn labels_type(&self, labels: [u8; 5]) -> HandType {
         let mut count = [0u8; 13];
-        for &CardIdx(idx) in &cards {
-            count[idx as usize] += 1;
+        let mut wild = 0;
+        for b in labels {
+            if self.wildcards.contains(&b) {
+                wild += 1;
+            } else {
+                count[self.card(b).0 as usize] += 1;
+            }
         }
 
         count.sort_unstable_by(|a, b| b.cmp(a));
+        // wildcards always make the best hand by joining the most common card.
+        count[0] += wild;
 
         match count {
             [5, ..] => HandType::FiveOfAKind,
@@ -73,80 +92,125 @@ impl Hand {
             _ => HandType::HighCard,
         }
     }
+}
 
-    fn determine_type_part2(cards: [CardIdx; 5]) -> HandType {
-        let mut count = [0u8; 13];
-        for &CardIdx(idx) in &cards {
-            count[idx as usize] += 1;
-        }
-        let jokers = count[0] as usize;
-        if jokers == 5 {
-            return HandType::FiveOfAKind;
-        }
-        let rest = &mut count[1..];
-        rest.sort_unstable_by(|a, b| b.cmp(a));
+/// The types of hands, from weakest to strongest.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HandType {
+    HighCard,
+    OnePair,
+    TwoPair,
+    ThreeOfAKind,
+    FullHouse,
+    FourOfAKind,
+    FiveOfAKind,
+}
 
-        match rest {
-            [5, ..] => HandType::FiveOfAKind,
-            [4, ..] => [HandType::FourOfAKind, HandType::FiveOfAKind][jokers],
-            [3, 2, ..] => HandType::FullHouse,
-            [3, ..] => [
-                HandType::ThreeOfAKind,
-                HandType::FourOfAKind,
-                HandType::FiveOfAKind,
-            ][jokers],
-            [2, 2, ..] => [HandType::TwoPair, HandType::FullHouse][jokers],
-            [2, ..] => [
-                HandType::OnePair,
-                HandType::ThreeOfAKind,
-                HandType::FourOfAKind,
-                HandType::FiveOfAKind,
-            ][jokers],
-            _ => [
-                HandType::HighCard,
-                HandType::OnePair,
-                HandType::ThreeOfAKind,
-                HandType::FourOfAKind,
-                HandType::FiveOfAKind,
-            ][jokers],
-        }
+#[derive(Copy, Clone, PartialEq, PartialOrd, Eq, Ord)]
+struct Hand {
+    typ: HandType,
+    cards: [CardIdx; 5],
+}
+
+impl Hand {
+    fn new(labels: [u8; 5], rules: &Rules) -> Self {
+        let typ = rules.labels_type(labels);
+        let cards = labels.map(|b| rules.card(b));
+        Self { cards, typ }
     }
 }
 
-fn parse_line(line: &str, part2: bool) -> (Hand, usize) {
-    let (hand, bid) = line.trim().split_once(' ').unwrap();
-    let hand: [u8; 5] = hand.as_bytes().try_into().unwrap();
-    let card_fn = if part2 {
-        CardIdx::from_byte_part2
-    } else {
-        CardIdx::from_byte_part1
-    };
-    let cards = hand.map(card_fn);
-    (Hand::new(cards, part2), bid.parse().unwrap())
+fn parse_line(line: &str, rules: &Rules) -> Result<(Hand, usize), ParseError> {
+    let (hand, bid) = parse::split_once(line.trim(), " ")?;
+    let hand = rules.parse_hand(hand)?;
+    Ok((Hand::new(hand, rules), parse::num(bid)?))
 }
 
-fn parse_input(input: &str, part2: bool) -> Vec<(Hand, usize)> {
-    input.trim().lines().map(|l| parse_line(l, part2)).collect()
+fn parse_input(input: &str, rules: &Rules) -> Result<Vec<(Hand, usize)>, ParseError> {
+    parse::lines(input, |l| parse_line(l, rules))
 }
 
-pub fn part1(input: &str) -> String {
-    let mut hands = parse_input(input, false);
+/// The sum of every hand's bid times its rank among the hands, from 1 for the weakest, with
+/// the hands ranked by `rules`.
+pub fn total_winnings(input: &str, rules: &Rules) -> anyhow::Result<usize> {
+    let mut hands = parse_input(input, rules)?;
     hands.sort_unstable();
-    hands
+    Ok(hands
         .iter()
         .enumerate()
         .map(|(i, (_, bid))| (i + 1) * bid)
-        .sum::<usize>()
-        .to_string()
+        .sum())
 }
 
-pub fn part2(input: &str) -> String {
-    let mut hands = parse_input(input, true);
-    hands.sort_unstable();
-    hands
-        .iter()
-        .enumerate()
-        .map(|(i, (_, bid))| (i + 1) * bid)
-        .sum::<usize>()
-        .to_string()
+register_day!(7, Day7, "Camel Cards", tags: ["sorting"]);
+
+impl Puzzle for Day7 {
+    // How a hand gets parsed depends on whether J is a jack or a joker, so the parts parse
+    // the input themselves.
+    type Parsed<'a> = &'a str;
+    const ALGORITHM: &'static str = "hand-type-sort-v1";
+
+    fn parse(input: &[u8]) -> anyhow::Result<&str> {
+        Ok(parse::utf8(input)?)
+    }
+
+    fn solve1(input: &&str, _: &Token) -> anyhow::Result<Answer> {
+        Ok(total_winnings(input, &PART1)?.into())
+    }
+
+    fn solve2(input: &&str, _: &Token) -> anyhow::Result<Answer> {
+        Ok(total_winnings(input, &PART2)?.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "32T3K 765\nT55J5 684\nKK677 28\nKTJJT 220\nQQQJA 483";
+
+    #[test]
+    fn example() {
+        let part1 = Rules::new(*b"23456789TJQKA", b"").unwrap();
+        let part2 = Rules::new(*b"J23456789TQKA", b"J").unwrap();
+        assert_eq!(total_winnings(EXAMPLE, &part1).unwrap(), 6440);
+        assert_eq!(total_winnings(EXAMPLE, &part2).unwrap(), 5905);
+    }
+
+    #[test]
+    fn several_wildcards() {
+        let rules = Rules::new(*b"23456789TJQKA", b"JQK").unwrap();
+        let cases = [
+            ("23456", HandType::HighCard),
+            ("2345J", HandType::OnePair),
+            ("2345K", HandType::OnePair),
+            ("2233A", HandType::TwoPair),
+            ("234JQ", HandType::ThreeOfAKind),
+            ("2233Q", HandType::FullHouse),
+            ("22JQ3", HandType::FourOfAKind),
+            ("JQK98", HandType::FourOfAKind),
+            ("JJQQK", HandType::FiveOfAKind),
+            ("AJQK2", HandType::FourOfAKind),
+            ("AAJKQ", HandType::FiveOfAKind),
+        ];
+        for (hand, typ) in cases {
+            assert_eq!(rules.hand_type(hand), Ok(typ), "{hand}");
+        }
+        // Without wildcards, the same hands are mostly weaker.
+        assert_eq!(PART1.hand_type("JJQQK"), Ok(HandType::TwoPair));
+        assert_eq!(PART2.hand_type("JJQQK"), Ok(HandType::FourOfAKind));
+
+        // Wildcards still rank by their own label when the types are equal, so the five of
+        // a kind starting with a J beats the one starting with a 2.
+        let input = "JQ222 10\nJA222 20\n2222Q 30";
+        assert_eq!(total_winnings(input, &rules).unwrap(), 20 + 2 * 30 + 3 * 10);
+    }
+
+    #[test]
+    fn invalid_rules() {
+        assert!(Rules::new(*b"23456789TJQKK", b"").is_err());
+        assert!(Rules::new(*b"23456789TJQKA", b"JX").is_err());
+        assert!(PART1.hand_type("2345").is_err());
+        assert!(PART1.hand_type("2345X").is_err());
+    }
 }
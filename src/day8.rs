@@ -1,30 +1,56 @@
-use ahash::AHashMap as Map;
+use alloc::vec::Vec;
+use core::fmt;
+
+use anyhow::Context;
+use serde::Serialize;
+
+use crate::{
+    cancel::Token,
+    collections::HashMap as Map,
+    explain, math,
+    parse::{self, ParseError},
+    Answer, Day8, Puzzle,
+};
 
 #[derive(Debug)]
-enum Inst {
+pub enum Inst {
     Left,
     Right,
 }
 
 #[derive(Debug)]
-struct Network<'a> {
+pub struct Network<'a> {
     indices: Map<&'a str, u16>,
     nodes: Vec<(u16, u16)>,
 }
 
-fn parse_input(input: &str) -> (Vec<Inst>, Network<'_>) {
-    let (insts, network) = input.trim().split_once('\n').unwrap();
-    let insts = insts
+fn parse_node(line: &str) -> Result<(&str, &str, &str), ParseError> {
+    let (node, neighbors) = parse::split_once(line, " = (")?;
+    let neighbors = parse::strip_suffix(neighbors, ")")?;
+    let (left, right) = parse::split_once(neighbors, ", ")?;
+    Ok((node, left, right))
+}
+
+fn parse_input(input: &str) -> Result<(Vec<Inst>, Network<'_>), ParseError> {
+    let mut lines = input.trim().lines().enumerate();
+    let (_, insts) = lines
+        .next()
+        .ok_or_else(|| ParseError::malformed("empty input"))?;
+    let insts: Vec<_> = insts
         .trim_end()
         .bytes()
-        .map(|b| match b {
-            b'L' => Inst::Left,
-            b'R' => Inst::Right,
-            _ => panic!("invalid instruction"),
+        .enumerate()
+        .map(|(i, b)| match b {
+            b'L' => Ok(Inst::Left),
+            b'R' => Ok(Inst::Right),
+            _ => Err(ParseError::invalid("instruction", b as char).at(1, i + 1)),
         })
-        .collect();
+        .collect::<Result<_, _>>()?;
+    if insts.is_empty() {
+        return Err(ParseError::malformed("no instructions"));
+    }
 
-    let mut indices = Map::new();
+    let mut indices = Map::default();
     let mut nodes = Vec::new();
 
     fn index<'a>(
@@ -42,20 +68,24 @@ fn parse_input(input: &str) -> (Vec<Inst>, Network<'_>) {
         }
     }
 
-    for line in network.trim_start().lines() {
-        let (node, neighbors) = line.split_once(" = (").unwrap();
-        let (left, right) = neighbors
-            .strip_suffix(')')
-            .unwrap()
-            .split_once(", ")
-            .unwrap();
+    for (i, line) in lines.filter(|(_, line)| !line.trim().is_empty()) {
+        let (node, left, right) = parse_node(line).map_err(|e| e.at_line(i + 1))?;
         let node = index(node, &mut indices, &mut nodes);
         let left = index(left, &mut indices, &mut nodes);
         let right = index(right, &mut indices, &mut nodes);
         nodes[node as usize] = (left, right);
     }
 
-    (insts, Network { indices, nodes })
+    Ok((insts, Network { indices, nodes }))
+}
+
+impl Network<'_> {
+    fn node(&self, name: &str) -> anyhow::Result<u16> {
+        self.indices
+            .get(name)
+            .copied()
+            .with_context(|| format!("there's no node {}", name))
+    }
 }
 
 fn count_steps(
@@ -80,32 +110,121 @@ fn count_steps(
     unreachable!()
 }
 
-pub fn part1(input: &str) -> String {
-    let (insts, network) = parse_input(input);
-    let start = network.indices["AAA"];
-    let end = network.indices["ZZZ"];
-    count_steps(&insts, &network, start, |i| i == end).to_string()
+#[derive(Serialize)]
+pub struct Run<'a> {
+    node: &'a str,
+    count: usize,
+}
+
+/// The path taken from AAA to ZZZ in part 1, with consecutive visits of the
+/// same node collapsed into a single run.
+#[derive(Serialize)]
+pub struct PathDetails<'a> {
+    steps: usize,
+    path: Vec<Run<'a>>,
 }
 
-pub fn part2(input: &str) -> String {
-    let (insts, network) = parse_input(input);
-    // There's so few end vertices (6 for my input) that a linear scan
-    // over a vector is faster than a hash set lookup.
-    let end: Vec<u16> = network
-        .indices
-        .iter()
-        .filter(|(n, _)| n.ends_with('Z'))
-        .map(|(_, &i)| i)
-        .collect();
-
-    let start = network
-        .indices
-        .iter()
-        .filter(|(n, _)| n.ends_with('A'))
-        .map(|(_, &i)| i);
-
-    start
-        .map(|start| count_steps(&insts, &network, start, |i| end.contains(&i)))
-        .fold(1usize, num::integer::lcm)
-        .to_string()
+impl fmt::Display for PathDetails<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Steps: {}", self.steps)?;
+        for (i, run) in self.path.iter().enumerate() {
+            if i > 0 {
+                write!(f, " -> ")?;
+            }
+            write!(f, "{}", run.node)?;
+            if run.count > 1 {
+                write!(f, " (x{})", run.count)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn details(input: &str) -> anyhow::Result<PathDetails<'_>> {
+    let (insts, network) = parse_input(input)?;
+    let mut names = vec![""; network.nodes.len()];
+    for (&name, &i) in &network.indices {
+        names[i as usize] = name;
+    }
+
+    let mut cur = network.node("AAA")?;
+    let end = network.node("ZZZ")?;
+    let mut path = vec![Run {
+        node: names[cur as usize],
+        count: 1,
+    }];
+    let mut steps = 0;
+
+    for inst in insts.iter().cycle() {
+        if cur == end {
+            break;
+        }
+        let (left, right) = network.nodes[cur as usize];
+        cur = match inst {
+            Inst::Left => left,
+            Inst::Right => right,
+        };
+        steps += 1;
+        match path.last_mut() {
+            Some(run) if run.node == names[cur as usize] => run.count += 1,
+            _ => path.push(Run {
+                node: names[cur as usize],
+                count: 1,
+            }),
+        }
+    }
+
+    Ok(PathDetails { steps, path })
+}
+
+/// The lengths of the instructions and the network, and how many nodes part 2 starts at.
+pub fn shape(input: &str) -> anyhow::Result<Vec<(&'static str, usize)>> {
+    let (insts, network) = parse_input(input)?;
+    let starts = network.indices.keys().filter(|n| n.ends_with('A')).count();
+    Ok(vec![
+        ("instructions", insts.len()),
+        ("nodes", network.nodes.len()),
+        ("start nodes", starts),
+    ])
+}
+
+register_day!(8, Day8, "Haunted Wasteland", tags: ["graph", "cycles"]);
+
+impl Puzzle for Day8 {
+    type Parsed<'a> = (Vec<Inst>, Network<'a>);
+    const ALGORITHM: &'static str = "cycle-lcm-v1";
+
+    fn parse(input: &[u8]) -> anyhow::Result<(Vec<Inst>, Network<'_>)> {
+        let input = parse::utf8(input)?;
+        Ok(parse_input(input)?)
+    }
+
+    fn solve1((insts, network): &(Vec<Inst>, Network<'_>), _: &Token) -> anyhow::Result<Answer> {
+        let start = network.node("AAA")?;
+        let end = network.node("ZZZ")?;
+        Ok(count_steps(insts, network, start, |i| i == end).into())
+    }
+
+    fn solve2((insts, network): &(Vec<Inst>, Network<'_>), _: &Token) -> anyhow::Result<Answer> {
+        // There's so few end vertices (6 for my input) that a linear scan
+        // over a vector is faster than a hash set lookup.
+        let end: Vec<u16> = network
+            .indices
+            .iter()
+            .filter(|(n, _)| n.ends_with('Z'))
+            .map(|(_, &i)| i)
+            .collect();
+
+        let start = network.indices.iter().filter(|(n, _)| n.ends_with('A'));
+
+        let lcm = start
+            .map(|(name, &start)| {
+                let steps = count_steps(insts, network, start, |i| end.contains(&i));
+                explain::step(|| format!("{} reaches an end node after {} steps", name, steps));
+                steps
+            })
+            .fold(1, math::lcm);
+        explain::step(|| format!("The lcm of those is {}", lcm));
+        Ok(lcm.into())
+    }
 }
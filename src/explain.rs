@@ -0,0 +1,48 @@
+//! Human-readable steps of how a solver gets to its answer, for `--explain`. Solvers call
+//! [`step`] at the interesting points of their computation (e.g. every cycle length that
+//! goes into an lcm), and the runner collects the steps with [`collect`]. When nothing is
+//! collecting, which is always the case for timed runs, a step is a thread-local lookup
+//! and its message never gets formatted.
+//!
+//! Without the `std` feature, steps can't be collected and [`step`] does nothing.
+
+use alloc::string::String;
+#[cfg(feature = "std")]
+use std::{
+    cell::RefCell,
+    panic::{self, AssertUnwindSafe},
+    vec::Vec,
+};
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    // The steps recorded on this thread so far, if they're being collected.
+    static STEPS: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+/// Records a step of the current solver, if its steps are being collected. `message` only
+/// gets called then.
+#[cfg(feature = "std")]
+pub fn step(message: impl FnOnce() -> String) {
+    STEPS.with_borrow_mut(|steps| {
+        if let Some(steps) = steps {
+            steps.push(message());
+        }
+    });
+}
+
+#[cfg(not(feature = "std"))]
+pub fn step(_message: impl FnOnce() -> String) {}
+
+/// Runs `f`, returning what it returned along with the steps it recorded. Panics are
+/// passed on.
+#[cfg(feature = "std")]
+pub fn collect<R>(f: impl FnOnce() -> R) -> (R, Vec<String>) {
+    let previous = STEPS.replace(Some(Vec::new()));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    let steps = STEPS.replace(previous).unwrap_or_default();
+    match result {
+        Ok(r) => (r, steps),
+        Err(payload) => panic::resume_unwind(payload),
+    }
+}
@@ -0,0 +1,81 @@
+//! A C interface to the solvers, only built with the `ffi` feature. Build a library to
+//! link against with
+//!
+//! ```text
+//! cargo rustc --lib --release --no-default-features --features ffi --crate-type cdylib
+//! ```
+//!
+//! (or `staticlib`), and include `include/aoc2023.h`, which the build script generates
+//! from this module.
+//!
+//! Every string passed back to C is allocated by Rust and must be freed with
+//! [`aoc2023_free`], and nothing passed in from C is kept after a call returns.
+
+use std::{
+    ffi::{c_char, c_int, c_uint, CStr, CString},
+    panic::{self, AssertUnwindSafe},
+    string::{String, ToString},
+};
+
+/// The part was solved, and `*out` points at the answer.
+pub const AOC2023_OK: c_int = 0;
+/// The part couldn't be solved (e.g. because the input is malformed), and `*out` points at
+/// the error message.
+pub const AOC2023_FAILED: c_int = 1;
+/// The arguments were invalid (a null pointer, or a day or part that doesn't exist), and
+/// `*out` is left alone.
+pub const AOC2023_INVALID: c_int = 2;
+
+fn solve(day: usize, part: usize, input: &[u8]) -> anyhow::Result<String> {
+    let input = std::str::from_utf8(input)?;
+    let input = crate::preprocess(day, input)?;
+    let solver = crate::solver(day).expect("the day was checked");
+    Ok(solver
+        .part(part, &input, &crate::cancel::Token::default())?
+        .to_string())
+}
+
+/// Solves `part` of `day` for `input`, a NUL-terminated UTF-8 string. Returns one of the
+/// `AOC2023_*` codes. Unless the arguments were invalid, `*out` gets pointed at a string
+/// that the caller owns and must free with `aoc2023_free`.
+///
+/// # Safety
+///
+/// `input` must be null or point at a NUL-terminated string, and `out` must be null or
+/// point at memory a `char *` can be written to.
+#[no_mangle]
+pub unsafe extern "C" fn aoc2023_solve(
+    day: c_uint,
+    part: c_uint,
+    input: *const c_char,
+    out: *mut *mut c_char,
+) -> c_int {
+    let (day, part) = (day as usize, part as usize);
+    if input.is_null() || out.is_null() || crate::solver(day).is_none() || !(1..=2).contains(&part)
+    {
+        return AOC2023_INVALID;
+    }
+    let input = unsafe { CStr::from_ptr(input) }.to_bytes();
+    // Unwinding into C is undefined behavior, so panics become failures.
+    let result = panic::catch_unwind(AssertUnwindSafe(|| solve(day, part, input)))
+        .unwrap_or_else(|_| Err(anyhow::anyhow!("the solver panicked")));
+    let (code, text) = match result {
+        Ok(answer) => (AOC2023_OK, answer),
+        Err(e) => (AOC2023_FAILED, format!("{:#}", e)),
+    };
+    let text = CString::new(text.replace('\0', "")).expect("NULs were removed");
+    unsafe { *out = text.into_raw() };
+    code
+}
+
+/// Frees a string returned by `aoc2023_solve`. Does nothing for null.
+///
+/// # Safety
+///
+/// `s` must be null or a string returned by `aoc2023_solve` that hasn't been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn aoc2023_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
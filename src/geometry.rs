@@ -0,0 +1,77 @@
+//! Directions and positions for the days that walk around on a grid.
+
+use core::ops::Add;
+
+use enum_map::Enum;
+use serde::{Deserialize, Serialize};
+
+/// A position on a grid, with x growing to the east and y growing to the south.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Point {
+    pub x: isize,
+    pub y: isize,
+}
+
+impl Point {
+    pub const fn new(x: isize, y: isize) -> Self {
+        Self { x, y }
+    }
+
+    /// The position `n` steps from this one in direction `dir`.
+    pub fn step(self, dir: Dir, n: isize) -> Self {
+        let delta = dir.delta();
+        Self::new(self.x + delta.x * n, self.y + delta.y * n)
+    }
+}
+
+impl Add for Point {
+    type Output = Point;
+
+    fn add(self, rhs: Point) -> Point {
+        Point::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Enum, Serialize, Deserialize)]
+pub enum Dir {
+    North,
+    East,
+    South,
+    West,
+}
+
+/// How far a single step in each direction moves, in the order of [`Dir::ALL`].
+pub const DELTAS: [Point; 4] = [
+    Point::new(0, -1),
+    Point::new(1, 0),
+    Point::new(0, 1),
+    Point::new(-1, 0),
+];
+
+impl Dir {
+    /// Every direction, clockwise starting from north.
+    pub const ALL: [Dir; 4] = [Dir::North, Dir::East, Dir::South, Dir::West];
+
+    pub const fn delta(self) -> Point {
+        DELTAS[self as usize]
+    }
+
+    /// The position one step from `p` in this direction.
+    pub fn step(self, p: Point) -> Point {
+        p + self.delta()
+    }
+
+    pub fn opposite(self) -> Dir {
+        Dir::ALL[(self as usize + 2) % 4]
+    }
+
+    /// The direction after turning right.
+    pub fn rotate_cw(self) -> Dir {
+        Dir::ALL[(self as usize + 1) % 4]
+    }
+
+    /// The direction after turning left.
+    pub fn rotate_ccw(self) -> Dir {
+        Dir::ALL[(self as usize + 3) % 4]
+    }
+}
@@ -0,0 +1,294 @@
+//! Directed graphs stored as adjacency lists, for the days whose input (or whose
+//! contracted input, like the junctions of day 23's maze) is a graph.
+
+use alloc::{
+    collections::{BinaryHeap, VecDeque},
+    string::String,
+    vec::Vec,
+};
+use core::{cmp::Reverse, fmt::Write, hash::Hash, ops::Add};
+
+use serde::{Deserialize, Serialize};
+
+use crate::collections::HashMap;
+
+/// A directed graph with vertices numbered from 0, and a weight of type `W` on every edge.
+/// Undirected graphs store every edge in both directions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Graph<W = ()> {
+    adjacency: Vec<Vec<(usize, W)>>,
+}
+
+impl<W> Default for Graph<W> {
+    fn default() -> Self {
+        Self {
+            adjacency: Vec::new(),
+        }
+    }
+}
+
+impl<W> Graph<W> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A graph with `n` vertices and no edges.
+    pub fn with_vertices(n: usize) -> Self {
+        Self {
+            adjacency: (0..n).map(|_| Vec::new()).collect(),
+        }
+    }
+
+    /// The number of vertices.
+    pub fn len(&self) -> usize {
+        self.adjacency.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.adjacency.is_empty()
+    }
+
+    /// Adds a vertex without any edges, returning its number.
+    pub fn add_vertex(&mut self) -> usize {
+        self.adjacency.push(Vec::new());
+        self.adjacency.len() - 1
+    }
+
+    /// Adds an edge from `from` to `to`, even if there already is one. Panics if either
+    /// vertex doesn't exist.
+    pub fn add_edge(&mut self, from: usize, to: usize, weight: W) {
+        assert!(to < self.len(), "there is no vertex {}", to);
+        self.adjacency[from].push((to, weight));
+    }
+
+    pub fn contains_edge(&self, from: usize, to: usize) -> bool {
+        self.adjacency[from].iter().any(|&(v, _)| v == to)
+    }
+
+    /// The vertices that `v` has an edge to, along with the weights of those edges.
+    pub fn neighbors(&self, v: usize) -> &[(usize, W)] {
+        &self.adjacency[v]
+    }
+
+    /// Every edge as `(from, to, weight)`, ordered by `from`.
+    pub fn edges(&self) -> impl Iterator<Item = (usize, usize, &W)> {
+        self.adjacency
+            .iter()
+            .enumerate()
+            .flat_map(|(from, edges)| edges.iter().map(move |(to, w)| (from, *to, w)))
+    }
+
+    /// Whether every edge leads to a vertex that exists, which deserializing doesn't
+    /// check.
+    pub fn is_valid(&self) -> bool {
+        self.edges().all(|(_, to, _)| to < self.len())
+    }
+
+    /// The number of edges on the shortest path from `start` to every vertex, or `None` for
+    /// the vertices it can't reach.
+    pub fn bfs(&self, start: usize) -> Vec<Option<usize>> {
+        let mut dist = vec![None; self.len()];
+        dist[start] = Some(0);
+        let mut queue = VecDeque::from([(start, 0)]);
+        while let Some((v, d)) = queue.pop_front() {
+            for &(w, _) in &self.adjacency[v] {
+                if dist[w].is_none() {
+                    dist[w] = Some(d + 1);
+                    queue.push_back((w, d + 1));
+                }
+            }
+        }
+        dist
+    }
+
+    /// The vertices reachable from `start`, in the order a depth-first search visits them.
+    pub fn dfs(&self, start: usize) -> Vec<usize> {
+        let mut visited = vec![false; self.len()];
+        let mut order = Vec::new();
+        let mut stack = vec![start];
+        while let Some(v) = stack.pop() {
+            if core::mem::replace(&mut visited[v], true) {
+                continue;
+            }
+            order.push(v);
+            // Reversed, so that the first neighbor gets visited first.
+            stack.extend(self.adjacency[v].iter().rev().map(|&(w, _)| w));
+        }
+        order
+    }
+
+    /// The total weight of the lightest path from `start` to every vertex, or `None` for the
+    /// vertices it can't reach. The weights mustn't be negative.
+    pub fn dijkstra(&self, start: usize) -> Vec<Option<W>>
+    where
+        W: Copy + Ord + Add<Output = W> + Default,
+    {
+        let mut dist = vec![None; self.len()];
+        let mut heap = BinaryHeap::from([Reverse((W::default(), start))]);
+        while let Some(Reverse((d, v))) = heap.pop() {
+            if dist[v].is_some() {
+                continue;
+            }
+            dist[v] = Some(d);
+            for &(w, weight) in &self.adjacency[v] {
+                if dist[w].is_none() {
+                    heap.push(Reverse((d + weight, w)));
+                }
+            }
+        }
+        dist
+    }
+
+    /// The strongly connected components of the graph, and the acyclic graph between them.
+    pub fn condensation(&self) -> Condensation {
+        // Tarjan's algorithm, with an explicit stack of the vertices being visited and how
+        // many of their edges have been followed so far.
+        const UNVISITED: usize = usize::MAX;
+        let n = self.len();
+        let mut index = vec![UNVISITED; n];
+        let mut low = vec![0; n];
+        let mut on_stack = vec![false; n];
+        let mut stack = Vec::new();
+        let mut component = vec![0; n];
+        let mut count = 0;
+        let mut next_index = 0;
+        for root in 0..n {
+            if index[root] != UNVISITED {
+                continue;
+            }
+            index[root] = next_index;
+            low[root] = next_index;
+            next_index += 1;
+            stack.push(root);
+            on_stack[root] = true;
+            let mut calls = vec![(root, 0)];
+            while let Some(&(v, i)) = calls.last() {
+                if let Some(&(w, _)) = self.adjacency[v].get(i) {
+                    calls.last_mut().unwrap().1 += 1;
+                    if index[w] == UNVISITED {
+                        index[w] = next_index;
+                        low[w] = next_index;
+                        next_index += 1;
+                        stack.push(w);
+                        on_stack[w] = true;
+                        calls.push((w, 0));
+                    } else if on_stack[w] {
+                        low[v] = low[v].min(index[w]);
+                    }
+                    continue;
+                }
+                calls.pop();
+                if let Some(&(parent, _)) = calls.last() {
+                    low[parent] = low[parent].min(low[v]);
+                }
+                if low[v] == index[v] {
+                    loop {
+                        let w = stack.pop().expect("v is still on the stack");
+                        on_stack[w] = false;
+                        component[w] = count;
+                        if w == v {
+                            break;
+                        }
+                    }
+                    count += 1;
+                }
+            }
+        }
+
+        // Tarjan's algorithm finds the components in reverse topological order.
+        for c in &mut component {
+            *c = count - 1 - *c;
+        }
+        let mut graph = Graph::with_vertices(count);
+        for (from, to, _) in self.edges() {
+            let (from, to) = (component[from], component[to]);
+            if from != to && !graph.contains_edge(from, to) {
+                graph.add_edge(from, to, ());
+            }
+        }
+        Condensation { component, graph }
+    }
+
+    /// The graph in Graphviz's DOT language, with the vertices and edges labeled by
+    /// `vertex_label` and `edge_label`. Edges whose label is `None` get none.
+    pub fn to_dot(
+        &self,
+        vertex_label: impl Fn(usize) -> String,
+        edge_label: impl Fn(&W) -> Option<String>,
+    ) -> String {
+        let mut dot = String::from("digraph {\n");
+        for v in 0..self.len() {
+            writeln!(dot, "    {} [label={:?}];", v, vertex_label(v)).unwrap();
+        }
+        for (from, to, weight) in self.edges() {
+            match edge_label(weight) {
+                Some(label) => writeln!(dot, "    {} -> {} [label={:?}];", from, to, label),
+                None => writeln!(dot, "    {} -> {};", from, to),
+            }
+            .unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// The strongly connected components of a graph, from [`Graph::condensation`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Condensation {
+    /// The component of every vertex. Components are numbered in topological order, so
+    /// edges between different components always go from a lower to a higher one.
+    pub component: Vec<usize>,
+    /// The graph with a vertex per component, and an edge between two components if there
+    /// is any edge between their vertices.
+    pub graph: Graph,
+}
+
+/// Builds a graph whose vertices are identified by labels of type `K` (e.g. the names of
+/// day 25's components), numbering every label the first time it comes up.
+pub struct Builder<K, W = ()> {
+    indices: HashMap<K, usize>,
+    labels: Vec<K>,
+    graph: Graph<W>,
+}
+
+impl<K: Hash + Eq + Clone, W> Builder<K, W> {
+    pub fn new() -> Self {
+        Self {
+            indices: HashMap::default(),
+            labels: Vec::new(),
+            graph: Graph::new(),
+        }
+    }
+
+    /// The number of the vertex labeled `label`, adding it if there isn't one yet.
+    pub fn vertex(&mut self, label: K) -> usize {
+        *self.indices.entry(label.clone()).or_insert_with(|| {
+            self.labels.push(label);
+            self.graph.add_vertex()
+        })
+    }
+
+    /// The label of vertex `v`.
+    pub fn label(&self, v: usize) -> &K {
+        &self.labels[v]
+    }
+
+    pub fn graph(&self) -> &Graph<W> {
+        &self.graph
+    }
+
+    pub fn graph_mut(&mut self) -> &mut Graph<W> {
+        &mut self.graph
+    }
+
+    /// The graph, and the label of every vertex.
+    pub fn build(self) -> (Graph<W>, Vec<K>) {
+        (self.graph, self.labels)
+    }
+}
+
+impl<K: Hash + Eq + Clone, W> Default for Builder<K, W> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,143 @@
+//! The rectangular maps most of the grid days get as their input, stored row by row.
+
+use alloc::vec::Vec;
+use core::ops::{Index, IndexMut};
+
+use crate::{lex, parse::ParseError};
+
+/// A grid of cells, indexed by `(x, y)` with `(0, 0)` at the top left. Positions can be
+/// any unsigned integer type, since most days store them as `u8`s to save space.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    cells: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+impl<T> Grid<T> {
+    /// A `width` by `height` grid with every cell set to `cell`.
+    pub fn new(width: usize, height: usize, cell: T) -> Self
+    where
+        T: Clone,
+    {
+        Self {
+            cells: vec![cell; width * height],
+            width,
+            height,
+        }
+    }
+
+    /// Parses a grid with one byte per cell, which fails if the grid is empty or its lines
+    /// aren't all equally long. Whitespace at the end of lines and blank lines at the end
+    /// aren't part of the grid. `cell` turns the byte at a position into a cell, and its
+    /// errors get that position filled in.
+    pub fn parse(
+        input: &[u8],
+        mut cell: impl FnMut((usize, usize), u8) -> Result<T, ParseError>,
+    ) -> Result<Self, ParseError> {
+        let mut cells = Vec::new();
+        let mut width = 0;
+        let mut height = 0;
+        for (y, line) in lex::lines(input.trim_ascii_end()).enumerate() {
+            let line = line.trim_ascii_end();
+            if y > 0 && line.len() != width {
+                let e = ParseError::malformed("this line isn't as long as the ones before it");
+                return Err(e.at_line(y + 1));
+            }
+            width = line.len();
+            height += 1;
+            for (x, &b) in line.iter().enumerate() {
+                cells.push(cell((x, y), b).map_err(|e| e.at(y + 1, x + 1))?);
+            }
+        }
+        if cells.is_empty() {
+            return Err(ParseError::malformed("the grid is empty"));
+        }
+        Ok(Self {
+            cells,
+            width,
+            height,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The cell at `(x, y)`, or `None` if that's off the grid.
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        (x < self.width && y < self.height).then(|| &self.cells[y * self.width + x])
+    }
+
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+        (x < self.width && y < self.height).then(|| &mut self.cells[y * self.width + x])
+    }
+
+    /// The cell at `(x, y)`, without checking that it's on the grid.
+    ///
+    /// # Safety
+    ///
+    /// `x` and `y` must be less than the width and the height of the grid.
+    pub unsafe fn get_unchecked(&self, x: usize, y: usize) -> &T {
+        debug_assert!(x < self.width && y < self.height);
+        unsafe { self.cells.get_unchecked(y * self.width + x) }
+    }
+
+    /// The positions above, below, left and right of `(x, y)` that are on the grid.
+    pub fn neighbors(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+        let (width, height) = (self.width, self.height);
+        let candidates = [
+            (x, y.wrapping_sub(1)),
+            (x, y + 1),
+            (x.wrapping_sub(1), y),
+            (x + 1, y),
+        ];
+        candidates
+            .into_iter()
+            .filter(move |&(x, y)| x < width && y < height)
+    }
+
+    /// The position of the first cell in reading order that `pred` returns true for.
+    pub fn position(&self, pred: impl FnMut(&T) -> bool) -> Option<(usize, usize)> {
+        let i = self.cells.iter().position(pred)?;
+        Some((i % self.width, i / self.width))
+    }
+
+    /// The rows of the grid, from top to bottom.
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width)
+    }
+}
+
+impl<T, I: Into<usize>> Index<(I, I)> for Grid<T> {
+    type Output = T;
+
+    /// The cell at `(x, y)`. Panics if that's off the grid.
+    fn index(&self, (x, y): (I, I)) -> &T {
+        let (x, y) = (x.into(), y.into());
+        assert!(
+            x < self.width && y < self.height,
+            "({}, {}) is off the grid",
+            x,
+            y
+        );
+        &self.cells[y * self.width + x]
+    }
+}
+
+impl<T, I: Into<usize>> IndexMut<(I, I)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (I, I)) -> &mut T {
+        let (x, y) = (x.into(), y.into());
+        assert!(
+            x < self.width && y < self.height,
+            "({}, {}) is off the grid",
+            x,
+            y
+        );
+        &mut self.cells[y * self.width + x]
+    }
+}
@@ -0,0 +1,89 @@
+//! The `gui` window, only built with the `gui` feature: pick a day, paste an input, and
+//! run the solvers on it.
+
+use std::time::Duration;
+
+use eframe::egui::{self, Color32, RichText};
+
+use crate::output::{Outcome, PartResult};
+
+struct SolverApp<F> {
+    run_part: F,
+    day: usize,
+    input: String,
+    results: Vec<PartResult>,
+}
+
+fn describe(result: &PartResult) -> RichText {
+    let (text, color) = match &result.outcome {
+        Outcome::Solved { answer, timing } => {
+            let median = Duration::from_nanos(timing.median_ns);
+            (format!("{} ({:.3?})", answer, median), Color32::GREEN)
+        }
+        Outcome::TimedOut { timeout_ns } => {
+            let timeout = Duration::from_nanos(*timeout_ns);
+            (format!("timed out after {:.3?}", timeout), Color32::MAGENTA)
+        }
+        Outcome::Failed { error, .. } => (error.clone(), Color32::RED),
+        Outcome::Missing { error } => (error.clone(), Color32::YELLOW),
+    };
+    let text = format!("Day {} Part {}: {}", result.day, result.part, text);
+    RichText::new(text).color(color).monospace()
+}
+
+impl<F: FnMut(usize, usize, &str) -> PartResult> eframe::App for SolverApp<F> {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::SidePanel::left("days").show(ctx, |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for day in 1..=25 {
+                    ui.selectable_value(&mut self.day, day, format!("Day {}", day));
+                }
+            });
+        });
+        egui::TopBottomPanel::bottom("results").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                let buttons: [(&str, &[usize]); 3] = [
+                    ("Run part 1", &[1]),
+                    ("Run part 2", &[2]),
+                    ("Run both parts", &[1, 2]),
+                ];
+                for (label, parts) in buttons {
+                    let enabled = !self.input.trim().is_empty();
+                    if ui.add_enabled(enabled, egui::Button::new(label)).clicked() {
+                        self.results = parts
+                            .iter()
+                            .map(|&part| (self.run_part)(self.day, part, &self.input))
+                            .collect();
+                    }
+                }
+            });
+            for result in &self.results {
+                ui.label(describe(result));
+            }
+        });
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.label(format!("Input for day {}:", self.day));
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                let editor = egui::TextEdit::multiline(&mut self.input).code_editor();
+                ui.add_sized(ui.available_size(), editor);
+            });
+        });
+    }
+}
+
+/// Opens the window and blocks until it gets closed. `run_part` runs a part of a day on
+/// the given input.
+pub fn run(run_part: impl FnMut(usize, usize, &str) -> PartResult) -> anyhow::Result<()> {
+    let app = SolverApp {
+        run_part,
+        day: 1,
+        input: String::new(),
+        results: Vec::new(),
+    };
+    eframe::run_native(
+        "Advent of Code 2023",
+        eframe::NativeOptions::default(),
+        Box::new(|_| Ok(Box::new(app))),
+    )
+    .map_err(|e| anyhow::anyhow!("couldn't open the window: {}", e))
+}
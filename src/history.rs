@@ -0,0 +1,54 @@
+//! Remembers the latest result of every part that was run on an input file, so that
+//! `status` can show them without running anything.
+
+use std::{fs, path::PathBuf};
+
+use crate::{output::PartResult, paths::StateFile};
+
+pub struct History {
+    file: PathBuf,
+    // Sorted by day and part, with at most one result per part.
+    results: Vec<PartResult>,
+    dirty: bool,
+}
+
+impl History {
+    /// Loads the recorded results. A missing or unreadable file just means that nothing
+    /// has been recorded yet.
+    pub fn load() -> Self {
+        let file = StateFile::Results.path();
+        let results = fs::read(&file)
+            .ok()
+            .and_then(|json| serde_json::from_slice(&json).ok())
+            .unwrap_or_default();
+        Self {
+            file,
+            results,
+            dirty: false,
+        }
+    }
+
+    /// Records a result, replacing the previous one of the same part.
+    pub fn record(&mut self, result: PartResult) {
+        self.dirty = true;
+        let key = |r: &PartResult| (r.day, r.part);
+        match self.results.binary_search_by_key(&key(&result), key) {
+            Ok(i) => self.results[i] = result,
+            Err(i) => self.results.insert(i, result),
+        }
+    }
+
+    pub fn latest(&self, day: usize, part: usize) -> Option<&PartResult> {
+        self.results.iter().find(|r| r.day == day && r.part == part)
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        if self.dirty {
+            if let Some(dir) = self.file.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&self.file, serde_json::to_string_pretty(&self.results)?)?;
+        }
+        Ok(())
+    }
+}
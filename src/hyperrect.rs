@@ -0,0 +1,134 @@
+//! Axis-aligned boxes of integer points, for counting how many combinations of values
+//! satisfy a tree of threshold rules (e.g. the part ratings of day 19).
+
+use crate::intervals::RangeSet;
+
+/// The points whose coordinate on every axis is in that axis' set. The sets are never
+/// empty; splitting returns `None` for a side that doesn't contain any points.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hyperrect<const N: usize>([RangeSet; N]);
+
+impl<const N: usize> Hyperrect<N> {
+    /// The box with the inclusive range `(min, max)` on each axis. Panics if any of the
+    /// ranges is empty.
+    pub fn new(ranges: [(usize, usize); N]) -> Self {
+        assert!(ranges.iter().all(|&(min, max)| min <= max), "empty range");
+        Self(ranges.map(|(min, max)| RangeSet::from(min..max + 1)))
+    }
+
+    /// The number of points in the box.
+    pub fn volume(&self) -> usize {
+        self.0.iter().map(RangeSet::len).product()
+    }
+
+    /// Splits the box into the points whose coordinate on `axis` is less than `value`, and
+    /// the points where it's at least `value`. For `coord > value`, split at `value + 1`
+    /// and take the second half.
+    pub fn split_at(self, axis: usize, value: usize) -> (Option<Self>, Option<Self>) {
+        let (below, above) = self.0[axis].split_at(value);
+        let with_set = |set: RangeSet| {
+            (!set.is_empty()).then(|| {
+                let mut sets = self.0.clone();
+                sets[axis] = set;
+                Self(sets)
+            })
+        };
+        (with_set(below), with_set(above))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{vec, vec::Vec};
+
+    use super::*;
+
+    fn volumes<const N: usize>(
+        (below, above): (Option<Hyperrect<N>>, Option<Hyperrect<N>>),
+    ) -> (usize, usize) {
+        let volume = |rect: Option<Hyperrect<N>>| rect.map_or(0, |rect| rect.volume());
+        (volume(below), volume(above))
+    }
+
+    #[test]
+    fn split_at_boundaries() {
+        let rect = Hyperrect::new([(1, 4000), (5, 5)]);
+        assert_eq!(rect.volume(), 4000);
+        let split = |value| volumes(rect.clone().split_at(0, value));
+        // `x < value`.
+        assert_eq!(split(0), (0, 4000));
+        assert_eq!(split(1), (0, 4000));
+        assert_eq!(split(2), (1, 3999));
+        assert_eq!(split(4000), (3999, 1));
+        assert_eq!(split(4001), (4000, 0));
+        assert_eq!(split(4002), (4000, 0));
+        // `x > value`, taking the second half.
+        assert_eq!(split(1 + 1), (1, 3999));
+        assert_eq!(split(3999 + 1), (3999, 1));
+        assert_eq!(split(4000 + 1), (4000, 0));
+
+        // A single value on the other axis leaves one side empty whichever way it's split.
+        let split = |value| volumes(rect.clone().split_at(1, value));
+        assert_eq!(split(5), (0, 4000));
+        assert_eq!(split(6), (4000, 0));
+    }
+
+    #[test]
+    fn empty_sides() {
+        let rect = Hyperrect::new([(3, 7); 3]);
+        assert_eq!(rect.clone().split_at(2, 3), (None, Some(rect.clone())));
+        assert_eq!(rect.clone().split_at(2, 8), (Some(rect.clone()), None));
+        // Splitting a side again where it already ends gives the same side back.
+        let (below, above) = rect.clone().split_at(1, 5);
+        let (below, above) = (below.unwrap(), above.unwrap());
+        assert_eq!(below.clone().split_at(1, 5), (Some(below), None));
+        assert_eq!(above.clone().split_at(1, 5), (None, Some(above)));
+    }
+
+    #[test]
+    fn volume_after_splits() {
+        let start = Hyperrect::new([(1, 6), (0, 9), (4, 8), (2, 2)]);
+        let mut pieces = vec![start.clone()];
+        for (axis, value) in [
+            (0, 3),
+            (1, 5),
+            (0, 5),
+            (2, 6),
+            (1, 1),
+            (3, 2),
+            (2, 9),
+            (0, 1),
+        ] {
+            pieces = pieces
+                .into_iter()
+                .flat_map(|rect| {
+                    let (below, above) = rect.split_at(axis, value);
+                    below.into_iter().chain(above)
+                })
+                .collect();
+        }
+        assert_eq!(
+            pieces.iter().map(Hyperrect::volume).sum::<usize>(),
+            start.volume()
+        );
+
+        // Every point of the box is in exactly one piece.
+        let contains = |rect: &Hyperrect<4>, point: [usize; 4]| {
+            (0..4).all(|axis| rect.0[axis].contains(point[axis]))
+        };
+        let mut points = Vec::new();
+        for x in 1..=6 {
+            for m in 0..=9 {
+                for a in 4..=8 {
+                    points.push([x, m, a, 2]);
+                }
+            }
+        }
+        for point in points {
+            assert_eq!(
+                pieces.iter().filter(|rect| contains(rect, point)).count(),
+                1
+            );
+        }
+    }
+}
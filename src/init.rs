@@ -0,0 +1,63 @@
+//! The `init` command: downloads every input that's missing, to set up a new machine in
+//! one go.
+
+use std::{fs, path::Path, process::ExitCode};
+
+use anyhow::Context;
+
+use crate::{client::Client, output::Exit, paths};
+
+/// Downloads the input of every day that doesn't have a non-empty input file in
+/// `input_dir` yet. Stops at the first day that fails, since the others would most likely
+/// fail the same way (e.g. because of an expired session).
+pub fn init(input_dir: &Path, client: &Client) -> anyhow::Result<ExitCode> {
+    fs::create_dir_all(input_dir)
+        .with_context(|| format!("Couldn't create {}.", input_dir.display()))?;
+    let (mut downloaded, mut present) = (0, 0);
+    let mut failed = None;
+    for day in 1..=25 {
+        let path = paths::input_file(input_dir, day);
+        if fs::metadata(&path).is_ok_and(|m| m.len() > 0) {
+            present += 1;
+            continue;
+        }
+        let result = client.input(day).and_then(|input| {
+            if input.trim().is_empty() {
+                anyhow::bail!("the downloaded input is empty");
+            }
+            fs::write(&path, &input)
+                .with_context(|| format!("couldn't write {}", path.display()))?;
+            Ok(input.len())
+        });
+        match result {
+            Ok(len) => {
+                println!(
+                    "Day {:>2}: downloaded {} ({} bytes)",
+                    day,
+                    path.display(),
+                    len
+                );
+                downloaded += 1;
+            }
+            Err(e) => {
+                println!("Day {:>2}: {:#}", day, e);
+                failed = Some(day);
+                break;
+            }
+        }
+    }
+    let skipped = failed.map_or(0, |day| 25 - day);
+    println!(
+        "Downloaded: {}, already there: {}, failed: {}, skipped: {}",
+        downloaded,
+        present,
+        failed.iter().count(),
+        skipped
+    );
+    Ok(if failed.is_some() {
+        Exit::Failed
+    } else {
+        Exit::Success
+    }
+    .into())
+}
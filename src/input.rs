@@ -0,0 +1,95 @@
+use std::{
+    fs,
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+};
+
+use crate::paths;
+
+// The input options of the single-day commands. At most one of them can be given. Without
+// either, the day's input file gets read, unless it doesn't exist and something is piped
+// into stdin. For both options, `-` means stdin. This is a plain comment because clap
+// would use a doc comment as the help text of every command it's flattened into.
+#[derive(clap::Args)]
+#[group(multiple = false)]
+pub struct InputArgs {
+    /// The puzzle input text itself, or `-` to read it from stdin.
+    #[arg(short, long, visible_alias = "input")]
+    input_text: Option<String>,
+    /// Read the puzzle input from this file, or from stdin for `-`.
+    #[arg(short = 'f', long)]
+    input_file: Option<PathBuf>,
+}
+
+impl InputArgs {
+    pub fn source(self, input_dir: &Path, day: usize) -> InputSource {
+        match (self.input_text, self.input_file) {
+            (Some(text), _) if text == "-" => InputSource::Stdin,
+            (Some(text), _) => InputSource::Text(text),
+            (_, Some(path)) if path.as_os_str() == "-" => InputSource::Stdin,
+            (_, Some(path)) => InputSource::File(path),
+            (None, None) => InputSource::FileOrStdin(paths::input_file(input_dir, day)),
+        }
+    }
+}
+
+/// Where the puzzle input for a day comes from.
+pub enum InputSource {
+    /// The input text itself, passed on the command line.
+    Text(String),
+    Stdin,
+    File(PathBuf),
+    /// The day's input file, falling back to stdin if the file doesn't exist
+    /// and something is piped into stdin.
+    FileOrStdin(PathBuf),
+}
+
+pub struct Input {
+    pub text: String,
+    /// The file the input was read from, if any.
+    pub file: Option<PathBuf>,
+}
+
+/// Guesses the day of an input file from the first number in its name, e.g. 17 for
+/// `inputs/day17.txt` or `17-example.txt`.
+pub fn day_from_path(path: &Path) -> Option<usize> {
+    let name = path.file_name()?.to_str()?;
+    let start = name.find(|c: char| c.is_ascii_digit())?;
+    let digits = &name[start..];
+    let end = digits
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(digits.len());
+    let day = digits[..end].parse().ok()?;
+    (1..=25).contains(&day).then_some(day)
+}
+
+impl InputSource {
+    pub fn read(self) -> io::Result<Input> {
+        let from_file = |path: PathBuf| {
+            let text = fs::read_to_string(&path)?;
+            Ok(Input {
+                text,
+                file: Some(path),
+            })
+        };
+        let unnamed = |text| Input { text, file: None };
+        match self {
+            InputSource::Text(text) => Ok(unnamed(text)),
+            InputSource::Stdin => io::read_to_string(io::stdin()).map(unnamed),
+            InputSource::File(path) => from_file(path),
+            InputSource::FileOrStdin(path) => match from_file(path) {
+                Err(e) if e.kind() == io::ErrorKind::NotFound && !io::stdin().is_terminal() => {
+                    // An empty stdin (e.g. /dev/null) most likely means that nothing was
+                    // piped in, so report the missing file instead.
+                    let text = io::read_to_string(io::stdin())?;
+                    if text.is_empty() {
+                        Err(e)
+                    } else {
+                        Ok(unnamed(text))
+                    }
+                }
+                result => result,
+            },
+        }
+    }
+}
@@ -0,0 +1,416 @@
+//! Sets of integers stored as sorted runs, and maps that shift runs of integers around
+//! (e.g. the almanac maps of day 5), so that days can work on whole ranges of values at
+//! once instead of on every value in them.
+
+use alloc::vec::Vec;
+use core::{fmt, ops::Range};
+
+/// A set of integers, stored as sorted, disjoint, non-adjacent and non-empty ranges.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct RangeSet {
+    ranges: Vec<Range<usize>>,
+}
+
+impl RangeSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds every value in `range` to the set, merging it with the ranges it overlaps or
+    /// touches.
+    pub fn insert(&mut self, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+        let first = self.ranges.partition_point(|r| r.end < range.start);
+        let last = self.ranges.partition_point(|r| r.start <= range.end);
+        let merged = match self.ranges.get(first..last) {
+            Some([head, .., tail]) | Some([head @ tail]) => {
+                head.start.min(range.start)..tail.end.max(range.end)
+            }
+            _ => range,
+        };
+        self.ranges.splice(first..last, [merged]);
+    }
+
+    /// The ranges making up the set, in ascending order.
+    pub fn ranges(&self) -> &[Range<usize>] {
+        &self.ranges
+    }
+
+    /// The number of values in the set.
+    pub fn len(&self) -> usize {
+        self.ranges.iter().map(|r| r.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    pub fn min(&self) -> Option<usize> {
+        self.ranges.first().map(|r| r.start)
+    }
+
+    pub fn contains(&self, value: usize) -> bool {
+        let i = self.ranges.partition_point(|r| r.end <= value);
+        self.ranges.get(i).is_some_and(|r| r.start <= value)
+    }
+
+    /// The values that are in both `self` and `other`.
+    pub fn intersect(&self, other: &RangeSet) -> RangeSet {
+        let mut ranges = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while let (Some(a), Some(b)) = (self.ranges.get(i), other.ranges.get(j)) {
+            let overlap = a.start.max(b.start)..a.end.min(b.end);
+            if !overlap.is_empty() {
+                ranges.push(overlap);
+            }
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        RangeSet { ranges }
+    }
+
+    /// Splits the set into the values less than `value`, and those that are at least
+    /// `value`.
+    pub fn split_at(&self, value: usize) -> (RangeSet, RangeSet) {
+        let i = self.ranges.partition_point(|r| r.end <= value);
+        let mut below = self.ranges[..i].to_vec();
+        let mut above = self.ranges[i..].to_vec();
+        if let Some(r) = above.first_mut().filter(|r| r.start < value) {
+            below.push(r.start..value);
+            r.start = value;
+        }
+        (RangeSet { ranges: below }, RangeSet { ranges: above })
+    }
+}
+
+impl From<Range<usize>> for RangeSet {
+    fn from(range: Range<usize>) -> Self {
+        let mut set = RangeSet::new();
+        set.insert(range);
+        set
+    }
+}
+
+impl FromIterator<Range<usize>> for RangeSet {
+    fn from_iter<I: IntoIterator<Item = Range<usize>>>(iter: I) -> Self {
+        let mut set = RangeSet::new();
+        for range in iter {
+            set.insert(range);
+        }
+        set
+    }
+}
+
+/// Part of a [`RangeMap`], mapping `src..src + len` to `dst..dst + len`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Shift {
+    pub dst: usize,
+    pub src: usize,
+    pub len: usize,
+}
+
+impl Shift {
+    fn identity(start: usize, len: usize) -> Self {
+        Shift {
+            dst: start,
+            src: start,
+            len,
+        }
+    }
+
+    pub fn src_range(&self) -> Range<usize> {
+        self.src..self.src + self.len
+    }
+
+    pub fn dst_range(&self) -> Range<usize> {
+        self.dst..self.dst + self.len
+    }
+
+    /// Where this shift maps the values of `range` that it covers, and which values those
+    /// are. Returns `None` if it covers none of them.
+    fn apply(&self, range: Range<usize>) -> Option<Shift> {
+        let start = range.start.max(self.src);
+        let end = range.end.min(self.src + self.len);
+        (start < end).then(|| Shift {
+            dst: self.dst + (start - self.src),
+            src: start,
+            len: end - start,
+        })
+    }
+}
+
+impl fmt::Debug for Shift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} -> {:?}", self.src_range(), self.dst_range())
+    }
+}
+
+/// A function on integers that shifts runs of values by a fixed amount, and maps every
+/// value that isn't in any of its shifts to itself. The shifts shouldn't overlap; where
+/// they do, the one starting first wins.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RangeMap {
+    // Sorted by their source start.
+    shifts: Vec<Shift>,
+}
+
+impl RangeMap {
+    pub fn new(mut shifts: Vec<Shift>) -> Self {
+        shifts.retain(|s| s.len > 0);
+        shifts.sort_unstable_by_key(|s| s.src);
+        Self { shifts }
+    }
+
+    /// The shifts of the map, sorted by where their sources start.
+    pub fn shifts(&self) -> &[Shift] {
+        &self.shifts
+    }
+
+    pub fn get(&self, value: usize) -> usize {
+        match self.shifts.iter().find(|s| s.src_range().contains(&value)) {
+            Some(s) => s.dst + (value - s.src),
+            None => value,
+        }
+    }
+
+    /// The shifts of the map with the overlaps cut away, along with identity shifts for the
+    /// gaps between them, so that the pieces cover every value below `usize::MAX` exactly
+    /// once.
+    fn pieces(&self) -> Vec<Shift> {
+        let mut pieces = Vec::with_capacity(2 * self.shifts.len() + 1);
+        let mut next = 0;
+        for &shift in &self.shifts {
+            if shift.src > next {
+                pieces.push(Shift::identity(next, shift.src - next));
+            }
+            if let Some(piece) = shift.apply(next..usize::MAX) {
+                pieces.push(piece);
+                next = piece.src + piece.len;
+            }
+        }
+        if next < usize::MAX {
+            pieces.push(Shift::identity(next, usize::MAX - next));
+        }
+        pieces
+    }
+
+    /// The pieces of `pieces` that cover any value of `range`, cut down to those values.
+    fn pieces_in(pieces: &[Shift], range: Range<usize>) -> impl Iterator<Item = Shift> + '_ {
+        let first = pieces.partition_point(|p| p.src + p.len <= range.start);
+        pieces[first..]
+            .iter()
+            .take_while(move |p| p.src < range.end)
+            .filter_map(move |p| p.apply(range.clone()))
+    }
+
+    /// Returns a map `m` such that `m.get(x) == self.get(rhs.get(x))` for every `x`.
+    pub fn compose(&self, rhs: &RangeMap) -> RangeMap {
+        let lhs_pieces = self.pieces();
+        let mut shifts: Vec<Shift> = Vec::new();
+        for r in rhs.pieces() {
+            for l in Self::pieces_in(&lhs_pieces, r.dst_range()) {
+                let shift = Shift {
+                    dst: l.dst,
+                    src: r.src + (l.src - r.dst),
+                    len: l.len,
+                };
+                if shift.src == shift.dst {
+                    continue;
+                }
+                // Merge with the previous shift if it continues it.
+                match shifts.last_mut() {
+                    Some(prev)
+                        if prev.src + prev.len == shift.src && prev.dst + prev.len == shift.dst =>
+                    {
+                        prev.len += shift.len
+                    }
+                    _ => shifts.push(shift),
+                }
+            }
+        }
+        RangeMap { shifts }
+    }
+
+    /// The smallest value that the map maps any value of `set` to, or `None` if the set is
+    /// empty.
+    pub fn min_image(&self, set: &RangeSet) -> Option<usize> {
+        let pieces = self.pieces();
+        set.ranges()
+            .iter()
+            .flat_map(|r| Self::pieces_in(&pieces, r.clone()))
+            .map(|p| p.dst)
+            .min()
+    }
+}
+
+#[cfg(test)]
+// The sets are written as arrays of their ranges, some of which only have one.
+#[allow(clippy::single_range_in_vec_init)]
+mod tests {
+    use super::*;
+
+    fn shift(src: Range<usize>, dst: usize) -> Shift {
+        Shift {
+            dst,
+            src: src.start,
+            len: src.len(),
+        }
+    }
+
+    #[test]
+    fn empty_and_touching_ranges() {
+        let mut set = RangeSet::new();
+        set.insert(5..5);
+        assert!(set.is_empty());
+        assert_eq!(set.min(), None);
+
+        set.insert(0..3);
+        set.insert(3..6);
+        set.insert(8..10);
+        set.insert(7..7);
+        assert_eq!(set.ranges(), [0..6, 8..10]);
+        set.insert(6..8);
+        assert_eq!(set.ranges(), [0..10]);
+        set.insert(12..14);
+        set.insert(2..11);
+        assert_eq!(set.ranges(), [0..11, 12..14]);
+        assert_eq!(set.len(), 13);
+        assert!(set.contains(10) && !set.contains(11) && set.contains(12));
+        assert!(!set.contains(14));
+
+        let set: RangeSet = [4..4, 10..12, 12..15, 1..2].into_iter().collect();
+        assert_eq!(set.ranges(), [1..2, 10..15]);
+        // Touching ranges don't share any values.
+        assert!(set.intersect(&RangeSet::from(2..10)).is_empty());
+        assert_eq!(
+            set.intersect(&RangeSet::from(1..11)).ranges(),
+            [1..2, 10..11]
+        );
+
+        let (below, above) = set.split_at(10);
+        assert_eq!(
+            (below.ranges(), above.ranges()),
+            (&[1..2][..], &[10..15][..])
+        );
+        let (below, above) = set.split_at(12);
+        assert_eq!(
+            (below.ranges(), above.ranges()),
+            (&[1..2, 10..12][..], &[12..15][..])
+        );
+        let (below, above) = set.split_at(15);
+        assert_eq!((below, above.is_empty()), (set.clone(), true));
+        let (below, above) = set.split_at(0);
+        assert_eq!((below.is_empty(), above), (true, set));
+    }
+
+    #[test]
+    fn identity_stretches() {
+        assert_eq!(RangeMap::default().get(12), 12);
+        let map = RangeMap::new(vec![shift(10..20, 50), shift(30..35, 0), shift(40..40, 7)]);
+        assert_eq!(map.shifts(), [shift(10..20, 50), shift(30..35, 0)]);
+        for (x, y) in [
+            (0, 0),
+            (9, 9),
+            (10, 50),
+            (19, 59),
+            (20, 20),
+            (29, 29),
+            (30, 0),
+        ] {
+            assert_eq!(map.get(x), y);
+        }
+        assert_eq!(map.get(35), 35);
+        assert_eq!(map.get(usize::MAX), usize::MAX);
+
+        // The pieces cover everything once, with the gaps mapping to themselves.
+        let pieces = map.pieces();
+        assert_eq!(
+            pieces[..4],
+            [
+                Shift::identity(0, 10),
+                shift(10..20, 50),
+                Shift::identity(20, 10),
+                shift(30..35, 0)
+            ]
+        );
+        assert_eq!(pieces[4], Shift::identity(35, usize::MAX - 35));
+
+        // Swapping two ranges twice leaves nothing but identity stretches, which aren't kept.
+        let swap = RangeMap::new(vec![shift(10..20, 30), shift(30..40, 10)]);
+        let twice = swap.compose(&swap);
+        assert_eq!(twice.shifts(), []);
+        for x in [5, 10, 25, 30, 45] {
+            assert_eq!(twice.get(x), x);
+        }
+    }
+
+    // Checks `compose` against `get` on every value that the maps move around.
+    fn check_compose(lhs: &RangeMap, rhs: &RangeMap) {
+        let composed = lhs.compose(rhs);
+        for x in 0..80 {
+            assert_eq!(
+                composed.get(x),
+                lhs.get(rhs.get(x)),
+                "{lhs:?} after {rhs:?} at {x}"
+            );
+        }
+        // The shifts don't overlap, so the first one covering a value is the only one.
+        for pair in composed.shifts().windows(2) {
+            assert!(pair[0].src + pair[0].len <= pair[1].src, "{composed:?}");
+        }
+    }
+
+    #[test]
+    fn compose_with_gaps() {
+        let gaps = RangeMap::new(vec![shift(10..20, 50), shift(30..35, 0)]);
+        let cases = [
+            // Maps the gaps of `gaps` elsewhere.
+            RangeMap::new(vec![shift(0..10, 60), shift(20..30, 40)]),
+            // Partly overlaps both its sources and its destinations.
+            RangeMap::new(vec![shift(15..32, 3), shift(55..58, 70)]),
+            // Touches every range's ends.
+            RangeMap::new(vec![shift(20..30, 10), shift(50..60, 20)]),
+            // Overlapping shifts, of which the first wins.
+            RangeMap::new(vec![shift(5..25, 45), shift(8..12, 0)]),
+            RangeMap::default(),
+        ];
+        for other in &cases {
+            check_compose(&gaps, other);
+            check_compose(other, &gaps);
+        }
+        for (a, b) in cases.iter().zip(cases.iter().rev()) {
+            check_compose(a, b);
+        }
+    }
+
+    #[test]
+    fn min_image_of_partial_overlaps() {
+        let map = RangeMap::new(vec![shift(10..20, 0), shift(30..40, 100)]);
+        let min_image = |ranges: &[Range<usize>]| {
+            let set: RangeSet = ranges.iter().cloned().collect();
+            let brute = set
+                .ranges()
+                .iter()
+                .flat_map(|r| r.clone().map(|x| map.get(x)))
+                .min();
+            let min = map.min_image(&set);
+            assert_eq!(min, brute, "{ranges:?}");
+            min
+        };
+        assert_eq!(min_image(&[]), None);
+        // Only the start of the range gets shifted down, past the values before it.
+        assert_eq!(min_image(&[5..15]), Some(0));
+        assert_eq!(min_image(&[15..25]), Some(5));
+        // Shifted up, so the values after the shift are the smallest.
+        assert_eq!(min_image(&[35..45]), Some(40));
+        assert_eq!(min_image(&[25..35]), Some(25));
+        assert_eq!(min_image(&[32..36, 38..42]), Some(40));
+        assert_eq!(min_image(&[19..20, 30..31]), Some(9));
+        assert_eq!(min_image(&[20..30]), Some(20));
+    }
+}
@@ -0,0 +1,49 @@
+//! Helpers for parsers that work on the bytes of the input instead of a `str`. Puzzle
+//! inputs are ASCII, so the parsers that matter for speed can skip validating UTF-8 and
+//! decoding `char`s, and still report problems as a [`ParseError`].
+
+use alloc::string::{String, ToString};
+
+use crate::parse::{ParseError, ParseErrorKind};
+
+/// The lines of `input`, without their line endings, like [`str::lines`].
+pub fn lines(input: &[u8]) -> impl Iterator<Item = &[u8]> + Clone {
+    let mut rest = (!input.is_empty()).then_some(input);
+    core::iter::from_fn(move || {
+        let s = rest?;
+        let (line, next) = match s.iter().position(|&b| b == b'\n') {
+            Some(i) => (&s[..i], Some(&s[i + 1..]).filter(|s| !s.is_empty())),
+            None => (s, None),
+        };
+        rest = next;
+        Some(line.strip_suffix(b"\r").unwrap_or(line))
+    })
+}
+
+/// The number of ASCII digits at the start of `s`.
+pub fn digits(s: &[u8]) -> usize {
+    s.iter().take_while(|b| b.is_ascii_digit()).count()
+}
+
+/// Parses `s` as an unsigned decimal number, quoting it in the error like [`parse::num`].
+///
+/// [`parse::num`]: crate::parse::num
+pub fn uint(s: &[u8]) -> Result<usize, ParseError> {
+    let invalid = |reason: &str| {
+        ParseError::new(ParseErrorKind::InvalidNumber {
+            token: String::from_utf8_lossy(s).into_owned(),
+            reason: reason.to_string(),
+        })
+    };
+    if s.is_empty() {
+        return Err(invalid("cannot parse integer from empty string"));
+    }
+    s.iter().try_fold(0usize, |n, &b| {
+        if !b.is_ascii_digit() {
+            return Err(invalid("invalid digit found in string"));
+        }
+        n.checked_mul(10)
+            .and_then(|n| n.checked_add((b - b'0') as usize))
+            .ok_or_else(|| invalid("number too large to fit in target type"))
+    })
+}
@@ -0,0 +1,319 @@
+//! The solutions of all 25 days, usable without the runner. Every day's module implements
+//! [`Puzzle`] for its `DayN` type, which parses the puzzle input once and solves both parts
+//! on the result, and registers the day along with its title and tags. [`days`] lists the
+//! registered days, and [`solver`] looks up a day's [`Solver`] by number. Malformed inputs
+//! make the parts return an error rather than panic, and problems found while parsing are
+//! reported as a [`ParseError`]. Inputs should go through [`preprocess`] before being
+//! solved.
+//!
+//! Without the `std` feature, the library only needs `alloc`. The solvers can't be timed
+//! then, and `stream` is left out. Day 24 turns `std` on, since its exact
+//! fractions come from a crate that needs it.
+
+#![no_std]
+#![allow(clippy::type_complexity, clippy::enum_variant_names)]
+
+#[macro_use]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(feature = "std")]
+use std::time::Duration;
+
+use serde::Serialize;
+
+pub use answer::Answer;
+pub use parse::{ParseError, ParseErrorKind};
+pub use preprocess::preprocess;
+
+mod answer;
+pub mod cancel;
+pub mod collections;
+pub mod explain;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod geometry;
+pub mod graph;
+pub mod grid;
+pub mod hyperrect;
+pub mod intervals;
+// Which of the parsing helpers get used depends on the days that are compiled in.
+#[cfg_attr(not(feature = "all-days"), allow(dead_code))]
+mod lex;
+pub mod math;
+#[cfg_attr(not(feature = "all-days"), allow(dead_code))]
+mod parse;
+mod preprocess;
+#[cfg(feature = "std")]
+pub mod stream;
+pub mod text;
+pub mod viz;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+/// A day's solution, split into parsing the input and solving either part on what was
+/// parsed, so that both parts can share the parsing. `Parsed` may borrow from the input.
+///
+/// Parsing starts from the bytes of the input, so that days which only care about ASCII
+/// don't pay for validating UTF-8. The others get a `str` from `parse::utf8`.
+///
+/// The parts get a [`cancel::Token`] from their caller. Days that can run for a while
+/// check it in their long loops and fail with [`cancel::Cancelled`] once it's cancelled,
+/// the others ignore it.
+pub trait Puzzle {
+    type Parsed<'a>;
+
+    /// Names the algorithm the day uses, like `"junction-graph-dfs-v1"`. It gets recorded
+    /// with every result, so that timings from different versions of a day can be told
+    /// apart. Change it whenever the algorithm changes enough to affect the timings.
+    const ALGORITHM: &'static str;
+
+    fn parse(input: &[u8]) -> anyhow::Result<Self::Parsed<'_>>;
+    fn solve1(parsed: &Self::Parsed<'_>, cancel: &cancel::Token) -> anyhow::Result<Answer>;
+    fn solve2(parsed: &Self::Parsed<'_>, cancel: &cancel::Token) -> anyhow::Result<Answer>;
+}
+
+/// Both parts of a day's solution, for looking days up by number. The solvers take the
+/// input as bytes, and [`Solver::part`] and friends wrap them for callers with a `str`.
+pub trait Solver: Sync {
+    /// The day's [`Puzzle::ALGORITHM`].
+    fn algorithm(&self) -> &'static str;
+
+    /// Runs part 1 or 2, returning the answer and how long parsing the input took. Panics
+    /// for any other part.
+    #[cfg(feature = "std")]
+    fn part_timed(
+        &self,
+        part: usize,
+        input: &[u8],
+        cancel: &cancel::Token,
+    ) -> anyhow::Result<(Answer, Duration)>;
+
+    /// Parses the input once and solves both parts on it. Returns how long parsing took,
+    /// and the answer of each part along with how long solving it took. Only failing to
+    /// parse the input fails both parts. Part 2 doesn't start if `cancel` got cancelled
+    /// while part 1 was running.
+    #[cfg(feature = "std")]
+    fn both_timed(
+        &self,
+        input: &[u8],
+        cancel: &cancel::Token,
+    ) -> anyhow::Result<(Duration, [anyhow::Result<(Answer, Duration)>; 2])>;
+
+    /// Runs part 1 or 2 without timing it, which also works where there's no clock (like
+    /// in the browser or without the `std` feature). Panics for any other part.
+    fn part(&self, part: usize, input: &str, cancel: &cancel::Token) -> anyhow::Result<Answer>;
+
+    /// Runs part 1 with a token that never gets cancelled.
+    fn part1(&self, input: &str) -> anyhow::Result<Answer> {
+        self.part(1, input, &cancel::Token::default())
+    }
+
+    /// Runs part 2 with a token that never gets cancelled.
+    fn part2(&self, input: &str) -> anyhow::Result<Answer> {
+        self.part(2, input, &cancel::Token::default())
+    }
+}
+
+/// Implements [`Solver`] for a day's type and adds the day to the registry, along with
+/// its title on the Advent of Code website, tags saying what kind of puzzle it is and
+/// alternative solvers of its parts (see [`Variant`]):
+///
+/// ```text
+/// register_day!(6, Day6, "Wait For It", tags: ["math"], variants: [(1, "scan", part1_scan)]);
+/// ```
+#[cfg_attr(not(feature = "all-days"), allow(unused_macros))]
+macro_rules! register_day {
+    (
+        $day:literal, $name:ident, $title:literal,
+        tags: [$($tag:literal),* $(,)?]
+        $(, variants: [$(($part:literal, $variant:literal, $solve:path)),* $(,)?])?
+        $(,)?
+    ) => {
+        impl $crate::Solver for $crate::$name {
+            fn algorithm(&self) -> &'static str {
+                <Self as $crate::Puzzle>::ALGORITHM
+            }
+
+            #[cfg(feature = "std")]
+            fn part_timed(
+                &self,
+                part: usize,
+                input: &[u8],
+                cancel: &$crate::cancel::Token,
+            ) -> anyhow::Result<($crate::Answer, std::time::Duration)> {
+                use $crate::Puzzle;
+                let _span = tracing::info_span!("day", day = $day, part).entered();
+                let start = std::time::Instant::now();
+                let parsed = tracing::info_span!("parse")
+                    .in_scope(|| Self::parse(input))
+                    .map_err(|e| $crate::parse::in_day(e, $day))?;
+                let parse_time = start.elapsed();
+                let answer = tracing::info_span!("solve").in_scope(|| match part {
+                    1 => Self::solve1(&parsed, cancel),
+                    2 => Self::solve2(&parsed, cancel),
+                    _ => panic!("there is no part {}", part),
+                });
+                Ok((answer.map_err(|e| $crate::parse::in_day(e, $day))?, parse_time))
+            }
+
+            #[cfg(feature = "std")]
+            fn both_timed(
+                &self,
+                input: &[u8],
+                cancel: &$crate::cancel::Token,
+            ) -> anyhow::Result<(
+                std::time::Duration,
+                [anyhow::Result<($crate::Answer, std::time::Duration)>; 2],
+            )> {
+                use $crate::Puzzle;
+                let _span = tracing::info_span!("day", day = $day).entered();
+                let start = std::time::Instant::now();
+                let parsed = tracing::info_span!("parse")
+                    .in_scope(|| Self::parse(input))
+                    .map_err(|e| $crate::parse::in_day(e, $day))?;
+                let parse_time = start.elapsed();
+                let answers = [1, 2].map(|part| {
+                    cancel.check()?;
+                    let start = std::time::Instant::now();
+                    let answer = tracing::info_span!("solve", part).in_scope(|| match part {
+                        1 => Self::solve1(&parsed, cancel),
+                        _ => Self::solve2(&parsed, cancel),
+                    });
+                    let answer = answer.map_err(|e| $crate::parse::in_day(e, $day))?;
+                    Ok((answer, start.elapsed()))
+                });
+                Ok((parse_time, answers))
+            }
+
+            fn part(
+                &self,
+                part: usize,
+                input: &str,
+                cancel: &$crate::cancel::Token,
+            ) -> anyhow::Result<$crate::Answer> {
+                use $crate::Puzzle;
+                let _span = tracing::info_span!("day", day = $day, part).entered();
+                let parsed =
+                    Self::parse(input.as_bytes()).map_err(|e| $crate::parse::in_day(e, $day))?;
+                let answer = match part {
+                    1 => Self::solve1(&parsed, cancel),
+                    2 => Self::solve2(&parsed, cancel),
+                    _ => panic!("there is no part {}", part),
+                };
+                answer.map_err(|e| $crate::parse::in_day(e, $day))
+            }
+        }
+
+        inventory::submit! {
+            $crate::DayInfo {
+                day: $day,
+                title: $title,
+                tags: &[$($tag),*],
+                solver: &$crate::$name,
+                variants: &[$($($crate::Variant {
+                    part: $part,
+                    name: $variant,
+                    solve: $solve,
+                }),*)?],
+            }
+        }
+    };
+}
+
+/// Declares the days, each of which only gets compiled in with its `dayN` feature. The
+/// modules register their days themselves with [`register_day!`].
+macro_rules! days {
+    ($($module:ident, $name:ident, $feature:literal;)*) => {
+        $(
+            #[cfg(feature = $feature)]
+            pub mod $module;
+
+            #[cfg(feature = $feature)]
+            #[doc = concat!("The [`Puzzle`] and [`Solver`] of [`", stringify!($module), "`].")]
+            pub struct $name;
+        )*
+    };
+}
+
+days! {
+    day1, Day1, "day1";
+    day2, Day2, "day2";
+    day3, Day3, "day3";
+    day4, Day4, "day4";
+    day5, Day5, "day5";
+    day6, Day6, "day6";
+    day7, Day7, "day7";
+    day8, Day8, "day8";
+    day9, Day9, "day9";
+    day10, Day10, "day10";
+    day11, Day11, "day11";
+    day12, Day12, "day12";
+    day13, Day13, "day13";
+    day14, Day14, "day14";
+    day15, Day15, "day15";
+    day16, Day16, "day16";
+    day17, Day17, "day17";
+    day18, Day18, "day18";
+    day19, Day19, "day19";
+    day20, Day20, "day20";
+    day21, Day21, "day21";
+    day22, Day22, "day22";
+    day23, Day23, "day23";
+    day24, Day24, "day24";
+    day25, Day25, "day25";
+}
+
+/// What `P` parses `input` into, as JSON, for debugging the parser or feeding the input to
+/// other tools.
+pub fn dump_parsed<P: Puzzle>(input: &[u8]) -> anyhow::Result<serde_json::Value>
+where
+    for<'a> P::Parsed<'a>: Serialize,
+{
+    let parsed = P::parse(input)?;
+    Ok(serde_json::to_value(&parsed)?)
+}
+
+/// A day in the registry, added by the day's module with [`register_day!`].
+pub struct DayInfo {
+    pub day: usize,
+    /// The puzzle's title on the Advent of Code website.
+    pub title: &'static str,
+    /// What kind of puzzle it is, like `"grid"` or `"graph"`.
+    pub tags: &'static [&'static str],
+    pub solver: &'static dyn Solver,
+    pub variants: &'static [Variant],
+}
+
+/// An alternative solver of one part of a day, for checking the day's [`Solver`] against
+/// (see `run-part --all-variants`).
+pub struct Variant {
+    pub part: usize,
+    pub name: &'static str,
+    pub solve: fn(&str) -> anyhow::Result<Answer>,
+}
+
+inventory::collect!(DayInfo);
+
+/// The registry entry of `day`, or `None` if there's no such day or it isn't compiled in.
+pub fn day_info(day: usize) -> Option<&'static DayInfo> {
+    inventory::iter::<DayInfo>
+        .into_iter()
+        .find(|info| info.day == day)
+}
+
+/// The registry entries of the days that are compiled in, in order.
+pub fn days() -> impl Iterator<Item = &'static DayInfo> {
+    (1..=25).filter_map(day_info)
+}
+
+/// The solver of `day`, or `None` if there's no such day or it isn't compiled in.
+pub fn solver(day: usize) -> Option<&'static dyn Solver> {
+    Some(day_info(day)?.solver)
+}
+
+/// Whether `day` is one of the 25 days, even if it isn't compiled in.
+pub fn is_day(day: usize) -> bool {
+    (1..=25).contains(&day)
+}
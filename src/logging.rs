@@ -0,0 +1,43 @@
+//! Where the `tracing` spans of the solvers go: to stderr for `-v`, and to a Chrome trace
+//! file for `--chrome-trace`. The solvers open a span for parsing and for each part, and
+//! the days with several phases (like 22 and 23) a span for each phase as well.
+
+use std::{io, path::Path};
+
+use tracing_chrome::{ChromeLayerBuilder, FlushGuard};
+use tracing_subscriber::{filter::LevelFilter, fmt::format::FmtSpan, prelude::*};
+
+/// Installs the subscriber for `verbose` (the number of `-v`s) and `chrome_trace`,
+/// coloring the log if `color` is set. The trace file is only complete once the
+/// returned guard has been dropped. Without either, no subscriber is installed, so
+/// that the spans cost next to nothing.
+pub fn init(verbose: u8, chrome_trace: Option<&Path>, color: bool) -> Option<FlushGuard> {
+    if verbose == 0 && chrome_trace.is_none() {
+        return None;
+    }
+    let level = match verbose {
+        1 => LevelFilter::INFO,
+        2 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    };
+    // Spans get printed when they close, since that's when their duration is known.
+    let stderr = (verbose > 0).then(|| {
+        tracing_subscriber::fmt::layer()
+            .with_writer(io::stderr)
+            .with_ansi(color)
+            .with_span_events(FmtSpan::CLOSE)
+            .with_filter(level)
+    });
+    let (chrome, guard) = match chrome_trace {
+        Some(path) => {
+            let (layer, guard) = ChromeLayerBuilder::new().file(path).build();
+            (Some(layer), Some(guard))
+        }
+        None => (None, None),
+    };
+    tracing_subscriber::registry()
+        .with(stderr)
+        .with(chrome)
+        .init();
+    guard
+}
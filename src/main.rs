@@ -1,121 +1,1908 @@
 #![allow(clippy::type_complexity, clippy::enum_variant_names)]
-#![feature(isqrt)]
 
-use std::time::{Duration, Instant};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env, fs,
+    io::{self, BufRead},
+    iter,
+    path::{Path, PathBuf},
+    process::{Command, ExitCode, Stdio},
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
 
 use anyhow::Context;
-use clap::Parser;
-use seq_macro::seq;
-
-seq!(N in 1..=25 {
-    mod day~N;
-});
-
-seq!(N in 1..=25 {
-    #[used]
-    static FNS: [[fn(&str) -> String; 2]; 25] = [
-        #(
-            [day~N::part1, day~N::part2],
-        )*
-    ];
-});
+use aoc2023::{cancel, explain, viz::Image, Answer, ParseError};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use config::Config;
+use history::History;
+use indicatif::ProgressBar;
+use input::{InputArgs, InputSource};
+use output::{Exit, Format, Outcome, PartResult, Printer, Report, Template, Timing};
+use paths::StateFile;
+use provenance::Provenance;
+use selection::DaySelection;
+use stats::Stats;
+
+mod affinity;
+mod client;
+mod clipboard;
+mod config;
+#[cfg(feature = "gui")]
+mod gui;
+mod history;
+mod init;
+mod input;
+mod logging;
+mod memory;
+// `render_details` is only used by the days that have details.
+#[cfg_attr(not(feature = "all-days"), allow(dead_code))]
+mod output;
+mod panics;
+mod paths;
+mod plugin;
+#[cfg(feature = "profile")]
+mod profile;
+mod provenance;
+mod scaffold;
+mod selection;
+mod serve;
+mod stats;
+mod status;
+mod tui;
+mod watch;
+
+#[global_allocator]
+static ALLOCATOR: memory::Counting = memory::Counting;
+
+type DetailsFn = fn(&str, Format) -> anyhow::Result<String>;
+
+// Days that can report more than just the answer, for the `details` command.
+static DETAILS: &[(usize, DetailsFn)] = &[
+    #[cfg(feature = "day5")]
+    (5, |input, format| {
+        output::render_details(5, aoc2023::day5::details(input)?, format)
+    }),
+    #[cfg(feature = "day8")]
+    (8, |input, format| {
+        output::render_details(8, aoc2023::day8::details(input)?, format)
+    }),
+    #[cfg(feature = "day14")]
+    (14, |input, format| {
+        output::render_details(14, aoc2023::day14::details(input)?, format)
+    }),
+    #[cfg(feature = "day16")]
+    (16, |input, format| {
+        output::render_details(16, aoc2023::day16::details(input)?, format)
+    }),
+    #[cfg(feature = "day20")]
+    (20, |input, format| {
+        output::render_details(20, aoc2023::day20::details(input)?, format)
+    }),
+    #[cfg(feature = "day22")]
+    (22, |input, format| {
+        output::render_details(22, aoc2023::day22::details(input)?, format)
+    }),
+    #[cfg(feature = "day24")]
+    (24, |input, format| {
+        output::render_details(24, aoc2023::day24::details(input)?, format)
+    }),
+    #[cfg(feature = "day25")]
+    (25, |input, format| {
+        let progress =
+            output::progress_bar(0, "{bar:40} phase {pos}/{len}, best cut {msg} (ETA {eta})");
+        let details = aoc2023::day25::details(input, |p| {
+            progress.set_length(p.phases as u64);
+            progress.set_position(p.phase as u64);
+            progress.set_message(p.best_cut.to_string());
+        });
+        progress.finish_and_clear();
+        output::render_details(25, details?, format)
+    }),
+];
+
+// Days whose parsed input can be written out, for `run-day --dump-parsed`.
+static DUMPS: &[(usize, fn(&[u8]) -> anyhow::Result<serde_json::Value>)] = &[
+    #[cfg(feature = "day2")]
+    (2, aoc2023::dump_parsed::<aoc2023::Day2>),
+    #[cfg(feature = "day19")]
+    (19, aoc2023::dump_parsed::<aoc2023::Day19>),
+    #[cfg(feature = "day22")]
+    (22, aoc2023::dump_parsed::<aoc2023::Day22>),
+    #[cfg(feature = "day24")]
+    (24, aoc2023::dump_parsed::<aoc2023::Day24>),
+];
+
+type ShapeFn = fn(&str) -> anyhow::Result<Vec<(&'static str, usize)>>;
+
+// Days that can count what their input consists of, for the `diff` command.
+static SHAPES: &[(usize, ShapeFn)] = &[
+    #[cfg(feature = "day2")]
+    (2, aoc2023::day2::shape),
+    #[cfg(feature = "day8")]
+    (8, aoc2023::day8::shape),
+    #[cfg(feature = "day19")]
+    (19, aoc2023::day19::shape),
+    #[cfg(feature = "day22")]
+    (22, aoc2023::day22::shape),
+];
+
+// Days that can draw their input, for the `viz` command.
+static VISUALIZATIONS: &[(usize, fn(&[u8]) -> anyhow::Result<Image>)] = &[
+    #[cfg(feature = "day10")]
+    (10, <aoc2023::Day10 as aoc2023::viz::Visualize>::visualize),
+    #[cfg(feature = "day16")]
+    (16, <aoc2023::Day16 as aoc2023::viz::Visualize>::visualize),
+];
+
+#[derive(clap::Args)]
+struct RunOptions {
+    /// Show how long each part took. Defaults to `show-time` from the config file, which
+    /// `--show-time=false` turns off again.
+    #[arg(
+        short = 't',
+        long,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true"
+    )]
+    show_time: Option<bool>,
+    /// Show the most heap memory each part had allocated at once. The parts of a day
+    /// don't share their parsed input then, so that each part's memory includes parsing.
+    #[arg(long)]
+    show_memory: bool,
+    /// Give up on a run if it hasn't finished after this long (e.g. `10s`).
+    #[arg(long, value_parser = humantime::parse_duration)]
+    timeout: Option<Duration>,
+    /// Number of measured runs per part.
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    runs: u32,
+    /// Number of extra runs before the measured ones, whose timings get discarded.
+    #[arg(long, default_value_t = 0)]
+    warmup: u32,
+    /// Keep warming up after the `--warmup` runs until this many milliseconds have passed,
+    /// so that caches and the CPU's clock speed have settled before measuring.
+    #[arg(long, value_name = "M", default_value_t = 0)]
+    warmup_ms: u64,
+    /// Pin the runner to this CPU core (on Linux only), so that the scheduler moving it
+    /// around doesn't add noise to the timings. The threads of days that solve in
+    /// parallel get pinned to the same core.
+    #[arg(long, value_name = "N")]
+    pin_core: Option<usize>,
+    /// Discard timings more than 3 median absolute deviations away from the median.
+    #[arg(long)]
+    reject_outliers: bool,
+    /// Only print the answers, one per line.
+    #[arg(short, long)]
+    quiet: bool,
+    /// Print one line per result, rendered from this template, e.g.
+    /// `'{day}.{part}: {answer} ({time_ms}ms)'`. The fields are day, part, input, status,
+    /// answer, algorithm, time_ms, time_us and time_ns.
+    #[arg(long, conflicts_with_all = ["quiet", "format"])]
+    template: Option<Template>,
+    /// Print the steps the solvers explain their answers with. They come from an extra,
+    /// untimed run of every solved part.
+    #[arg(long)]
+    explain: bool,
+    /// Use the wide variant of the parts that have one (days 6, 9 and 11), which computes
+    /// with big integers so that the answer can't overflow on oversized inputs.
+    #[arg(long)]
+    wide: bool,
+    /// Put the last answer on the clipboard, ready to be pasted into the answer box.
+    #[arg(long)]
+    copy: bool,
+    /// Append the results to this file, one JSON object per line with the fields of
+    /// `--format json`'s results and the time of the run, so that runs add up to a log.
+    #[arg(long)]
+    save_output: Option<PathBuf>,
+    /// Cancelling this stops the running solver.
+    #[arg(skip)]
+    cancel: cancel::Token,
+    /// The `--threads` of the runner, for passing on to subprocesses.
+    #[arg(skip)]
+    threads: Option<usize>,
+    /// Parse the input once and solve both parts on it when running both parts of a day.
+    #[arg(skip)]
+    share_parse: bool,
+    /// Write the parsed input of the day being run to this file.
+    #[arg(skip)]
+    dump_parsed: Option<PathBuf>,
+    /// Reuse the recorded results of days whose input hasn't changed since both parts
+    /// were last solved, instead of running them again.
+    #[arg(skip)]
+    use_cache: bool,
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+}
+
+impl RunOptions {
+    fn warmup(&self) -> Warmup {
+        Warmup {
+            runs: self.warmup,
+            time: Duration::from_millis(self.warmup_ms),
+        }
+    }
+
+    fn printer(&self, color: bool) -> Printer {
+        let mut printer = Printer::new(
+            self.format,
+            self.show_time.unwrap_or(false),
+            self.quiet,
+            self.template.clone(),
+            color,
+        );
+        if self.copy {
+            printer.copy_answer();
+        }
+        if let Some(path) = &self.save_output {
+            printer.save_output(path.clone());
+        }
+        printer
+    }
+
+    /// The options of the interactive frontends, which run every part once.
+    fn interactive(timeout: Option<Duration>) -> Self {
+        RunOptions {
+            show_time: Some(true),
+            show_memory: false,
+            timeout,
+            runs: 1,
+            warmup: 0,
+            warmup_ms: 0,
+            pin_core: None,
+            reject_outliers: false,
+            quiet: false,
+            template: None,
+            explain: false,
+            wide: false,
+            copy: false,
+            save_output: None,
+            format: Format::Text,
+            cancel: cancel::Token::default(),
+            threads: None,
+            share_parse: false,
+            dump_parsed: None,
+            use_cache: false,
+        }
+    }
+}
 
 #[derive(Parser)]
+struct Cli {
+    /// Directory with the `day{N}.txt` input files. Defaults to `$AOC_INPUT_DIR`, then
+    /// `input-dir` from the config file, then `input`.
+    #[arg(long, global = true)]
+    input_dir: Option<PathBuf>,
+    /// Don't color the text output. Setting `NO_COLOR` does the same.
+    #[arg(long, global = true)]
+    no_color: bool,
+    /// Log every parse and solve to stderr with how long it took. Repeat for more detail
+    /// (`-vv` also logs the phases inside days).
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    verbose: u8,
+    /// Write a trace of the parses, solves and phases inside days to this file, for
+    /// `chrome://tracing` or Perfetto.
+    #[arg(long, global = true)]
+    chrome_trace: Option<PathBuf>,
+    /// Number of threads for the days that solve in parallel (with the `parallel`
+    /// feature), or 0 for one per core. Defaults to `threads` from the config file, then
+    /// to one per core.
+    #[arg(long, value_name = "N", global = true)]
+    threads: Option<usize>,
+    #[command(subcommand)]
+    command: Args,
+}
+
+#[derive(Subcommand)]
 enum Args {
     #[clap(alias = "rp")]
     RunPart {
         day: usize,
         part: usize,
-        #[arg(short, long)]
-        input: Option<String>,
-        #[arg(short = 't', long)]
-        show_time: bool,
+        #[command(flatten)]
+        input: InputArgs,
+        /// Run against every file matching this glob pattern instead, and print a table
+        /// of the results (e.g. `'inputs/day12/*.txt'`).
+        #[arg(long, conflicts_with = "InputArgs")]
+        inputs: Option<String>,
+        /// Run every variant of the part's solver, and check that they agree on the answer.
+        #[arg(long, conflicts_with = "inputs")]
+        all_variants: bool,
+        /// Also run the solver of this shared library (see `src/plugin.rs` for what it has
+        /// to export), and check that it agrees with the built-in variants. Can be given
+        /// more than once.
+        #[arg(long = "plugin", value_name = "PATH", conflicts_with = "inputs")]
+        plugins: Vec<PathBuf>,
+        /// Time this many runs after a warmup run, for quick benchmarks. Short for
+        /// `--runs N --warmup 1`.
+        #[arg(
+            long,
+            value_name = "N",
+            conflicts_with_all = ["runs", "warmup"],
+            value_parser = clap::value_parser!(u32).range(1..),
+        )]
+        repeat: Option<u32>,
+        #[command(flatten)]
+        opts: RunOptions,
     },
     #[clap(alias = "rd")]
     RunDay {
         day: usize,
-        #[clap(short, long)]
-        input: Option<String>,
-        #[arg(short = 't', long)]
-        show_time: bool,
+        #[command(flatten)]
+        input: InputArgs,
+        #[arg(short = 'T', long)]
+        show_total_time: bool,
+        /// Also write what the day parses the input into to this file, as JSON. Only some
+        /// days support this.
+        #[arg(long)]
+        dump_parsed: Option<PathBuf>,
+        #[command(flatten)]
+        opts: RunOptions,
+    },
+    /// Run both parts on an input file, taking the day from the file name.
+    Run {
+        /// The input file, e.g. `path/to/day17.txt`.
+        #[arg(long)]
+        auto: PathBuf,
         #[arg(short = 'T', long)]
         show_total_time: bool,
+        #[command(flatten)]
+        opts: RunOptions,
     },
     #[clap(alias = "ra")]
     RunAll {
-        #[arg(short = 't', long)]
-        show_time: bool,
+        /// Which days to run, e.g. `5..=10` or `1,3,17`.
+        #[arg(long, default_value = "1..=25")]
+        days: DaySelection,
+        /// Only run the days with this tag (see the `days` command), e.g. `graph`.
+        #[arg(long)]
+        tag: Option<String>,
         #[arg(short = 'T', long)]
         show_total_time: bool,
+        /// Don't start any more days once this much time has passed (e.g. `5s`).
+        #[arg(long, value_parser = humantime::parse_duration)]
+        budget: Option<Duration>,
+        /// Print the recorded answers of days whose input hasn't changed since both parts
+        /// were solved, marked as cached, and only run the other days. Cached parts show
+        /// the time they took when they were recorded.
+        #[arg(long)]
+        cached: bool,
+        /// Also write every result, along with the git revision the runner was built from,
+        /// to this file as JSON (e.g. `summary.json`), for archiving or comparing runs
+        /// between machines.
+        #[arg(long, value_name = "PATH")]
+        summary: Option<PathBuf>,
+        #[command(flatten)]
+        opts: RunOptions,
+    },
+    /// Like `run-all`, but every day runs in a fresh process, so that later days don't
+    /// benefit from the allocator and caches being warmed up by earlier ones.
+    BenchAll {
+        /// Which days to run, e.g. `5..=10` or `1,3,17`.
+        #[arg(long, default_value = "1..=25")]
+        days: DaySelection,
+        /// Only run the days with this tag (see the `days` command), e.g. `graph`.
+        #[arg(long)]
+        tag: Option<String>,
+        #[arg(short = 'T', long)]
+        show_total_time: bool,
+        /// Also write every result, along with the CPU and the git revision the runner was
+        /// built from, to this file as JSON (e.g. `profile.json`).
+        #[arg(long, value_name = "PATH")]
+        export: Option<PathBuf>,
+        /// Don't run anything, but show the times of two exported files side by side, e.g.
+        /// from different machines. `run-all --summary` files work too.
+        #[arg(
+            long,
+            num_args = 2,
+            value_names = ["A", "B"],
+            conflicts_with_all = ["export", "tag"]
+        )]
+        compare_machines: Option<Vec<PathBuf>>,
+        /// With `--compare-machines`, a part that takes more than this many times as long
+        /// on B as on A counts as a regression, which makes the command fail.
+        #[arg(long, default_value_t = 1.1, requires = "compare_machines")]
+        max_slowdown: f64,
+        #[command(flatten)]
+        opts: RunOptions,
+    },
+    /// Run a day on an input too large to read into memory, one line at a time. Only some
+    /// days support this.
+    Stream {
+        day: usize,
+        /// The input file, or `-` for stdin.
+        file: PathBuf,
+        /// Only run this part. Required when reading from stdin.
+        #[arg(long, value_parser = clap::value_parser!(u8).range(1..=2))]
+        part: Option<u8>,
+        #[arg(short = 't', long)]
+        show_time: bool,
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+    /// Print additional information about how a day's answer was found.
+    Details {
+        day: usize,
+        #[command(flatten)]
+        input: InputArgs,
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+    /// Run both parts of a day on two inputs and show the answers side by side, e.g. to
+    /// find out why a solver works on the example but not on the real input. Some days
+    /// also compare what the inputs consist of.
+    Diff {
+        day: usize,
+        a: PathBuf,
+        b: PathBuf,
+        /// Give up on a run if it hasn't finished after this long (e.g. `10s`).
+        #[arg(long, value_parser = humantime::parse_duration)]
+        timeout: Option<Duration>,
+    },
+    /// Draw a day's input along with what its solution found out about it. Only some days
+    /// support this.
+    Viz {
+        day: usize,
+        #[command(flatten)]
+        input: InputArgs,
+        /// Save the picture as a PNG.
+        #[arg(long, required_unless_present = "ansi", conflicts_with = "ansi")]
+        output: Option<PathBuf>,
+        /// Print the picture to the terminal.
+        #[arg(long)]
+        ansi: bool,
+        /// Size of the square every cell gets drawn as in the PNG, in pixels.
+        #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u16).range(1..))]
+        scale: u16,
+    },
+    /// Run a part over and over for a while under a sampling profiler, and save a
+    /// flamegraph of where the time went.
+    #[cfg(feature = "profile")]
+    Profile {
+        #[arg(value_parser = clap::value_parser!(u32).range(1..=25))]
+        day: u32,
+        #[arg(value_parser = clap::value_parser!(u8).range(1..=2))]
+        part: u8,
+        #[command(flatten)]
+        input: InputArgs,
+        /// How long to keep running the part (e.g. `10s`).
+        #[arg(long, default_value = "5s", value_parser = humantime::parse_duration)]
+        duration: Duration,
+        /// How many times a second to sample the stack.
+        #[arg(long, default_value_t = 1000)]
+        frequency: i32,
+        /// Where to save the flamegraph SVG.
+        #[arg(long, default_value = "flamegraph.svg")]
+        output: PathBuf,
+    },
+    /// Answer part 1 of day 2 for a bag holding other numbers of cubes than the puzzle's
+    /// 12 red, 13 green and 14 blue ones.
+    #[cfg(feature = "day2")]
+    Cubes {
+        #[command(flatten)]
+        input: InputArgs,
+        #[arg(long, default_value_t = 12)]
+        red: usize,
+        #[arg(long, default_value_t = 13)]
+        green: usize,
+        #[arg(long, default_value_t = 14)]
+        blue: usize,
+    },
+    /// Print a completion script for the given shell.
+    Completions { shell: clap_complete::Shell },
+    /// Open a dashboard to run days and look at their results interactively.
+    Tui {
+        /// Give up on a run if it hasn't finished after this long (e.g. `10s`).
+        #[arg(long, value_parser = humantime::parse_duration)]
+        timeout: Option<Duration>,
+    },
+    /// Open a window to run the solvers on pasted inputs.
+    #[cfg(feature = "gui")]
+    Gui {
+        /// Give up on a run if it hasn't finished after this long (e.g. `10s`).
+        #[arg(long, value_parser = humantime::parse_duration)]
+        timeout: Option<Duration>,
+    },
+    /// Serve the solvers as a JSON API on localhost: `POST /solve/{day}/{part}` with the
+    /// input as the body returns the answer and how long solving took.
+    Serve {
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+        /// Give up on a run if it hasn't finished after this long (e.g. `10s`).
+        #[arg(long, value_parser = humantime::parse_duration)]
+        timeout: Option<Duration>,
+    },
+    /// Run both parts of a day, and run them again whenever the input or the runner
+    /// executable changes, e.g. after rebuilding it with `cargo build` in another terminal.
+    Watch {
+        day: usize,
+        /// Give up on a run if it hasn't finished after this long (e.g. `10s`).
+        #[arg(long, value_parser = humantime::parse_duration)]
+        timeout: Option<Duration>,
+        /// Number of measured runs per part.
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+        runs: u32,
+    },
+    /// Create the solver stub, input file and example input file of a day, skipping the
+    /// input files that already exist. All 25 days of 2023 have a solver already, so this
+    /// fails unless the day's stub has been deleted to start it over.
+    NewDay {
+        #[arg(value_parser = clap::value_parser!(u32).range(1..=25))]
+        day: u32,
+        /// The crate's source directory, where the stub goes.
+        #[arg(long, default_value = "src")]
+        src: PathBuf,
+    },
+    /// Create a crate for another year's puzzles, with a runner, a stub for every day and
+    /// empty input and example files.
+    NewYear {
+        #[arg(value_parser = clap::value_parser!(u16).range(2015..))]
+        year: u16,
+        /// Where to create the crate. Defaults to `aoc{year}` in the working directory.
+        #[arg(long)]
+        dir: Option<PathBuf>,
+    },
+    /// Download the inputs of all days that don't have one yet, leaving a couple of
+    /// seconds between downloads. Needs the session cookie of adventofcode.com in
+    /// `$AOC_SESSION` or as `session` in the config file.
+    Init,
+    /// Show which inputs exist, and the latest answer and time of every part.
+    Status,
+    /// List the days that are compiled in, with their titles, tags and the variants of
+    /// their parts.
+    #[clap(alias = "list")]
+    Days {
+        /// Only list the days with this tag, e.g. `graph`.
+        #[arg(long)]
+        tag: Option<String>,
+    },
+    /// Delete what the runner remembers between runs.
+    Clean {
+        /// What to delete. Without any, everything gets deleted.
+        #[arg(value_enum)]
+        files: Vec<StateFile>,
+        /// Only print what would be deleted.
+        #[arg(short = 'n', long)]
+        dry_run: bool,
     },
 }
 
-fn run_part(
+/// How long to wait for a timed out solver to notice that it got cancelled, before
+/// giving up on it.
+const CANCEL_GRACE: Duration = Duration::from_secs(1);
+
+/// Extra runs of a solver before the measured ones, whose timings get discarded: `runs`
+/// of them, and then more until `time` has passed since the first one started.
+#[derive(Debug, Clone, Copy, Default)]
+struct Warmup {
+    runs: u32,
+    time: Duration,
+}
+
+impl Warmup {
+    fn done(&self, runs: u32, start: Instant) -> bool {
+        runs >= self.runs && start.elapsed() >= self.time
+    }
+}
+
+/// Runs a solver `warmup`'s runs and then `runs` more times, returning what each of the
+/// latter returned along with how long it took. The solver gets a token to check for
+/// cancellation. With a timeout, the solver runs on a worker thread and `None` is returned
+/// if any run doesn't finish in time. The run then gets cancelled, but only the days with
+/// long loops check for that, so a solver that doesn't stop within `CANCEL_GRACE` just
+/// gets detached and keeps running until it finishes or the process exits. Cancelling
+/// `cancel` stops the solver with an error, as does the solver failing on the input or
+/// panicking.
+fn solve<T: Send + 'static>(
+    f: impl Fn(&str, &cancel::Token) -> anyhow::Result<T> + Send + 'static,
+    input: &str,
+    warmup: Warmup,
+    runs: u32,
+    timeout: Option<Duration>,
+    cancel: &cancel::Token,
+) -> anyhow::Result<Option<Vec<(T, Duration)>>> {
+    let mut samples = Vec::with_capacity(runs as usize);
+
+    let Some(timeout) = timeout else {
+        let start = Instant::now();
+        let mut warmups = 0;
+        while samples.len() < runs as usize {
+            let now = Instant::now();
+            let output = panics::catch(|| f(input, cancel))?;
+            if cancel.is_cancelled() {
+                anyhow::bail!("Cancelled.");
+            }
+            let output = output?;
+            if warmup.done(warmups, start) {
+                samples.push((output, now.elapsed()));
+            } else {
+                warmups += 1;
+            }
+        }
+        return Ok(Some(samples));
+    };
+
+    let input = input.to_owned();
+    let token = cancel.child();
+    let worker_token = token.clone();
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let start = Instant::now();
+        let (mut warmups, mut measured) = (0, 0);
+        while measured < runs {
+            let warming_up = !warmup.done(warmups, start);
+            let now = Instant::now();
+            let output = match panics::catch(|| f(&input, &worker_token)) {
+                // Either the run timed out and nobody is waiting for it anymore, or
+                // `cancel` was cancelled, which the receiver finds out about by itself.
+                _ if worker_token.is_cancelled() => break,
+                Ok(output) => output,
+                Err(e) => Err(e),
+            };
+            let elapsed = now.elapsed();
+            let failed = output.is_err();
+            // The receiver is gone if we already timed out, in which case nobody cares about the result.
+            if tx.send((output, elapsed, warming_up)).is_err() || failed {
+                break;
+            }
+            if warming_up {
+                warmups += 1;
+            } else {
+                measured += 1;
+            }
+        }
+    });
+
+    // Every run gets the full timeout, warmups included.
+    while samples.len() < runs as usize {
+        match rx.recv_timeout(timeout) {
+            Ok((output, _, true)) => drop(output?),
+            Ok((output, elapsed, false)) => samples.push((output?, elapsed)),
+            Err(RecvTimeoutError::Timeout) => {
+                token.cancel();
+                // Wait for the worker to stop, which drops the sender.
+                while rx.recv_timeout(CANCEL_GRACE).is_ok() {}
+                return Ok(None);
+            }
+            Err(RecvTimeoutError::Disconnected) if cancel.is_cancelled() => {
+                anyhow::bail!("Cancelled.")
+            }
+            Err(RecvTimeoutError::Disconnected) => anyhow::bail!("Solver panicked."),
+        }
+    }
+    Ok(Some(samples))
+}
+
+/// Writes what `day` parses `input` into to `path`, as JSON.
+fn dump_parsed(day: usize, input: &str, path: &Path) -> anyhow::Result<()> {
+    let Some(&(_, dump)) = DUMPS.iter().find(|&&(d, _)| d == day) else {
+        anyhow::bail!("day {} can't dump its parsed input", day);
+    };
+    let json = serde_json::to_string_pretty(&dump(input.as_bytes())?)?;
+    fs::write(path, json + "\n").with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Reads and preprocesses the input once and runs each of `parts` on it. Stdin can only
+/// be read once, so the parts of a day must not read the input separately. The input's
+/// hash is that of the preprocessed text, so e.g. changing its line endings doesn't count
+/// as changing it. When `opts.share_parse` is set and `parts` are both parts, the input
+/// only gets parsed once for both of them, unless the memory of each part is measured
+/// (with `opts.show_memory` or the `count-allocs` feature). With `opts.dump_parsed` set,
+/// the parsed input also gets written there. With `opts.use_cache`, parts whose recorded
+/// results are all solved for the same input come back as those, marked as cached, without
+/// running. `progress` gets advanced by one for every part. Days that aren't compiled in
+/// come back missing without reading the input.
+fn run_parts(
+    day: usize,
+    parts: &[usize],
+    source: InputSource,
+    opts: &RunOptions,
+    provenance: &mut Provenance,
+    history: &mut History,
+    progress: &ProgressBar,
+) -> Vec<PartResult> {
+    if aoc2023::solver(day).is_none() {
+        progress.inc(parts.len() as u64);
+        return parts
+            .iter()
+            .map(|&part| missing(day, part, unavailable(day)))
+            .collect();
+    }
+    match source.read() {
+        Ok(input) => {
+            let text = match aoc2023::preprocess(day, &input.text) {
+                Ok(text) => text,
+                Err(e) => {
+                    progress.inc(parts.len() as u64);
+                    return parts
+                        .iter()
+                        .map(|&part| rejected(day, part, &e, &input.text))
+                        .collect();
+                }
+            };
+            if let Some(path) = &opts.dump_parsed {
+                if let Err(e) = dump_parsed(day, &text, path) {
+                    progress
+                        .suspend(|| eprintln!("Warning: couldn't dump the parsed input: {:#}", e));
+                }
+            }
+            let hash = provenance::hash(&text);
+            if let Some(file) = &input.file {
+                if let Some(old) = provenance.record(file, &hash) {
+                    progress.suspend(|| {
+                        eprintln!(
+                            "Warning: {} changed since it was last run (hash {:.12} -> {:.12}).",
+                            file.display(),
+                            old,
+                            hash
+                        )
+                    });
+                }
+            }
+            if opts.use_cache {
+                let cached: Option<Vec<_>> = parts
+                    .iter()
+                    .map(|&part| {
+                        let result = history.latest(day, part)?;
+                        let solved = matches!(result.outcome, Outcome::Solved { .. });
+                        (solved && result.input_hash.as_ref() == Some(&hash)).then(|| PartResult {
+                            cached: true,
+                            ..result.clone()
+                        })
+                    })
+                    .collect();
+                if let Some(results) = cached {
+                    progress.inc(parts.len() as u64);
+                    return results;
+                }
+            }
+            let per_part_memory = opts.show_memory || memory::COUNTS_ALLOCATIONS;
+            // The wide variants parse on their own.
+            let wide = opts.wide && parts.iter().any(|&part| wide_variant(day, part).is_some());
+            let results = if opts.share_parse && !per_part_memory && !wide && parts == [1, 2] {
+                progress.set_message(format!("day {}", day));
+                run_both(day, &text, opts)
+            } else {
+                parts
+                    .iter()
+                    .map(|&part| {
+                        progress.set_message(format!("day {} part {}", day, part));
+                        run_part(day, part, &text, opts)
+                    })
+                    .collect()
+            };
+            results
+                .into_iter()
+                .map(|result| {
+                    progress.inc(1);
+                    let mut result = PartResult {
+                        input_hash: Some(hash.clone()),
+                        ..result
+                    };
+                    if input.file.is_some() {
+                        history.record(result.clone());
+                    }
+                    if opts.explain && matches!(result.outcome, Outcome::Solved { .. }) {
+                        result.explanation = explanation(day, result.part, &text);
+                    }
+                    result
+                })
+                .collect()
+        }
+        Err(e) => parts
+            .iter()
+            .map(|&part| {
+                progress.inc(1);
+                missing(
+                    day,
+                    part,
+                    format!("Input for this day isn't available: {}", e),
+                )
+            })
+            .collect(),
+    }
+}
+
+/// The result of a part that couldn't run at all, because of `error`.
+fn missing(day: usize, part: usize, error: String) -> PartResult {
+    PartResult {
+        day,
+        part,
+        input: None,
+        variant: None,
+        algorithm: None,
+        input_hash: None,
+        outcome: Outcome::Missing { error },
+        explanation: Vec::new(),
+        cached: false,
+    }
+}
+
+/// Why there's no solver for `day`.
+fn unavailable(day: usize) -> String {
+    if aoc2023::is_day(day) {
+        format!(
+            "Day {0} isn't compiled in, it needs the `day{0}` feature.",
+            day
+        )
+    } else {
+        format!("There is no day {}.", day)
+    }
+}
+
+/// The steps that `day`'s solver explains the answer of `part` with, from running it once
+/// more on `input`. The part already got solved, so failing this time just means no steps.
+fn explanation(day: usize, part: usize, input: &str) -> Vec<String> {
+    let solver = aoc2023::solver(day).expect("the part got solved");
+    let never = cancel::Token::default();
+    panics::catch(|| explain::collect(|| solver.part_timed(part, input.as_bytes(), &never)))
+        .map(|(_, steps)| steps)
+        .unwrap_or_default()
+}
+
+/// The outcome of a part that failed with `e` on `input`. Parse errors that know their
+/// line get it quoted from the input, with a caret under the column if that's known too.
+fn failed(e: &anyhow::Error, input: &str) -> Outcome {
+    let excerpt = e.downcast_ref::<ParseError>().and_then(|e| {
+        let number = e.line?;
+        let line = input.lines().nth(number.checked_sub(1)?)?;
+        let gutter = " ".repeat(number.to_string().len());
+        let mut excerpt = format!("{} | {}", number, line);
+        if let Some(column) = e.column {
+            excerpt += &format!("\n{} | {:>2$}", gutter, "^", column);
+        }
+        Some(excerpt)
+    });
+    Outcome::Failed {
+        error: format!("{:#}", e),
+        excerpt,
+    }
+}
+
+/// The result of a part that didn't run because [`aoc2023::preprocess`] rejected its
+/// input.
+fn rejected(day: usize, part: usize, e: &anyhow::Error, input: &str) -> PartResult {
+    let mut outcome = failed(e, input);
+    if let Outcome::Failed { error, .. } = &mut outcome {
+        error.insert_str(0, "Input rejected: ");
+    }
+    PartResult {
+        day,
+        part,
+        input: None,
+        variant: None,
+        algorithm: None,
+        input_hash: None,
+        outcome,
+        explanation: Vec::new(),
+        cached: false,
+    }
+}
+
+/// The variant of `part` of `day` that computes with big integers, if it has one.
+fn wide_variant(day: usize, part: usize) -> Option<&'static aoc2023::Variant> {
+    aoc2023::day_info(day)?
+        .variants
+        .iter()
+        .find(|v| v.part == part && v.name == "wide")
+}
+
+/// Runs one part on an input that already went through [`aoc2023::preprocess`]. With
+/// `opts.wide`, parts that have a wide variant run that instead.
+fn run_part(day: usize, part: usize, input: &str, opts: &RunOptions) -> PartResult {
+    let Some(solver) = aoc2023::solver(day) else {
+        return missing(day, part, unavailable(day));
+    };
+    if let Some(variant) = wide_variant(day, part).filter(|_| opts.wide) {
+        let f = variant.solve;
+        return PartResult {
+            variant: Some(variant.name.to_string()),
+            ..run_solver(
+                day,
+                part,
+                move |input, _| Ok((f(input)?, None)),
+                input,
+                opts,
+            )
+        };
+    }
+    let result = run_solver(
+        day,
+        part,
+        move |input, cancel| {
+            let (answer, parse_time) = solver.part_timed(part, input.as_bytes(), cancel)?;
+            Ok((answer, Some(parse_time)))
+        },
+        input,
+        opts,
+    );
+    PartResult {
+        algorithm: Some(solver.algorithm().to_string()),
+        ..result
+    }
+}
+
+/// Runs both parts on an input that already went through [`aoc2023::preprocess`],
+/// parsing it only once. Part 1 gets timed including the parsing, part 2 without it, so
+/// that the two add up to how long the day took.
+fn run_both(day: usize, input: &str, opts: &RunOptions) -> Vec<PartResult> {
+    let solver = aoc2023::solver(day).expect("run_parts checked that the day exists");
+    let result = solve(
+        move |input, cancel| solver.both_timed(input.as_bytes(), cancel),
+        input,
+        opts.warmup(),
+        opts.runs,
+        opts.timeout,
+        &opts.cancel,
+    );
+    let outcomes = match result {
+        Err(e) => [(); 2].map(|_| failed(&e, input)),
+        Ok(Some(samples)) => {
+            let parse_times: Vec<_> = samples.iter().map(|((parse, _), _)| *parse).collect();
+            let (_, last) = &samples.last().expect("at least one run").0;
+            [0, 1].map(|i| match &last[i] {
+                Err(e) => failed(e, input),
+                Ok((answer, _)) => {
+                    let times = samples
+                        .iter()
+                        .filter_map(|((parse, answers), _)| {
+                            let (_, time) = answers[i].as_ref().ok()?;
+                            Some(if i == 0 { *parse + *time } else { *time })
+                        })
+                        .collect();
+                    let parse_times = (i == 0).then(|| parse_times.clone());
+                    Outcome::Solved {
+                        answer: answer.to_string(),
+                        timing: Timing {
+                            parse_shared: i == 1,
+                            ..timing(times, parse_times, opts)
+                        },
+                    }
+                }
+            })
+        }
+        Ok(None) => [(); 2].map(|_| Outcome::TimedOut {
+            timeout_ns: output::nanos(opts.timeout.unwrap()),
+        }),
+    };
+    let algorithm = solver.algorithm();
+    (1..=2)
+        .zip(outcomes)
+        .map(|(part, outcome)| PartResult {
+            day,
+            part,
+            input: None,
+            variant: None,
+            algorithm: Some(algorithm.to_string()),
+            input_hash: None,
+            outcome,
+            explanation: Vec::new(),
+            cached: false,
+        })
+        .collect()
+}
+
+/// The timing of a part from how long its runs took, and how long parsing took in each of
+/// them if that's known.
+fn timing(times: Vec<Duration>, parse_times: Option<Vec<Duration>>, opts: &RunOptions) -> Timing {
+    let stats = Stats::from_samples(times, opts.reject_outliers);
+    let parse_stats = parse_times.map(|t| Stats::from_samples(t, opts.reject_outliers));
+    Timing {
+        parse_median_ns: parse_stats.map(|stats| output::nanos(stats.median)),
+        ..Timing::from(&stats)
+    }
+}
+
+/// Like [`run_part`], but with `f` instead of the part's default solver. `f` returns how
+/// long parsing took along with the answer, if it parses separately from solving.
+fn run_solver(
     day: usize,
     part: usize,
-    input: Option<String>,
-    show_time: bool,
-    acc: Option<&mut Duration>,
-) -> anyhow::Result<()> {
-    let input = match input {
-        Some(input) => input,
-        None => std::fs::read_to_string(format!("input/day{}.txt", day))
-            .context("Input for this day isn't available.")?,
+    f: impl Fn(&str, &cancel::Token) -> anyhow::Result<(Answer, Option<Duration>)> + Send + 'static,
+    input: &str,
+    opts: &RunOptions,
+) -> PartResult {
+    let result = solve(
+        move |input, cancel| {
+            let (output, usage) = memory::measure(|| f(input, cancel));
+            output.map(|(answer, parse_time)| (answer, parse_time, usage))
+        },
+        input,
+        opts.warmup(),
+        opts.runs,
+        opts.timeout,
+        &opts.cancel,
+    );
+    let outcome = match result {
+        Err(e) => failed(&e, input),
+        Ok(Some(samples)) => {
+            let ((answer, _, _), _) = samples.last().expect("at least one run");
+            let answer = answer.to_string();
+            let parse_times = samples.iter().map(|&((_, parse, _), _)| parse).collect();
+            // Runs that allocate differently (e.g. because of a cache warmed up by an
+            // earlier run) report the most any of them allocated.
+            let usage = |field: fn(&memory::Usage) -> usize| {
+                samples
+                    .iter()
+                    .map(|((_, _, usage), _)| field(usage) as u64)
+                    .max()
+            };
+            let counts = memory::COUNTS_ALLOCATIONS;
+            let timing = Timing {
+                peak_memory_bytes: usage(|u| u.peak).filter(|_| opts.show_memory),
+                allocations: usage(|u| u.allocations).filter(|_| counts),
+                allocated_bytes: usage(|u| u.allocated_bytes).filter(|_| counts),
+                ..timing(
+                    samples.iter().map(|&(_, time)| time).collect(),
+                    parse_times,
+                    opts,
+                )
+            };
+            Outcome::Solved { answer, timing }
+        }
+        Ok(None) => Outcome::TimedOut {
+            timeout_ns: output::nanos(opts.timeout.unwrap()),
+        },
     };
-    let fns = &FNS[day - 1];
-    let now = Instant::now();
-    let output = fns[part - 1](&input);
-    let elapsed = now.elapsed();
-    println!("===== Day {} Part {} =====", day, part);
-    println!("{}", output);
-    if show_time {
-        println!("Finished in: {:.3?}", elapsed);
+    PartResult {
+        day,
+        part,
+        input: None,
+        variant: None,
+        algorithm: None,
+        input_hash: None,
+        outcome,
+        explanation: Vec::new(),
+        cached: false,
     }
-    if let Some(acc) = acc {
-        *acc += elapsed;
+}
+
+/// Runs both parts of a day in a subprocess, which reports back through the JSON output
+/// of `run-day`.
+fn bench_day(exe: &Path, day: usize, input_dir: &Path, opts: &RunOptions) -> Vec<PartResult> {
+    let failed = |error: String| {
+        (1..=2)
+            .map(|part| PartResult {
+                day,
+                part,
+                input: None,
+                variant: None,
+                algorithm: None,
+                input_hash: None,
+                outcome: Outcome::Failed {
+                    error: error.clone(),
+                    excerpt: None,
+                },
+                explanation: Vec::new(),
+                cached: false,
+            })
+            .collect()
+    };
+    let mut cmd = Command::new(exe);
+    cmd.args(["run-day", &day.to_string(), "--format", "json"])
+        .arg("--input-dir")
+        .arg(input_dir)
+        .args(["--runs", &opts.runs.to_string()])
+        .args(["--warmup", &opts.warmup.to_string()])
+        .args(["--warmup-ms", &opts.warmup_ms.to_string()])
+        // Don't let the subprocess fall back to reading our stdin.
+        .stdin(Stdio::null());
+    if let Some(timeout) = opts.timeout {
+        cmd.args([
+            "--timeout",
+            &humantime::format_duration(timeout).to_string(),
+        ]);
+    }
+    if opts.reject_outliers {
+        cmd.arg("--reject-outliers");
+    }
+    if opts.explain {
+        cmd.arg("--explain");
+    }
+    if opts.wide {
+        cmd.arg("--wide");
+    }
+    if opts.show_memory {
+        cmd.arg("--show-memory");
+    }
+    if let Some(core) = opts.pin_core {
+        cmd.args(["--pin-core", &core.to_string()]);
+    }
+    if let Some(threads) = opts.threads {
+        cmd.args(["--threads", &threads.to_string()]);
+    }
+
+    let output = match cmd.output() {
+        Ok(output) => output,
+        Err(e) => return failed(format!("Couldn't start the subprocess: {}", e)),
+    };
+    // A failing part still produces a report, only a crash doesn't.
+    match serde_json::from_slice::<Report>(&output.stdout) {
+        Ok(report) => {
+            // Pass on warnings, e.g. about changed inputs.
+            eprint!("{}", String::from_utf8_lossy(&output.stderr));
+            report.results
+        }
+        Err(_) => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let reason = stderr.lines().find(|l| !l.trim().is_empty()).unwrap_or("");
+            failed(format!("Subprocess failed ({}): {}", output.status, reason))
+        }
+    }
+}
+
+/// Runs both parts of `day` on the inputs at `paths` and prints their answers side by
+/// side, followed by the inputs' shapes if the day can count them. Rows that differ get
+/// marked with a `*`.
+fn diff(day: usize, paths: [&Path; 2], timeout: Option<Duration>) -> anyhow::Result<ExitCode> {
+    if aoc2023::solver(day).is_none() {
+        anyhow::bail!("{}", unavailable(day));
+    }
+    let read = |path: &Path| -> anyhow::Result<String> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Couldn't read {}.", path.display()))?;
+        let text = aoc2023::preprocess(day, &text)
+            .with_context(|| format!("{} was rejected.", path.display()))?;
+        Ok(text.into_owned())
+    };
+    let texts = [read(paths[0])?, read(paths[1])?];
+    let opts = RunOptions::interactive(timeout);
+    let mut all_solved = true;
+    let mut rows = vec![(String::new(), paths.map(|p| p.display().to_string()))];
+    for part in 1..=2 {
+        let cells = texts.each_ref().map(|text| {
+            let outcome = run_part(day, part, text, &opts).outcome;
+            all_solved &= matches!(outcome, Outcome::Solved { .. });
+            match outcome {
+                Outcome::Solved { answer, .. } => answer,
+                Outcome::Failed { error, .. } | Outcome::Missing { error } => error,
+                outcome => outcome.label().to_string(),
+            }
+        });
+        rows.push((format!("part {}", part), cells));
+    }
+    if let Some(&(_, shape)) = SHAPES.iter().find(|&&(d, _)| d == day) {
+        // Inputs that don't parse already failed above.
+        if let [Ok(a), Ok(b)] = texts.each_ref().map(|text| shape(text)) {
+            for (&(name, x), &(_, y)) in a.iter().zip(&b) {
+                rows.push((name.to_string(), [x.to_string(), y.to_string()]));
+            }
+        }
+    }
+    let label_width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+    let a_width = rows.iter().map(|(_, [a, _])| a.len()).max().unwrap_or(0);
+    for (i, (label, [a, b])) in rows.iter().enumerate() {
+        let mark = if i > 0 && a != b { '*' } else { ' ' };
+        println!("{} {:label_width$}  {:a_width$}  {}", mark, label, a, b);
+    }
+    Ok(if all_solved {
+        Exit::Success
+    } else {
+        Exit::Failed
+    }
+    .into())
+}
+
+/// Prints the median time of every part in two exported profiles side by side, with how
+/// long the second machine took relative to the first. Only parts that got solved count.
+/// Parts that took more than `max_slowdown` times as long on the second machine are
+/// regressions.
+fn compare_machines(paths: [&Path; 2], max_slowdown: f64) -> anyhow::Result<ExitCode> {
+    let read = |path: &Path| -> anyhow::Result<output::RunSummary> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Couldn't read {}.", path.display()))?;
+        serde_json::from_str(&text)
+            .with_context(|| format!("{} isn't an exported profile.", path.display()))
+    };
+    let profiles = [read(paths[0])?, read(paths[1])?];
+    for ((name, path), profile) in ["A", "B"].iter().zip(paths).zip(&profiles) {
+        let mut machine = vec![profile.cpu.as_deref().unwrap_or(&profile.target)];
+        if let Some(revision) = &profile.git_revision {
+            machine.push(&revision[..revision.len().min(10)]);
+        }
+        println!("{}: {} ({})", name, path.display(), machine.join(", "));
+    }
+    let medians = profiles.each_ref().map(|profile| {
+        profile
+            .report
+            .results
+            .iter()
+            .filter_map(|r| match &r.outcome {
+                Outcome::Solved { timing, .. } => {
+                    Some(((r.day, r.part), Duration::from_nanos(timing.median_ns)))
+                }
+                _ => None,
+            })
+            .collect::<BTreeMap<_, _>>()
+    });
+    let parts: BTreeSet<_> = medians.iter().flat_map(|m| m.keys().copied()).collect();
+    let ratio = |a: Duration, b: Duration| match a.is_zero() {
+        true => "-".to_string(),
+        false => format!("{:.2}x", b.as_secs_f64() / a.as_secs_f64()),
+    };
+    let mut rows = vec![["Day", "Part", "A", "B", "B/A"].map(String::from)];
+    let mut totals = [Duration::ZERO; 2];
+    let mut regressions = Vec::new();
+    for (day, part) in parts {
+        let [a, b] = medians.each_ref().map(|m| m.get(&(day, part)).copied());
+        let time = |t: Option<Duration>| t.map_or("-".to_string(), |t| format!("{:.3?}", t));
+        let relative = match (a, b) {
+            (Some(a), Some(b)) => {
+                totals[0] += a;
+                totals[1] += b;
+                if b.as_secs_f64() > a.as_secs_f64() * max_slowdown {
+                    regressions.push(format!("{}.{}", day, part));
+                }
+                ratio(a, b)
+            }
+            _ => "-".to_string(),
+        };
+        rows.push([
+            day.to_string(),
+            part.to_string(),
+            time(a),
+            time(b),
+            relative,
+        ]);
     }
-    Ok(())
+    rows.push([
+        "Total".to_string(),
+        String::new(),
+        format!("{:.3?}", totals[0]),
+        format!("{:.3?}", totals[1]),
+        ratio(totals[0], totals[1]),
+    ]);
+    let widths: [usize; 5] = std::array::from_fn(|i| {
+        rows.iter()
+            .map(|row| row[i].chars().count())
+            .max()
+            .unwrap_or(0)
+    });
+    for [day, part, a, b, relative] in &rows {
+        println!(
+            "{:<w0$}  {:>w1$}  {:>w2$}  {:>w3$}  {:>w4$}",
+            day,
+            part,
+            a,
+            b,
+            relative,
+            w0 = widths[0],
+            w1 = widths[1],
+            w2 = widths[2],
+            w3 = widths[3],
+            w4 = widths[4],
+        );
+    }
+    if !regressions.is_empty() {
+        eprintln!(
+            "Error: these parts took more than {:.2}x as long on B: {}.",
+            max_slowdown,
+            regressions.join(", ")
+        );
+        return Ok(Exit::Regression.into());
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn has_tag(day: usize, tag: &str) -> bool {
+    aoc2023::day_info(day).is_some_and(|info| info.tags.contains(&tag))
+}
+
+/// An error for a tag that none of `days` have, listing the tags of the days that are
+/// compiled in.
+fn unknown_tag(tag: &str, days: &str) -> anyhow::Error {
+    let tags: BTreeSet<_> = aoc2023::days()
+        .flat_map(|info| info.tags)
+        .copied()
+        .collect();
+    let tags: Vec<_> = tags.into_iter().collect();
+    anyhow::anyhow!(
+        "None of {} have the tag {:?}, the tags are {}.",
+        days,
+        tag,
+        tags.join(", ")
+    )
 }
 
-fn main() -> anyhow::Result<()> {
-    let args = Args::parse();
-    match args {
+fn list_days(tag: Option<&str>) -> anyhow::Result<ExitCode> {
+    let days: Vec<_> = aoc2023::days()
+        .filter(|info| tag.is_none_or(|tag| info.tags.contains(&tag)))
+        .collect();
+    if let (Some(tag), []) = (tag, &days[..]) {
+        return Err(unknown_tag(tag, "the days"));
+    }
+    for info in days {
+        println!(
+            "{:>3}  {}  [{}]",
+            info.day,
+            info.title,
+            info.tags.join(", ")
+        );
+        for variant in info.variants {
+            println!("       part {} variant {}", variant.part, variant.name);
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn clean(mut files: Vec<StateFile>, dry_run: bool) -> anyhow::Result<ExitCode> {
+    if files.is_empty() {
+        files = StateFile::value_variants().to_vec();
+    }
+    for file in files {
+        let path = file.path();
+        if !path.exists() {
+            continue;
+        }
+        if dry_run {
+            println!("Would delete {}", path.display());
+        } else {
+            let removed = if path.is_dir() {
+                fs::remove_dir_all(&path)
+            } else {
+                fs::remove_file(&path)
+            };
+            removed.with_context(|| format!("failed to delete {}", path.display()))?;
+            println!("Deleted {}", path.display());
+        }
+    }
+    if !dry_run {
+        // Only succeeds if nothing else is left in there.
+        let _ = fs::remove_dir(paths::state_dir());
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn main() -> anyhow::Result<ExitCode> {
+    let mut cli = Cli::parse();
+    panics::install_hook();
+    let config = Config::load()?;
+    let input_dir = paths::input_dir(cli.input_dir, config.input_dir);
+    let color = output::use_color(cli.no_color);
+    let _trace_guard = logging::init(cli.verbose, cli.chrome_trace.as_deref(), color);
+    let threads = cli.threads.or(config.threads);
+    #[cfg(feature = "parallel")]
+    if let Some(threads) = threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .context("Couldn't start the solver threads.")?;
+    }
+    if let Args::RunPart { opts, .. }
+    | Args::RunDay { opts, .. }
+    | Args::Run { opts, .. }
+    | Args::RunAll { opts, .. }
+    | Args::BenchAll { opts, .. } = &mut cli.command
+    {
+        opts.show_time = opts.show_time.or(config.show_time);
+        opts.threads = threads;
+        if opts.show_memory || memory::COUNTS_ALLOCATIONS {
+            memory::enable();
+        }
+        // Threads inherit this, including the ones the solvers run on.
+        if let Some(core) = opts.pin_core {
+            affinity::pin_to_core(core)?;
+        }
+    }
+    if let Args::RunAll {
+        days,
+        tag: Some(tag),
+        ..
+    }
+    | Args::BenchAll {
+        days,
+        tag: Some(tag),
+        ..
+    } = &mut cli.command
+    {
+        days.retain(|&day| has_tag(day, tag));
+        if days.days().is_empty() {
+            return Err(unknown_tag(tag, "the selected days"));
+        }
+    }
+    if let Args::RunPart {
+        repeat: Some(runs),
+        opts,
+        ..
+    } = &mut cli.command
+    {
+        opts.runs = *runs;
+        opts.warmup = 1;
+    }
+    let mut provenance = Provenance::load();
+    let mut history = History::load();
+    let exit_code = match cli.command {
         Args::RunPart {
             day,
             part,
             input,
-            show_time,
-        } => run_part(day, part, input, show_time, None),
+            all_variants,
+            plugins,
+            opts,
+            ..
+        } if all_variants || !plugins.is_empty() => {
+            let variants: Vec<_> = aoc2023::day_info(day)
+                .filter(|_| all_variants)
+                .into_iter()
+                .flat_map(|info| info.variants)
+                .filter(|v| v.part == part)
+                .map(|v| (v.name, v.solve))
+                .collect();
+            if variants.is_empty() && plugins.is_empty() {
+                anyhow::bail!("Day {} part {} doesn't have any other variants.", day, part);
+            }
+            let plugins = plugins
+                .iter()
+                .map(|path| plugin::Plugin::load(path))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            let input = input
+                .source(&input_dir, day)
+                .read()
+                .context("Input for this day isn't available.")?;
+            let text = aoc2023::preprocess(day, &input.text).context("Input rejected.")?;
+            let default = PartResult {
+                variant: Some("default".to_string()),
+                ..run_part(day, part, &text, &opts)
+            };
+            let results: Vec<_> = iter::once(default)
+                .chain(variants.into_iter().map(|(name, f)| PartResult {
+                    variant: Some(name.to_string()),
+                    ..run_solver(
+                        day,
+                        part,
+                        move |input, _| Ok((f(input)?, None)),
+                        &text,
+                        &opts,
+                    )
+                }))
+                .chain(plugins.into_iter().map(|plugin| PartResult {
+                    variant: Some(plugin.name().to_string()),
+                    ..run_solver(
+                        day,
+                        part,
+                        move |input, _| Ok((plugin.solve(day, part, input)?, None)),
+                        &text,
+                        &opts,
+                    )
+                }))
+                .collect();
+            let mut answers = results.iter().filter_map(|r| match &r.outcome {
+                Outcome::Solved { answer, .. } => Some(answer),
+                _ => None,
+            });
+            let first = answers.next();
+            let agree = answers.all(|answer| Some(answer) == first);
+            let mut printer = opts.printer(color);
+            printer.batch("Variant", results);
+            let exit_code = printer.finish(false)?;
+            if agree {
+                Ok(exit_code)
+            } else {
+                eprintln!("Error: the variants don't agree on the answer.");
+                Ok(Exit::WrongAnswer.into())
+            }
+        }
+        Args::RunPart {
+            day,
+            part,
+            input,
+            inputs: None,
+            opts,
+            ..
+        } => {
+            let mut printer = opts.printer(color);
+            for result in run_parts(
+                day,
+                &[part],
+                input.source(&input_dir, day),
+                &opts,
+                &mut provenance,
+                &mut history,
+                &ProgressBar::hidden(),
+            ) {
+                printer.part(result);
+            }
+            printer.finish(false)
+        }
+        Args::RunPart {
+            day,
+            part,
+            inputs: Some(pattern),
+            opts,
+            ..
+        } => {
+            let paths = glob::glob(&pattern)
+                .context("Invalid glob pattern.")?
+                .collect::<Result<Vec<_>, _>>()?;
+            if paths.is_empty() {
+                anyhow::bail!("No files match {:?}.", pattern);
+            }
+            let mut printer = opts.printer(color);
+            let results = paths
+                .into_iter()
+                .flat_map(|path| {
+                    let name = path.display().to_string();
+                    let source = InputSource::File(path);
+                    let progress = ProgressBar::hidden();
+                    let mut results = run_parts(
+                        day,
+                        &[part],
+                        source,
+                        &opts,
+                        &mut provenance,
+                        &mut history,
+                        &progress,
+                    );
+                    for result in &mut results {
+                        result.input = Some(name.clone());
+                    }
+                    results
+                })
+                .collect();
+            printer.batch("Input", results);
+            printer.finish(false)
+        }
         Args::RunDay {
             day,
             input,
-            show_time,
             show_total_time,
+            dump_parsed,
+            opts,
         } => {
-            let mut acc = show_total_time.then_some(Duration::ZERO);
-
-            run_part(day, 1, input.clone(), show_time, acc.as_mut())?;
-            run_part(day, 2, input, show_time, acc.as_mut())?;
-
-            if let Some(acc) = acc {
-                println!("Total time: {:.3?}", acc);
+            if dump_parsed.is_some() && !DUMPS.iter().any(|&(d, _)| d == day) {
+                anyhow::bail!("Day {} can't dump its parsed input.", day);
+            }
+            let opts = RunOptions {
+                share_parse: true,
+                dump_parsed,
+                ..opts
+            };
+            let mut printer = opts.printer(color);
+            for result in run_parts(
+                day,
+                &[1, 2],
+                input.source(&input_dir, day),
+                &opts,
+                &mut provenance,
+                &mut history,
+                &ProgressBar::hidden(),
+            ) {
+                printer.part(result);
+            }
+            printer.finish(show_total_time)
+        }
+        Args::Run {
+            auto,
+            show_total_time,
+            opts,
+        } => {
+            let Some(day) = input::day_from_path(&auto) else {
+                anyhow::bail!("Couldn't find a day number in {:?}.", auto);
+            };
+            let opts = RunOptions {
+                share_parse: true,
+                ..opts
+            };
+            let mut printer = opts.printer(color);
+            let source = InputSource::File(auto);
+            let progress = ProgressBar::hidden();
+            for result in run_parts(
+                day,
+                &[1, 2],
+                source,
+                &opts,
+                &mut provenance,
+                &mut history,
+                &progress,
+            ) {
+                printer.part(result);
             }
-            Ok(())
+            printer.finish(show_total_time)
         }
         Args::RunAll {
-            show_time,
+            days,
             show_total_time,
+            budget,
+            cached,
+            summary,
+            opts,
+            ..
         } => {
-            let mut acc = show_total_time.then_some(Duration::ZERO);
-            for day in 1..=25 {
-                run_part(day, 1, None, show_time, acc.as_mut())?;
-                run_part(day, 2, None, show_time, acc.as_mut())?;
+            let opts = RunOptions {
+                use_cache: cached,
+                ..opts
+            };
+            let mut printer = opts.printer(color);
+            if let Some(path) = summary {
+                printer.summary_file(path);
             }
-
-            if let Some(acc) = acc {
-                println!("Total time: {:.3?}", acc);
+            let progress = printer.progress(days.days().len() * 2);
+            let start = Instant::now();
+            let mut completed = Vec::new();
+            for &day in days.days() {
+                if budget.is_some_and(|budget| start.elapsed() >= budget) {
+                    break;
+                }
+                let source = InputSource::File(paths::input_file(&input_dir, day));
+                for result in run_parts(
+                    day,
+                    &[1, 2],
+                    source,
+                    &opts,
+                    &mut provenance,
+                    &mut history,
+                    &progress,
+                ) {
+                    printer.part(result);
+                }
+                completed.push(day);
+            }
+            if let Some(budget) = budget {
+                printer.budget(budget, completed.len() < days.days().len(), completed);
+            }
+            printer.summary();
+            printer.finish(show_total_time)
+        }
+        Args::BenchAll {
+            compare_machines: Some(paths),
+            max_slowdown,
+            ..
+        } => compare_machines([&paths[0], &paths[1]], max_slowdown),
+        Args::BenchAll {
+            days,
+            show_total_time,
+            export,
+            opts,
+            ..
+        } => {
+            let exe = env::current_exe().context("Couldn't find the current executable.")?;
+            let mut printer = opts.printer(color);
+            if let Some(path) = export {
+                printer.summary_file(path);
+            }
+            let progress = printer.progress(days.days().len() * 2);
+            for &day in days.days() {
+                progress.set_message(format!("day {}", day));
+                for result in bench_day(&exe, day, &input_dir, &opts) {
+                    printer.part(result);
+                }
+                progress.inc(2);
+            }
+            printer.summary();
+            printer.finish(show_total_time)
+        }
+        Args::Details { day, input, format } => {
+            let Some(&(_, details)) = DETAILS.iter().find(|&&(d, _)| d == day) else {
+                anyhow::bail!("Day {} doesn't have any details.", day);
+            };
+            let input = input
+                .source(&input_dir, day)
+                .read()
+                .context("Input for this day isn't available.")?;
+            let text = aoc2023::preprocess(day, &input.text).context("Input rejected.")?;
+            println!("{}", details(&text, format)?);
+            Ok(ExitCode::SUCCESS)
+        }
+        Args::Stream {
+            day,
+            file,
+            part,
+            show_time,
+            format,
+        } => {
+            if !aoc2023::stream::days().any(|d| d == day) {
+                let days: Vec<_> = aoc2023::stream::days().map(|d| d.to_string()).collect();
+                anyhow::bail!(
+                    "Day {} can't be streamed, only days {} can.",
+                    day,
+                    days.join(", ")
+                );
+            }
+            let stdin = file.as_os_str() == "-";
+            let parts = match part {
+                Some(part) => vec![part as usize],
+                None if stdin => anyhow::bail!("Stdin can only be read once, pick a --part."),
+                None => vec![1, 2],
+            };
+            let mut printer = Printer::new(format, show_time, false, None, color);
+            for part in parts {
+                let start = Instant::now();
+                let answer = panics::catch(|| {
+                    let mut reader: Box<dyn BufRead> = if stdin {
+                        Box::new(io::stdin().lock())
+                    } else {
+                        let file = fs::File::open(&file)
+                            .with_context(|| format!("failed to open {}", file.display()))?;
+                        Box::new(io::BufReader::new(file))
+                    };
+                    aoc2023::stream::solve(day, part, &mut reader).expect("the day streams")
+                });
+                let outcome = match answer.and_then(|answer| answer) {
+                    Ok(answer) => Outcome::Solved {
+                        answer: answer.to_string(),
+                        timing: Timing::from(&Stats::from_samples(vec![start.elapsed()], false)),
+                    },
+                    Err(e) => Outcome::Failed {
+                        error: format!("{:#}", e),
+                        excerpt: None,
+                    },
+                };
+                printer.part(PartResult {
+                    day,
+                    part,
+                    input: None,
+                    variant: None,
+                    algorithm: None,
+                    input_hash: None,
+                    outcome,
+                    explanation: Vec::new(),
+                    cached: false,
+                });
+            }
+            printer.finish(false)
+        }
+        Args::Viz {
+            day,
+            input,
+            output,
+            ansi: _,
+            scale,
+        } => {
+            let Some(&(_, visualize)) = VISUALIZATIONS.iter().find(|&&(d, _)| d == day) else {
+                anyhow::bail!("Day {} can't be visualized.", day);
+            };
+            let input = input
+                .source(&input_dir, day)
+                .read()
+                .context("Input for this day isn't available.")?;
+            let text = aoc2023::preprocess(day, &input.text).context("Input rejected.")?;
+            let image = visualize(text.as_bytes())?;
+            match output {
+                Some(path) => {
+                    let file = fs::File::create(&path)
+                        .with_context(|| format!("failed to create {}", path.display()))?;
+                    image
+                        .write_png(io::BufWriter::new(file), scale.into())
+                        .with_context(|| format!("failed to write {}", path.display()))?;
+                }
+                None => print!("{}", image.to_ansi()),
             }
-            Ok(())
+            Ok(ExitCode::SUCCESS)
         }
+        #[cfg(feature = "day2")]
+        Args::Cubes {
+            input,
+            red,
+            green,
+            blue,
+        } => {
+            let input = input
+                .source(&input_dir, 2)
+                .read()
+                .context("Input for this day isn't available.")?;
+            let text = aoc2023::preprocess(2, &input.text).context("Input rejected.")?;
+            let sum = aoc2023::day2::possible_games(&text, red, green, blue)
+                .context("Input rejected.")?;
+            println!("{}", sum);
+            Ok(ExitCode::SUCCESS)
+        }
+        #[cfg(feature = "profile")]
+        Args::Profile {
+            day,
+            part,
+            input,
+            duration,
+            frequency,
+            output,
+        } => {
+            let day = day as usize;
+            let Some(solver) = aoc2023::solver(day) else {
+                anyhow::bail!(unavailable(day));
+            };
+            let input = input
+                .source(&input_dir, day)
+                .read()
+                .context("Input for this day isn't available.")?;
+            let text = aoc2023::preprocess(day, &input.text).context("Input rejected.")?;
+            let runs = profile::profile(
+                || solver.part(part.into(), &text, &cancel::Token::default()),
+                duration,
+                frequency,
+                &output,
+            )?;
+            eprintln!(
+                "Profiled {} runs of day {} part {}, saved the flamegraph to {}.",
+                runs,
+                day,
+                part,
+                output.display()
+            );
+            Ok(ExitCode::SUCCESS)
+        }
+        Args::Completions { shell } => {
+            let mut cmd = Cli::command();
+            let name = cmd.get_name().to_owned();
+            clap_complete::generate(shell, &mut cmd, name, &mut std::io::stdout());
+            Ok(ExitCode::SUCCESS)
+        }
+        Args::Diff { day, a, b, timeout } => diff(day, [&a, &b], timeout),
+        Args::Tui { timeout } => {
+            tui::run(|day, parts, cancel| {
+                let opts = RunOptions {
+                    cancel: cancel.clone(),
+                    ..RunOptions::interactive(timeout)
+                };
+                let source = InputSource::File(paths::input_file(&input_dir, day));
+                let progress = ProgressBar::hidden();
+                run_parts(
+                    day,
+                    parts,
+                    source,
+                    &opts,
+                    &mut provenance,
+                    &mut history,
+                    &progress,
+                )
+            })?;
+            Ok(ExitCode::SUCCESS)
+        }
+        #[cfg(feature = "gui")]
+        Args::Gui { timeout } => {
+            let opts = RunOptions::interactive(timeout);
+            gui::run(|day, part, input| match aoc2023::preprocess(day, input) {
+                Ok(input) => run_part(day, part, &input, &opts),
+                Err(e) => rejected(day, part, &e, input),
+            })?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Args::Serve { port, timeout } => {
+            let opts = RunOptions::interactive(timeout);
+            serve::serve(port, |day, part, input| {
+                match aoc2023::preprocess(day, input) {
+                    Ok(input) => run_part(day, part, &input, &opts),
+                    Err(e) => rejected(day, part, &e, input),
+                }
+            })?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Args::Watch { day, timeout, runs } => {
+            // Look this up before the executable gets rebuilt, after which the running
+            // process's executable no longer has a path.
+            let exe = env::current_exe().context("Couldn't find the current executable.")?;
+            let opts = RunOptions {
+                runs,
+                threads,
+                ..RunOptions::interactive(timeout)
+            };
+            let files = [paths::input_file(&input_dir, day), exe.clone()];
+            watch::watch(day, &files, || bench_day(&exe, day, &input_dir, &opts))
+        }
+        Args::NewDay { day, src } => {
+            scaffold::new_day(day as usize, &src, &input_dir)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Args::NewYear { year, dir } => {
+            let dir = dir.unwrap_or_else(|| PathBuf::from(format!("aoc{}", year)));
+            scaffold::new_year(year, &dir)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Args::Init => {
+            let client = client::Client::new(client::session(config.session)?);
+            init::init(&input_dir, &client)
+        }
+        Args::Status => {
+            status::print(&input_dir, &history);
+            Ok(ExitCode::SUCCESS)
+        }
+        Args::Days { tag } => list_days(tag.as_deref()),
+        Args::Clean { files, dry_run } => clean(files, dry_run),
+    };
+    if let Err(e) = provenance.save() {
+        eprintln!("Warning: couldn't save input hashes: {:#}", e);
+    }
+    if let Err(e) = history.save() {
+        eprintln!("Warning: couldn't save the results: {:#}", e);
     }
+    exit_code
 }
@@ -0,0 +1,79 @@
+//! Number theory and interpolation that more than one day needs.
+
+/// The greatest common divisor of `a` and `b`, with `gcd(0, 0) == 0`.
+pub fn gcd(mut a: usize, mut b: usize) -> usize {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// The least common multiple of `a` and `b`, which is 0 if either of them is.
+pub fn lcm(a: usize, b: usize) -> usize {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    a / gcd(a, b) * b
+}
+
+/// Returns `(g, x, y)` such that `a * x + b * y == g == gcd(a, b)`.
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (1, 0);
+    let (mut old_y, mut y) = (0, 1);
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_x, x) = (x, old_x - q * x);
+        (old_y, y) = (y, old_y - q * y);
+    }
+    (old_r, old_x, old_y)
+}
+
+/// Solves the system of congruences `x ≡ residue (mod modulus)` given as `(residue,
+/// modulus)` pairs, returning the smallest non-negative solution along with the modulus
+/// every solution is congruent under. The moduli must be positive, but don't have to be
+/// coprime. Returns `None` if the congruences contradict each other.
+pub fn crt(congruences: impl IntoIterator<Item = (i128, i128)>) -> Option<(i128, i128)> {
+    let (mut x, mut m) = (0, 1);
+    for (r, n) in congruences {
+        assert!(n > 0, "modulus {} isn't positive", n);
+        // x + m * k ≡ r (mod n) has a solution iff gcd(m, n) divides r - x.
+        let (g, p, _) = extended_gcd(m, n);
+        let diff = r - x;
+        if diff % g != 0 {
+            return None;
+        }
+        let step = n / g;
+        let k = (diff / g % step * p % step).rem_euclid(step);
+        x += m * k;
+        m *= step;
+        x = x.rem_euclid(m);
+    }
+    Some((x, m))
+}
+
+/// The value at `x` of the polynomial of lowest degree passing through `points`, computed
+/// exactly with Lagrange interpolation. Returns `None` if that value isn't an integer. The
+/// x coordinates must be distinct, and the intermediate products must fit in an `i128`.
+pub fn extrapolate(points: &[(i128, i128)], x: i128) -> Option<i128> {
+    // The sum so far, as the fraction num / den.
+    let (mut num, mut den) = (0i128, 1i128);
+    for (i, &(xi, yi)) in points.iter().enumerate() {
+        let (mut term_num, mut term_den) = (yi, 1i128);
+        for (j, &(xj, _)) in points.iter().enumerate() {
+            if i != j {
+                term_num *= x - xj;
+                term_den *= xi - xj;
+            }
+        }
+        num = num * term_den + term_num * den;
+        den *= term_den;
+        let g = extended_gcd(num, den).0.abs();
+        if g > 1 {
+            num /= g;
+            den /= g;
+        }
+    }
+    (num % den == 0).then(|| num / den)
+}
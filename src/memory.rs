@@ -0,0 +1,115 @@
+//! Heap usage of the solvers, for `--show-memory`. The binary's global allocator keeps
+//! count of the bytes allocated on the heap, and of the most that were allocated at once
+//! since [`measure`] started measuring. With the `count-allocs` feature, it also counts
+//! every allocation. Until [`enable`] gets called it only forwards to the system
+//! allocator, so that runs that don't show their memory aren't slowed down. That's
+//! mimalloc with the `fast-alloc` feature, and the system's allocator otherwise.
+
+use std::{
+    alloc::{GlobalAlloc, Layout},
+    sync::atomic::{AtomicBool, AtomicIsize, AtomicUsize, Ordering::Relaxed},
+};
+
+#[cfg(feature = "fast-alloc")]
+static INNER: mimalloc::MiMalloc = mimalloc::MiMalloc;
+#[cfg(not(feature = "fast-alloc"))]
+static INNER: std::alloc::System = std::alloc::System;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+// Signed, since memory allocated before counting got enabled can be freed after.
+static ALLOCATED: AtomicIsize = AtomicIsize::new(0);
+static PEAK: AtomicIsize = AtomicIsize::new(0);
+// With `count-allocs`, the number of allocations and the bytes they asked for.
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+/// Whether this build counts allocations, i.e. has the `count-allocs` feature.
+pub const COUNTS_ALLOCATIONS: bool = cfg!(feature = "count-allocs");
+
+/// The underlying allocator, counting the bytes allocated through it once enabled.
+pub struct Counting;
+
+impl Counting {
+    fn grow(&self, bytes: usize) {
+        if ENABLED.load(Relaxed) {
+            if COUNTS_ALLOCATIONS {
+                ALLOCATIONS.fetch_add(1, Relaxed);
+                ALLOCATED_BYTES.fetch_add(bytes, Relaxed);
+            }
+            let now = ALLOCATED.fetch_add(bytes as isize, Relaxed) + bytes as isize;
+            PEAK.fetch_max(now, Relaxed);
+        }
+    }
+
+    fn shrink(&self, bytes: usize) {
+        if ENABLED.load(Relaxed) {
+            ALLOCATED.fetch_sub(bytes as isize, Relaxed);
+        }
+    }
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { INNER.alloc(layout) };
+        if !ptr.is_null() {
+            self.grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { INNER.alloc_zeroed(layout) };
+        if !ptr.is_null() {
+            self.grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { INNER.dealloc(ptr, layout) };
+        self.shrink(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = unsafe { INNER.realloc(ptr, layout, new_size) };
+        if !new_ptr.is_null() {
+            // Counted as allocating the new block before freeing the old one, which is
+            // what it costs when the block has to move. It counts as an allocation too.
+            self.grow(new_size);
+            self.shrink(layout.size());
+        }
+        new_ptr
+    }
+}
+
+/// Starts counting allocations.
+pub fn enable() {
+    ENABLED.store(true, Relaxed);
+}
+
+/// The heap usage of a run of a solver, from [`measure`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Usage {
+    /// The most heap memory the run had allocated at once, in bytes.
+    pub peak: usize,
+    /// The number of allocations (including reallocations) the run made, and the bytes
+    /// they asked for in total. Always 0 without the `count-allocs` feature.
+    pub allocations: usize,
+    pub allocated_bytes: usize,
+}
+
+/// Runs `f`, returning what it returned along with its heap usage. Whatever other threads
+/// allocate in the meantime counts too, and nothing gets counted until [`enable`] has
+/// been called.
+pub fn measure<R>(f: impl FnOnce() -> R) -> (R, Usage) {
+    let baseline = ALLOCATED.load(Relaxed);
+    PEAK.store(baseline, Relaxed);
+    let (allocations, allocated_bytes) = (ALLOCATIONS.load(Relaxed), ALLOCATED_BYTES.load(Relaxed));
+    let result = f();
+    let usage = Usage {
+        peak: (PEAK.load(Relaxed) - baseline).max(0) as usize,
+        allocations: ALLOCATIONS.load(Relaxed) - allocations,
+        allocated_bytes: ALLOCATED_BYTES.load(Relaxed) - allocated_bytes,
+    };
+    (result, usage)
+}
@@ -0,0 +1,751 @@
+//! Everything the runner prints goes through this module. With `--format json`, the output
+//! is a single [`Report`]. `SCHEMA_VERSION` only gets bumped for breaking changes (removed or
+//! renamed fields, changed meanings). New fields can be added at any time, so consumers
+//! should ignore fields they don't know about, and all fields added after version 1 are
+//! optional when deserializing.
+
+use std::{
+    env,
+    fmt::Display,
+    fs,
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    str::FromStr,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::Context;
+use clap::ValueEnum;
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::{Deserialize, Serialize};
+
+use crate::stats::Stats;
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The exit codes of the run commands, so that scripts can tell why a run didn't succeed.
+/// Other errors exit with 1 and invalid arguments with clap's 2, as usual.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exit {
+    Success = 0,
+    /// The variants of a part disagree on the answer, so at least one of them is wrong.
+    WrongAnswer = 3,
+    /// A part couldn't run because its input or its day is missing, and nothing failed.
+    Missing = 4,
+    /// A part failed or timed out.
+    Failed = 5,
+    /// `bench-all --compare-machines` found a part that got slower.
+    Regression = 6,
+}
+
+impl From<Exit> for ExitCode {
+    fn from(exit: Exit) -> Self {
+        ExitCode::from(exit as u8)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Report {
+    pub schema_version: u32,
+    #[serde(default)]
+    pub results: Vec<PartResult>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_time_ns: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget: Option<BudgetSummary>,
+}
+
+/// What `run-all --summary` and `bench-all --export` write: the [`Report`] of `--format
+/// json`, along with where and when it was made, so that summaries can be archived or
+/// compared between machines with `bench-all --compare-machines`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunSummary {
+    #[serde(flatten)]
+    pub report: Report,
+    /// The commit the runner was built from, if it was built from a git checkout.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_revision: Option<String>,
+    /// The architecture and OS the runner was built for, e.g. `x86_64-linux`.
+    pub target: String,
+    /// The model of the CPU the run was made on, if it could be found out.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu: Option<String>,
+    /// When the run finished, in RFC 3339.
+    pub finished_at: String,
+}
+
+/// How far `run-all --budget` got before running out of time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BudgetSummary {
+    pub budget_ns: u64,
+    pub exhausted: bool,
+    pub completed_days: Vec<usize>,
+}
+
+/// A line of a `--save-output` file, which gets one per result of every run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedResult {
+    pub schema_version: u32,
+    /// When the run finished, in RFC 3339.
+    pub saved_at: String,
+    #[serde(flatten)]
+    pub result: PartResult,
+}
+
+/// The model name of the CPU, from `/proc/cpuinfo`, so only on Linux (and not on every
+/// architecture there either).
+fn cpu_model() -> Option<String> {
+    let cpuinfo = fs::read_to_string("/proc/cpuinfo").ok()?;
+    cpuinfo.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        (key.trim() == "model name").then(|| value.trim().to_string())
+    })
+}
+
+/// Appends `results` to the file at `path` as JSON lines of [`SavedResult`]s, creating it
+/// if it doesn't exist yet.
+fn save_results(path: &Path, results: &[PartResult]) -> anyhow::Result<()> {
+    let saved_at = humantime::format_rfc3339_seconds(SystemTime::now()).to_string();
+    let mut lines = String::new();
+    for result in results {
+        let line = SavedResult {
+            schema_version: SCHEMA_VERSION,
+            saved_at: saved_at.clone(),
+            result: result.clone(),
+        };
+        lines += &serde_json::to_string(&line)?;
+        lines.push('\n');
+    }
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(lines.as_bytes()))
+        .with_context(|| format!("couldn't save the results to {}", path.display()))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartResult {
+    pub day: usize,
+    pub part: usize,
+    /// The input file, for results of `run-part --inputs`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input: Option<String>,
+    /// The solver variant, for results of `run-part --all-variants`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variant: Option<String>,
+    /// The algorithm of the day that produced the result, unless it's from a variant.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub algorithm: Option<String>,
+    /// The SHA-256 hash of the input, if it could be read.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_hash: Option<String>,
+    #[serde(flatten)]
+    pub outcome: Outcome,
+    /// The steps the solver explained its answer with, for `--explain`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub explanation: Vec<String>,
+    /// Whether the result was recorded by an earlier run instead of running the part, for
+    /// `run-all --cached`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cached: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Outcome {
+    Solved {
+        answer: String,
+        timing: Timing,
+    },
+    TimedOut {
+        timeout_ns: u64,
+    },
+    Failed {
+        error: String,
+        /// The line of the input that failed to parse, with a caret under the offending
+        /// column if it's known.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        excerpt: Option<String>,
+    },
+    Missing {
+        error: String,
+    },
+}
+
+impl Outcome {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Outcome::Solved { .. } => "solved",
+            Outcome::TimedOut { .. } => "timed out",
+            Outcome::Failed { .. } => "failed",
+            Outcome::Missing { .. } => "missing",
+        }
+    }
+
+    fn style(&self) -> &'static str {
+        match self {
+            Outcome::Solved { .. } => GREEN,
+            Outcome::TimedOut { .. } => MAGENTA,
+            Outcome::Failed { .. } => RED,
+            Outcome::Missing { .. } => YELLOW,
+        }
+    }
+}
+
+// SGR parameters for the parts of the text output.
+const HEADER: &str = "1;36";
+const ANSWER: &str = "1";
+const TIME: &str = "2";
+const GREEN: &str = "32";
+const RED: &str = "31";
+const YELLOW: &str = "33";
+const MAGENTA: &str = "35";
+
+/// Text output is colored unless `--no-color` is given, `NO_COLOR` is set, or stdout
+/// isn't a terminal.
+pub fn use_color(no_color: bool) -> bool {
+    !no_color && env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()) && io::stdout().is_terminal()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Day,
+    Part,
+    Input,
+    Status,
+    Answer,
+    Algorithm,
+    TimeMs,
+    TimeUs,
+    TimeNs,
+}
+
+const FIELDS: &[(&str, Field)] = &[
+    ("day", Field::Day),
+    ("part", Field::Part),
+    ("input", Field::Input),
+    ("status", Field::Status),
+    ("answer", Field::Answer),
+    ("algorithm", Field::Algorithm),
+    ("time_ms", Field::TimeMs),
+    ("time_us", Field::TimeUs),
+    ("time_ns", Field::TimeNs),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Piece {
+    Text(String),
+    Field(Field),
+}
+
+/// A line of text output per result, e.g. `{day}.{part}: {answer} ({time_ms}ms)`. The
+/// fields are `day`, `part`, `input`, `status`, `answer` (the error message for parts that
+/// weren't solved), `algorithm`, and the median time as `time_ms`, `time_us` or
+/// `time_ns`. Fields that don't apply to a result are rendered as `-`. `{{` and `}}` are
+/// literal braces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template(Vec<Piece>);
+
+impl FromStr for Template {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut pieces = Vec::new();
+        let mut text = String::new();
+        let mut rest = s;
+        while let Some(i) = rest.find(['{', '}']) {
+            text.push_str(&rest[..i]);
+            let (brace, after) = rest[i..].split_at(1);
+            if let Some(after) = after.strip_prefix(brace) {
+                text.push_str(brace);
+                rest = after;
+                continue;
+            }
+            if brace == "}" {
+                return Err("unmatched `}`, use `}}` for a literal brace".to_string());
+            }
+            let (name, after) = after
+                .split_once('}')
+                .ok_or("unclosed `{`, use `{{` for a literal brace")?;
+            let &(_, field) = FIELDS.iter().find(|&&(n, _)| n == name).ok_or_else(|| {
+                let names: Vec<_> = FIELDS.iter().map(|(n, _)| *n).collect();
+                format!(
+                    "unknown field {{{}}}, expected one of {}",
+                    name,
+                    names.join(", ")
+                )
+            })?;
+            if !text.is_empty() {
+                pieces.push(Piece::Text(std::mem::take(&mut text)));
+            }
+            pieces.push(Piece::Field(field));
+            rest = after;
+        }
+        text.push_str(rest);
+        if !text.is_empty() {
+            pieces.push(Piece::Text(text));
+        }
+        Ok(Template(pieces))
+    }
+}
+
+impl Template {
+    pub fn render(&self, result: &PartResult) -> String {
+        let timing = match &result.outcome {
+            Outcome::Solved { timing, .. } => Some(timing),
+            _ => None,
+        };
+        let time = |scale: f64| match timing {
+            Some(timing) => format!("{:.3}", timing.median_ns as f64 / scale),
+            None => "-".to_string(),
+        };
+        let mut line = String::new();
+        for piece in &self.0 {
+            match piece {
+                Piece::Text(text) => line += text,
+                Piece::Field(field) => match field {
+                    Field::Day => line += &result.day.to_string(),
+                    Field::Part => line += &result.part.to_string(),
+                    Field::Input => line += result.input.as_deref().unwrap_or("-"),
+                    Field::Status => line += result.outcome.label(),
+                    Field::Answer => match &result.outcome {
+                        Outcome::Solved { answer, .. } => line += answer,
+                        Outcome::TimedOut { .. } => line += "-",
+                        Outcome::Failed { error, .. } | Outcome::Missing { error } => line += error,
+                    },
+                    Field::Algorithm => line += result.algorithm.as_deref().unwrap_or("-"),
+                    Field::TimeMs => line += &time(1e6),
+                    Field::TimeUs => line += &time(1e3),
+                    Field::TimeNs => match timing {
+                        Some(timing) => line += &timing.median_ns.to_string(),
+                        None => line += "-",
+                    },
+                },
+            }
+        }
+        line
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Timing {
+    pub runs: usize,
+    pub rejected: usize,
+    pub min_ns: u64,
+    pub median_ns: u64,
+    pub mean_ns: u64,
+    pub max_ns: u64,
+    /// The median time spent parsing the input, for solvers that parse separately.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parse_median_ns: Option<u64>,
+    /// Whether the part reused the input parsed for the other part, in which case its time
+    /// doesn't include any parsing.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub parse_shared: bool,
+    /// The most heap memory any run had allocated at once, with `--show-memory`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peak_memory_bytes: Option<u64>,
+    /// The most allocations any run made, and the most bytes they asked for, in builds
+    /// with the `count-allocs` feature.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allocations: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allocated_bytes: Option<u64>,
+}
+
+/// The output of the `details` command. The shape of `details` depends on the day.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetailsReport<T> {
+    pub schema_version: u32,
+    pub day: usize,
+    pub details: T,
+}
+
+pub fn render_details<T: Serialize + Display>(
+    day: usize,
+    details: T,
+    format: Format,
+) -> anyhow::Result<String> {
+    Ok(match format {
+        Format::Text => details.to_string(),
+        Format::Json => serde_json::to_string_pretty(&DetailsReport {
+            schema_version: SCHEMA_VERSION,
+            day,
+            details,
+        })?,
+    })
+}
+
+pub fn nanos(d: Duration) -> u64 {
+    d.as_nanos().try_into().unwrap_or(u64::MAX)
+}
+
+/// `bytes` in the largest binary unit that keeps it at least 1, e.g. `1.5 MiB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+impl From<&Stats> for Timing {
+    fn from(stats: &Stats) -> Self {
+        Timing {
+            runs: stats.runs,
+            rejected: stats.rejected,
+            min_ns: nanos(stats.min),
+            median_ns: nanos(stats.median),
+            mean_ns: nanos(stats.mean),
+            max_ns: nanos(stats.max),
+            parse_median_ns: None,
+            parse_shared: false,
+            peak_memory_bytes: None,
+            allocations: None,
+            allocated_bytes: None,
+        }
+    }
+}
+
+/// A progress bar on stderr with an indicatif `template`. It stays hidden when stdout
+/// isn't a terminal, since the output is probably going to a file or another program then.
+pub fn progress_bar(len: usize, template: &str) -> ProgressBar {
+    if !io::stdout().is_terminal() {
+        return ProgressBar::hidden();
+    }
+    let style = ProgressStyle::with_template(template).expect("valid template");
+    ProgressBar::new(len as u64).with_style(style)
+}
+
+/// Collects the results of a command. In text mode, results get printed as soon as they
+/// come in, in JSON mode they're printed all at once in [`Printer::finish`].
+pub struct Printer {
+    format: Format,
+    show_time: bool,
+    quiet: bool,
+    template: Option<Template>,
+    color: bool,
+    progress: ProgressBar,
+    results: Vec<PartResult>,
+    total: Duration,
+    budget: Option<BudgetSummary>,
+    copy: bool,
+    save_output: Option<PathBuf>,
+    summary_file: Option<PathBuf>,
+}
+
+impl Printer {
+    /// In quiet mode, text output is just the answers, one per line. Errors still get
+    /// printed, but to stderr. With a template, text output is just one rendered line
+    /// per result.
+    pub fn new(
+        format: Format,
+        show_time: bool,
+        quiet: bool,
+        template: Option<Template>,
+        color: bool,
+    ) -> Self {
+        Self {
+            format,
+            show_time,
+            quiet,
+            template,
+            color,
+            progress: ProgressBar::hidden(),
+            results: Vec::new(),
+            total: Duration::ZERO,
+            budget: None,
+            copy: false,
+            save_output: None,
+            summary_file: None,
+        }
+    }
+
+    pub fn part(&mut self, result: PartResult) {
+        if let Outcome::Solved { timing, .. } = &result.outcome {
+            self.total += Duration::from_nanos(timing.median_ns);
+        }
+        if self.format == Format::Text {
+            self.progress.suspend(|| self.print_part(&result));
+        }
+        self.results.push(result);
+    }
+
+    /// Shows a progress bar for `len` parts, and returns it so that the runner can
+    /// advance it. The bar stays hidden in quiet mode.
+    pub fn progress(&mut self, len: usize) -> ProgressBar {
+        if !self.quiet {
+            self.progress = progress_bar(len, "{bar:40} {pos}/{len} parts, {msg} (ETA {eta})");
+        }
+        self.progress.clone()
+    }
+
+    fn paint(&self, style: &str, text: impl Display) -> String {
+        if self.color {
+            format!("\x1b[{}m{}\x1b[0m", style, text)
+        } else {
+            text.to_string()
+        }
+    }
+
+    // Whether the text output is only the results, without headers, summaries and totals.
+    fn bare(&self) -> bool {
+        self.quiet || self.template.is_some()
+    }
+
+    fn print_part(&self, result: &PartResult) {
+        if let Some(template) = &self.template {
+            println!("{}", template.render(result));
+            return;
+        }
+        if self.quiet {
+            match &result.outcome {
+                Outcome::Solved { answer, .. } => println!("{}", answer),
+                Outcome::TimedOut { timeout_ns } => eprintln!(
+                    "Day {} part {} timed out after {:.3?}",
+                    result.day,
+                    result.part,
+                    Duration::from_nanos(*timeout_ns)
+                ),
+                Outcome::Failed { error, .. } | Outcome::Missing { error } => {
+                    eprintln!("Day {} part {}: {}", result.day, result.part, error)
+                }
+            }
+            return;
+        }
+        let cached = if result.cached { " (cached)" } else { "" };
+        let header = format!(
+            "===== Day {} Part {}{} =====",
+            result.day, result.part, cached
+        );
+        println!("{}", self.paint(HEADER, header));
+        let style = result.outcome.style();
+        match &result.outcome {
+            Outcome::Solved { answer, timing } => {
+                println!("{}", self.paint(ANSWER, answer));
+                if self.show_time {
+                    let median = Duration::from_nanos(timing.median_ns);
+                    let mut line = format!("Finished in: {:.3?}", median);
+                    if let Some(parse) = timing.parse_median_ns {
+                        line += &format!(", {:.3?} of it parsing", Duration::from_nanos(parse));
+                    }
+                    if timing.parse_shared {
+                        line += ", reusing part 1's parsed input";
+                    }
+                    if let (Some(count), Some(bytes)) = (timing.allocations, timing.allocated_bytes)
+                    {
+                        line += &format!(", {} allocations ({})", count, format_bytes(bytes));
+                    }
+                    if timing.runs + timing.rejected != 1 {
+                        line += &format!(
+                            " (median of {} runs, min {:.3?}, mean {:.3?}, max {:.3?}",
+                            timing.runs,
+                            Duration::from_nanos(timing.min_ns),
+                            Duration::from_nanos(timing.mean_ns),
+                            Duration::from_nanos(timing.max_ns),
+                        );
+                        if timing.rejected > 0 {
+                            line += &format!(", {} outliers rejected", timing.rejected);
+                        }
+                        line += ")";
+                    }
+                    println!("{}", self.paint(TIME, line));
+                }
+                if let Some(peak) = timing.peak_memory_bytes {
+                    let line = format!("Peak memory: {}", format_bytes(peak));
+                    println!("{}", self.paint(TIME, line));
+                }
+                for step in &result.explanation {
+                    println!("  {}", step);
+                }
+            }
+            Outcome::TimedOut { timeout_ns } => {
+                let line = format!("Timed out after {:.3?}", Duration::from_nanos(*timeout_ns));
+                println!("{}", self.paint(style, line));
+            }
+            Outcome::Failed { error, excerpt } => {
+                println!("{}", self.paint(style, error));
+                if let Some(excerpt) = excerpt {
+                    println!("{}", excerpt);
+                }
+            }
+            Outcome::Missing { error } => println!("{}", self.paint(style, error)),
+        }
+    }
+
+    /// Adds the results of running one part against several inputs, or with several
+    /// variants of its solver. In text mode, they get printed as a table with one line per
+    /// input or variant instead of one block per result, with `label` as the first heading.
+    pub fn batch(&mut self, label: &str, results: Vec<PartResult>) {
+        if self.format == Format::Text && self.bare() {
+            for result in &results {
+                self.print_part(result);
+            }
+        } else if self.format == Format::Text {
+            let rows: Vec<_> = results
+                .iter()
+                .map(|r| {
+                    let input = r.variant.as_deref().or(r.input.as_deref()).unwrap_or("-");
+                    match &r.outcome {
+                        Outcome::Solved { answer, timing } => {
+                            let median = Duration::from_nanos(timing.median_ns);
+                            (input, answer.clone(), ANSWER, format!("{:.3?}", median))
+                        }
+                        outcome => {
+                            let label = outcome.label().to_string();
+                            (input, label, outcome.style(), "-".to_string())
+                        }
+                    }
+                })
+                .collect();
+            let input_width = rows.iter().map(|r| r.0.len()).max().unwrap_or(0);
+            let input_width = input_width.max(label.len());
+            let answer_width = rows.iter().map(|r| r.1.len()).max().unwrap_or(0).max(6);
+            let header = format!("{:input_width$}  {:answer_width$}  Time", label, "Answer");
+            println!("{}", self.paint(HEADER, header));
+            for (input, answer, style, time) in rows {
+                // Pad before painting, so the escape codes don't count towards the width.
+                let answer = self.paint(style, format!("{answer:answer_width$}"));
+                println!("{input:input_width$}  {answer}  {}", self.paint(TIME, time));
+            }
+        }
+        for result in results {
+            if let Outcome::Solved { timing, .. } = &result.outcome {
+                self.total += Duration::from_nanos(timing.median_ns);
+            }
+            self.results.push(result);
+        }
+    }
+
+    /// Prints a table with the status of every part that was run. This is only
+    /// useful for text output, as the JSON report already contains every status.
+    pub fn summary(&self) {
+        if self.format != Format::Text || self.bare() {
+            return;
+        }
+        println!("{}", self.paint(HEADER, "===== Summary ====="));
+        println!("Day  Part 1     Part 2");
+        for day in self.results.chunk_by(|a, b| a.day == b.day) {
+            let status = |part, width| match day.iter().find(|r| r.part == part) {
+                Some(r) => self.paint(r.outcome.style(), format!("{:<width$}", r.outcome.label())),
+                None => format!("{:<width$}", "-"),
+            };
+            println!("{:>3}  {}  {}", day[0].day, status(1, 9), status(2, 0));
+        }
+        let count = |label| {
+            self.results
+                .iter()
+                .filter(|r| r.outcome.label() == label)
+                .count()
+        };
+        println!(
+            "Solved: {}, failed: {}, missing: {}, timed out: {}",
+            count("solved"),
+            count("failed"),
+            count("missing"),
+            count("timed out")
+        );
+    }
+
+    pub fn budget(&mut self, budget: Duration, exhausted: bool, completed_days: Vec<usize>) {
+        self.budget = Some(BudgetSummary {
+            budget_ns: nanos(budget),
+            exhausted,
+            completed_days,
+        });
+    }
+
+    /// Makes [`Printer::finish`] put the last answer on the clipboard.
+    pub fn copy_answer(&mut self) {
+        self.copy = true;
+    }
+
+    /// Makes [`Printer::finish`] append the results to the file at `path`.
+    pub fn save_output(&mut self, path: PathBuf) {
+        self.save_output = Some(path);
+    }
+
+    /// Makes [`Printer::finish`] write a [`RunSummary`] to the file at `path`.
+    pub fn summary_file(&mut self, path: PathBuf) {
+        self.summary_file = Some(path);
+    }
+
+    /// Prints whatever hasn't been printed yet. The exit code is a failure if any
+    /// part didn't get solved.
+    pub fn finish(self, show_total_time: bool) -> anyhow::Result<ExitCode> {
+        self.progress.finish_and_clear();
+        if let Some(path) = &self.save_output {
+            save_results(path, &self.results)?;
+        }
+        if self.copy {
+            let answer = self.results.iter().rev().find_map(|r| match &r.outcome {
+                Outcome::Solved { answer, .. } => Some(answer),
+                _ => None,
+            });
+            match answer.map(|answer| crate::clipboard::copy(answer)) {
+                Some(Ok(())) => {}
+                Some(Err(e)) => eprintln!("Warning: couldn't copy the answer: {:#}", e),
+                None => eprintln!("Warning: there's no answer to copy."),
+            }
+        }
+        let total = show_total_time.then_some(self.total);
+        let outcomes = || self.results.iter().map(|r| &r.outcome);
+        let exit =
+            if outcomes().any(|o| matches!(o, Outcome::Failed { .. } | Outcome::TimedOut { .. })) {
+                Exit::Failed
+            } else if outcomes().any(|o| matches!(o, Outcome::Missing { .. })) {
+                Exit::Missing
+            } else {
+                Exit::Success
+            };
+        if self.format == Format::Text && !self.bare() {
+            if let Some(total) = total {
+                println!("{}", self.paint(TIME, format!("Total time: {:.3?}", total)));
+            }
+            if let Some(budget) = self.budget.as_ref().filter(|b| b.exhausted) {
+                let days = budget.completed_days.iter().map(|d| d.to_string());
+                let line = format!(
+                    "Budget of {:.3?} exhausted, completed days: {}",
+                    Duration::from_nanos(budget.budget_ns),
+                    days.collect::<Vec<_>>().join(", ")
+                );
+                println!("{}", self.paint(YELLOW, line));
+            }
+        }
+        let report = Report {
+            schema_version: SCHEMA_VERSION,
+            results: self.results,
+            total_time_ns: total.map(nanos),
+            budget: self.budget,
+        };
+        if let Some(path) = &self.summary_file {
+            let summary = RunSummary {
+                report: report.clone(),
+                git_revision: option_env!("AOC2023_GIT_REVISION").map(String::from),
+                target: format!("{}-{}", env::consts::ARCH, env::consts::OS),
+                cpu: cpu_model(),
+                finished_at: humantime::format_rfc3339_seconds(SystemTime::now()).to_string(),
+            };
+            fs::write(path, serde_json::to_string_pretty(&summary)? + "\n")
+                .with_context(|| format!("couldn't write the summary to {}", path.display()))?;
+        }
+        if self.format == Format::Json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+        Ok(exit.into())
+    }
+}
@@ -0,0 +1,52 @@
+//! Turning panicking solvers into failed parts, so that one broken day doesn't take the
+//! rest of a run down with it.
+
+use std::{
+    cell::{Cell, RefCell},
+    panic::{self, AssertUnwindSafe, PanicHookInfo},
+};
+
+thread_local! {
+    // Whether a solver is running on this thread under `catch`.
+    static CATCHING: Cell<bool> = const { Cell::new(false) };
+    // Where and why the solver on this thread panicked, recorded by the hook.
+    static MESSAGE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+fn describe(info: &PanicHookInfo<'_>) -> String {
+    let payload = info.payload();
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("Box<dyn Any>");
+    match info.location() {
+        Some(location) => format!(" at {}: {}", location, message),
+        None => format!(": {}", message),
+    }
+}
+
+/// Installs a panic hook that keeps quiet about panics caught by [`catch`], since those
+/// get reported as the part's error instead. Other panics are printed as usual.
+pub fn install_hook() {
+    let default = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        if CATCHING.get() {
+            MESSAGE.set(Some(describe(info)));
+        } else {
+            default(info);
+        }
+    }));
+}
+
+/// Runs `f`, turning a panic into an error saying where and why it panicked.
+pub fn catch<R>(f: impl FnOnce() -> R) -> anyhow::Result<R> {
+    MESSAGE.take();
+    let previous = CATCHING.replace(true);
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    CATCHING.set(previous);
+    result.map_err(|_| {
+        let message = MESSAGE.take().unwrap_or_default();
+        anyhow::anyhow!("Solver panicked{}", message)
+    })
+}
@@ -0,0 +1,183 @@
+//! Helpers for turning malformed inputs into errors that point at the offending line,
+//! instead of panics. All parsers report problems with the input as a [`ParseError`].
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{fmt, str::FromStr};
+
+/// What's wrong with an input.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ParseErrorKind {
+    #[error("invalid number {token:?}: {reason}")]
+    InvalidNumber { token: String, reason: String },
+    #[error("expected {expected:?} in {token:?}")]
+    Missing { expected: String, token: String },
+    #[error("invalid {what} {token:?}")]
+    Invalid { what: &'static str, token: String },
+    /// A problem with the input as a whole, like a grid without a start tile.
+    #[error("{0}")]
+    Malformed(String),
+}
+
+/// A [`ParseErrorKind`] together with where in which day's input it happened, as far as
+/// that's known. Parsers fill in the line (and column, for grids), and the [`Solver`]s of
+/// the days fill in the day.
+///
+/// [`Solver`]: crate::Solver
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{}{kind}", Location(self))]
+pub struct ParseError {
+    pub day: Option<usize>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub kind: ParseErrorKind,
+}
+
+// The "day 1, line 2, column 3: " prefix of a parse error.
+struct Location<'a>(&'a ParseError);
+
+impl fmt::Display for Location<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let e = self.0;
+        let parts = [("day", e.day), ("line", e.line), ("column", e.column)];
+        let mut any = false;
+        for (name, value) in parts {
+            if let Some(value) = value {
+                let sep = if any { ", " } else { "" };
+                write!(f, "{}{} {}", sep, name, value)?;
+                any = true;
+            }
+        }
+        if any {
+            write!(f, ": ")?;
+        }
+        Ok(())
+    }
+}
+
+impl ParseError {
+    pub fn new(kind: ParseErrorKind) -> Self {
+        Self {
+            day: None,
+            line: None,
+            column: None,
+            kind,
+        }
+    }
+
+    /// `token` isn't a valid `what`, e.g. `invalid("cell", 'x')`.
+    pub fn invalid(what: &'static str, token: impl ToString) -> Self {
+        Self::new(ParseErrorKind::Invalid {
+            what,
+            token: token.to_string(),
+        })
+    }
+
+    pub fn malformed(message: impl ToString) -> Self {
+        Self::new(ParseErrorKind::Malformed(message.to_string()))
+    }
+
+    /// Sets the 1-based line number, unless a more specific one was set already.
+    pub fn at_line(mut self, line: usize) -> Self {
+        self.line.get_or_insert(line);
+        self
+    }
+
+    /// Sets the 1-based column number.
+    pub fn at_column(mut self, column: usize) -> Self {
+        self.column.get_or_insert(column);
+        self
+    }
+
+    /// Sets the 1-based line and column numbers.
+    pub fn at(self, line: usize, column: usize) -> Self {
+        self.at_line(line).at_column(column)
+    }
+
+    /// The part of the input that couldn't be parsed, if the error is about a single one.
+    pub fn token(&self) -> Option<&str> {
+        match &self.kind {
+            ParseErrorKind::InvalidNumber { token, .. }
+            | ParseErrorKind::Missing { token, .. }
+            | ParseErrorKind::Invalid { token, .. } => Some(token),
+            ParseErrorKind::Malformed(_) => None,
+        }
+    }
+}
+
+/// Fills in the day of the parse error in `e`, if there is one.
+pub fn in_day(mut e: anyhow::Error, day: usize) -> anyhow::Error {
+    if let Some(e) = e.downcast_mut::<ParseError>() {
+        e.day.get_or_insert(day);
+    }
+    e
+}
+
+/// `input` as a `str`, for the parsers that don't work on bytes. Fails at the first byte
+/// that isn't valid UTF-8.
+pub fn utf8(input: &[u8]) -> Result<&str, ParseError> {
+    core::str::from_utf8(input).map_err(|e| {
+        let before = &input[..e.valid_up_to()];
+        let line_start = before
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |i| i + 1);
+        let line = before.iter().filter(|&&b| b == b'\n').count() + 1;
+        let column = String::from_utf8_lossy(&before[line_start..])
+            .chars()
+            .count()
+            + 1;
+        ParseError::malformed("the input isn't valid UTF-8").at(line, column)
+    })
+}
+
+/// Parses every non-empty line of `input` with `parse`, adding the line number to errors.
+pub fn lines<T>(
+    input: &str,
+    mut parse: impl FnMut(&str) -> Result<T, ParseError>,
+) -> Result<Vec<T>, ParseError> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| parse(line).map_err(|e| e.at_line(i + 1)))
+        .collect()
+}
+
+/// Parses a number, or anything else that implements `FromStr`, quoting `s` in the error.
+pub fn num<T>(s: &str) -> Result<T, ParseError>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    s.parse().map_err(|e: T::Err| {
+        ParseError::new(ParseErrorKind::InvalidNumber {
+            token: s.to_string(),
+            reason: e.to_string(),
+        })
+    })
+}
+
+fn missing(expected: &str, s: &str) -> ParseError {
+    ParseError::new(ParseErrorKind::Missing {
+        expected: expected.to_string(),
+        token: s.to_string(),
+    })
+}
+
+/// Splits `s` at the first `delimiter`, or fails with an error naming the delimiter.
+pub fn split_once<'a>(s: &'a str, delimiter: &str) -> Result<(&'a str, &'a str), ParseError> {
+    s.split_once(delimiter).ok_or_else(|| missing(delimiter, s))
+}
+
+/// Strips `prefix` off `s`, or fails with an error naming the prefix.
+pub fn strip_prefix<'a>(s: &'a str, prefix: &str) -> Result<&'a str, ParseError> {
+    s.strip_prefix(prefix).ok_or_else(|| missing(prefix, s))
+}
+
+/// Strips `suffix` off `s`, or fails with an error naming the suffix.
+pub fn strip_suffix<'a>(s: &'a str, suffix: &str) -> Result<&'a str, ParseError> {
+    s.strip_suffix(suffix).ok_or_else(|| missing(suffix, s))
+}
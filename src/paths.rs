@@ -0,0 +1,70 @@
+//! Where the runner looks for files.
+
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+pub const INPUT_DIR_VAR: &str = "AOC_INPUT_DIR";
+
+/// The directory with the `day{N}.txt` input files. `--input-dir` takes precedence over
+/// `$AOC_INPUT_DIR`, then the config file, then the default of `input`.
+pub fn input_dir(flag: Option<PathBuf>, config: Option<PathBuf>) -> PathBuf {
+    flag.or_else(|| {
+        env::var_os(INPUT_DIR_VAR)
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+    })
+    .or(config)
+    .unwrap_or_else(|| PathBuf::from("input"))
+}
+
+pub fn input_file(dir: &Path, day: usize) -> PathBuf {
+    dir.join(format!("day{}.txt", day))
+}
+
+/// Where `new-day` puts the example input of a day, e.g. `input/day17-example.txt`.
+pub fn example_file(dir: &Path, day: usize) -> PathBuf {
+    dir.join(format!("day{}-example.txt", day))
+}
+
+/// The config files, in increasing order of precedence: the user's config
+/// (`$XDG_CONFIG_HOME/aoc2023/config.toml`, usually `~/.config/aoc2023/config.toml`),
+/// then `aoc.toml` in the working directory.
+pub fn config_files() -> Vec<PathBuf> {
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")));
+    let user = config_home.map(|dir| dir.join("aoc2023").join("config.toml"));
+    user.into_iter()
+        .chain([PathBuf::from("aoc.toml")])
+        .collect()
+}
+
+/// Where the runner keeps what it remembers between runs.
+pub fn state_dir() -> PathBuf {
+    PathBuf::from(".aoc2023")
+}
+
+/// The files in [`state_dir`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum StateFile {
+    /// The hashes of the input files that were run.
+    Inputs,
+    /// The latest result of every part.
+    Results,
+    /// What was downloaded from adventofcode.com, so that it only gets downloaded once.
+    Cache,
+}
+
+impl StateFile {
+    pub fn path(self) -> PathBuf {
+        let name = match self {
+            StateFile::Inputs => "inputs.json",
+            StateFile::Results => "results.json",
+            StateFile::Cache => "cache",
+        };
+        state_dir().join(name)
+    }
+}
@@ -0,0 +1,93 @@
+//! Solvers loaded from shared libraries at runtime with `run-part --plugin`, to compare
+//! other implementations of a day against the built-in one. A plugin exports the same two
+//! functions as the `ffi` feature's library (see `include/aoc2023.h`):
+//!
+//! - `int aoc2023_solve(unsigned int day, unsigned int part, const char *input, char **out)`
+//!   returns `AOC2023_OK` with the answer in `*out`, `AOC2023_FAILED` with an error
+//!   message in `*out`, or `AOC2023_INVALID` for parts the plugin doesn't implement.
+//! - `void aoc2023_free(char *s)` frees the strings put into `*out`.
+//!
+//! So a build of this crate with the `ffi` feature is a plugin too.
+
+use std::{
+    ffi::{c_char, c_int, c_uint, CStr, CString},
+    path::Path,
+    ptr,
+    sync::Arc,
+};
+
+use anyhow::Context;
+use aoc2023::Answer;
+use libloading::Library;
+
+type SolveFn = unsafe extern "C" fn(c_uint, c_uint, *const c_char, *mut *mut c_char) -> c_int;
+type FreeFn = unsafe extern "C" fn(*mut c_char);
+
+// The return codes of `aoc2023_solve`.
+const OK: c_int = 0;
+const FAILED: c_int = 1;
+const INVALID: c_int = 2;
+
+/// A loaded plugin. Clones share the library, which stays loaded until the last one is
+/// dropped.
+#[derive(Clone)]
+pub struct Plugin {
+    name: String,
+    solve: SolveFn,
+    free: FreeFn,
+    // Keeps `solve` and `free` valid.
+    _library: Arc<Library>,
+}
+
+impl Plugin {
+    /// Loads the plugin at `path`, naming it after the file.
+    ///
+    /// Loading a library runs its initialization code, so only load libraries you trust.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let library = unsafe { Library::new(path) }
+            .with_context(|| format!("couldn't load {}", path.display()))?;
+        let (solve, free) = unsafe {
+            let solve = *library
+                .get::<SolveFn>(b"aoc2023_solve\0")
+                .with_context(|| format!("{} doesn't export aoc2023_solve", path.display()))?;
+            let free = *library
+                .get::<FreeFn>(b"aoc2023_free\0")
+                .with_context(|| format!("{} doesn't export aoc2023_free", path.display()))?;
+            (solve, free)
+        };
+        let name = path.file_stem().map_or_else(
+            || path.display().to_string(),
+            |s| s.to_string_lossy().into(),
+        );
+        Ok(Plugin {
+            name,
+            solve,
+            free,
+            _library: Arc::new(library),
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Runs the plugin's solver of `part` of `day` on `input`.
+    pub fn solve(&self, day: usize, part: usize, input: &str) -> anyhow::Result<Answer> {
+        let input = CString::new(input).context("the input contains a NUL byte")?;
+        let mut out = ptr::null_mut();
+        let code = unsafe { (self.solve)(day as c_uint, part as c_uint, input.as_ptr(), &mut out) };
+        let text = (!out.is_null()).then(|| {
+            let text = unsafe { CStr::from_ptr(out) }
+                .to_string_lossy()
+                .into_owned();
+            unsafe { (self.free)(out) };
+            text
+        });
+        match (code, text) {
+            (OK, Some(answer)) => Ok(answer.into()),
+            (FAILED, Some(error)) => Err(anyhow::anyhow!(error)),
+            (INVALID, _) => anyhow::bail!("{} doesn't solve day {} part {}", self.name, day, part),
+            (code, _) => anyhow::bail!("{} returned the invalid code {}", self.name, code),
+        }
+    }
+}
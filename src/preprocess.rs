@@ -0,0 +1,69 @@
+//! Cleaning up inputs before they get solved. Every input goes through the same checks
+//! first, after which a day can clean it up further with its own hook. Doing this once up
+//! front keeps the parsers from each having to cope with things like Windows line endings,
+//! so that an input works the same however an editor saved it.
+
+use alloc::{borrow::Cow, string::String};
+
+use crate::parse::{self, ParseError};
+
+/// The days with their own preprocessing hook, which runs after the shared cleanup.
+static HOOKS: &[(usize, fn(&str) -> anyhow::Result<Cow<'_, str>>)] = &[
+    #[cfg(feature = "day15")]
+    (15, crate::day15::preprocess),
+];
+
+/// Whether `input` is already normalized: `\n` line endings, no whitespace at the end of
+/// any line, and no blank lines at the end.
+fn is_normalized(input: &str) -> bool {
+    !input.contains('\r')
+        && !input.contains(" \n")
+        && !input.contains("\t\n")
+        && input.trim_end() == input.strip_suffix('\n').unwrap_or(input)
+}
+
+/// Rejects inputs that can't be puzzle inputs, and normalizes the rest: the byte order
+/// mark, `\r`s in line endings, whitespace at the end of lines and blank lines at the end
+/// get stripped. The last line keeps its line break if it had one.
+fn clean(input: &str) -> Result<Cow<'_, str>, ParseError> {
+    if let Some((i, c)) = input
+        .char_indices()
+        .find(|&(_, c)| c.is_control() && !matches!(c, '\n' | '\r' | '\t'))
+    {
+        let before = &input[..i];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+        let message = format!(
+            "unexpected control character {:?}, is this a binary file?",
+            c
+        );
+        return Err(ParseError::malformed(message).at(line, column));
+    }
+    let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+    if is_normalized(input) {
+        return Ok(Cow::Borrowed(input));
+    }
+    let mut normalized = String::with_capacity(input.len());
+    for line in input.trim_end().lines() {
+        normalized += line.trim_end();
+        normalized.push('\n');
+    }
+    if !input.trim_end_matches([' ', '\t']).ends_with('\n') {
+        normalized.pop();
+    }
+    Ok(Cow::Owned(normalized))
+}
+
+/// Prepares `input` for being solved as `day`. The runner does this once per input,
+/// before hashing it and before running any part on it, so the parts can assume the
+/// input is clean.
+pub fn preprocess(day: usize, input: &str) -> anyhow::Result<Cow<'_, str>> {
+    let input = clean(input).map_err(|e| parse::in_day(e.into(), day))?;
+    let Some(&(_, hook)) = HOOKS.iter().find(|&&(d, _)| d == day) else {
+        return Ok(input);
+    };
+    if let Cow::Owned(s) = hook(&input).map_err(|e| parse::in_day(e, day))? {
+        return Ok(Cow::Owned(s));
+    }
+    Ok(input)
+}
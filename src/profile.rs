@@ -0,0 +1,41 @@
+//! The `profile` command, only built with the `profile` feature: run a part over and over
+//! under a sampling profiler, and draw where the time went as a flamegraph.
+
+use std::{
+    fs, hint, io,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+use aoc2023::Answer;
+
+/// Keeps calling `run` until `duration` has passed, sampling the stack `frequency` times
+/// a second, and saves the samples as a flamegraph SVG to `output`. Returns the number of
+/// runs. `run` gets called at least once, and the first run that fails stops profiling.
+pub fn profile(
+    mut run: impl FnMut() -> anyhow::Result<Answer>,
+    duration: Duration,
+    frequency: i32,
+    output: &Path,
+) -> anyhow::Result<usize> {
+    let guard = pprof::ProfilerGuardBuilder::default()
+        .frequency(frequency)
+        // Leave out the frames the profiler's signal handler runs in.
+        .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+        .build()
+        .context("couldn't start the profiler")?;
+    let start = Instant::now();
+    let mut runs = 0;
+    while runs == 0 || start.elapsed() < duration {
+        hint::black_box(run()?);
+        runs += 1;
+    }
+    let report = guard.report().build()?;
+    let file = fs::File::create(output)
+        .with_context(|| format!("failed to create {}", output.display()))?;
+    report
+        .flamegraph(io::BufWriter::new(file))
+        .with_context(|| format!("failed to write {}", output.display()))?;
+    Ok(runs)
+}
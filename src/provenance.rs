@@ -0,0 +1,63 @@
+//! Remembers the hash of every input file that was run, to warn when one of them changes
+//! (e.g. gets overwritten with someone else's input), since answers and timings from
+//! earlier runs only apply to the same input.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::paths::StateFile;
+
+pub fn hash(input: &str) -> String {
+    format!("{:x}", Sha256::digest(input))
+}
+
+pub struct Provenance {
+    file: PathBuf,
+    // Keyed by the canonical path of each input file.
+    hashes: BTreeMap<String, String>,
+    dirty: bool,
+}
+
+impl Provenance {
+    /// Loads the recorded hashes. A missing or unreadable file just means that nothing
+    /// has been recorded yet.
+    pub fn load() -> Self {
+        let file = StateFile::Inputs.path();
+        let hashes = fs::read(&file)
+            .ok()
+            .and_then(|json| serde_json::from_slice(&json).ok())
+            .unwrap_or_default();
+        Self {
+            file,
+            hashes,
+            dirty: false,
+        }
+    }
+
+    /// Records the hash of an input file, returning the previously recorded hash if it
+    /// was different.
+    pub fn record(&mut self, input: &Path, hash: &str) -> Option<String> {
+        let key = fs::canonicalize(input).unwrap_or_else(|_| input.to_owned());
+        let key = key.to_string_lossy().into_owned();
+        if self.hashes.get(&key).is_some_and(|old| old == hash) {
+            return None;
+        }
+        self.dirty = true;
+        self.hashes.insert(key, hash.to_owned())
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        if self.dirty {
+            if let Some(dir) = self.file.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&self.file, serde_json::to_string_pretty(&self.hashes)?)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,195 @@
+//! The `new-day` and `new-year` commands. `new-day` creates the files to start solving a
+//! day of this crate with. All 25 days are already listed in the `days!` table in
+//! `lib.rs`, each behind its `dayN` feature, and the stub registers itself, so there's
+//! nothing to wire up beyond creating the file. `new-year` creates a separate crate for
+//! another year's puzzles, with a stub for every day and a table of them for its runner.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+
+use crate::paths;
+
+const TEMPLATE: &str = "\
+use crate::{cancel::Token, parse, Answer, Day{day}, Puzzle};
+
+register_day!({day}, Day{day}, \"TODO\", tags: []);
+
+impl Puzzle for Day{day} {
+    type Parsed<'a> = &'a str;
+    const ALGORITHM: &'static str = \"v1\";
+
+    fn parse(input: &[u8]) -> anyhow::Result<&str> {
+        Ok(parse::utf8(input)?)
+    }
+
+    fn solve1(input: &&str, _: &Token) -> anyhow::Result<Answer> {
+        let _ = input;
+        todo!()
+    }
+
+    fn solve2(input: &&str, _: &Token) -> anyhow::Result<Answer> {
+        let _ = input;
+        todo!()
+    }
+}
+";
+
+/// Creates `path` with `contents`, returning whether it didn't exist yet. Existing files
+/// are left alone.
+fn create(path: &Path, contents: &str) -> anyhow::Result<bool> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+    match OpenOptions::new().write(true).create_new(true).open(path) {
+        Ok(mut file) => {
+            file.write_all(contents.as_bytes())
+                .with_context(|| format!("failed to write {}", path.display()))?;
+            Ok(true)
+        }
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => Ok(false),
+        Err(e) => Err(e).with_context(|| format!("failed to create {}", path.display())),
+    }
+}
+
+/// Creates the solver stub of `day` in `src`, the crate's source directory, and empty
+/// input and example files in `input_dir`, skipping the input files that already exist.
+/// Fails without creating anything if the stub already exists.
+pub fn new_day(day: usize, src: &Path, input_dir: &Path) -> anyhow::Result<()> {
+    // Every source directory of the crate has one, unlike most other directories.
+    if !src.join("lib.rs").is_file() {
+        anyhow::bail!(
+            "{} isn't the source directory of the crate, pass the right one with --src.",
+            src.display()
+        );
+    }
+    let stub = src.join(format!("day{}.rs", day));
+    if stub.exists() {
+        anyhow::bail!(
+            "{} already exists. Delete it first to start the day over.",
+            stub.display()
+        );
+    }
+    let files: [(PathBuf, &str); 3] = [
+        (stub, &TEMPLATE.replace("{day}", &day.to_string())),
+        (paths::input_file(input_dir, day), ""),
+        (paths::example_file(input_dir, day), ""),
+    ];
+    for (path, contents) in files {
+        if create(&path, contents)? {
+            println!("Created {}", path.display());
+        } else {
+            println!("{} already exists", path.display());
+        }
+    }
+    println!(
+        "Rebuild the runner, then run the day with `run-day {}`.",
+        day
+    );
+    Ok(())
+}
+
+const YEAR_MANIFEST: &str = "\
+[package]
+name = \"aoc{year}\"
+version = \"0.1.0\"
+edition = \"2021\"
+
+[dependencies]
+anyhow = \"1.0.44\"
+
+# Keeps the crate out of the workspace of any crate it's created in.
+[workspace]
+";
+
+const YEAR_MAIN: &str = "\
+//! Advent of Code {year}. `cargo run --release -- <day> [part]` runs a day on
+//! `input/day<day>.txt`, both parts unless one is given.
+
+use std::{env, fs, time::Instant};
+
+use anyhow::Context;
+
+{mods}
+type Part = fn(&str) -> anyhow::Result<String>;
+
+const DAYS: [[Part; 2]; 25] = [
+{table}];
+
+fn main() -> anyhow::Result<()> {
+    let mut args = env::args().skip(1);
+    let usage = \"usage: aoc{year} <day> [part]\";
+    let day: usize = args.next().context(usage)?.parse().context(usage)?;
+    let parts = match args.next() {
+        Some(part) => vec![part.parse::<usize>().context(usage)?],
+        None => vec![1, 2],
+    };
+    let solvers = DAYS.get(day.wrapping_sub(1)).context(\"the days go from 1 to 25\")?;
+    let path = format!(\"input/day{}.txt\", day);
+    let input = fs::read_to_string(&path).with_context(|| format!(\"couldn't read {}\", path))?;
+    for part in parts {
+        let solve = solvers.get(part.wrapping_sub(1)).context(\"the parts are 1 and 2\")?;
+        let start = Instant::now();
+        let answer = solve(&input)?;
+        println!(\"Day {} part {}: {} ({:?})\", day, part, answer, start.elapsed());
+    }
+    Ok(())
+}
+";
+
+const YEAR_DAY: &str = "\
+pub fn part1(input: &str) -> anyhow::Result<String> {
+    let _ = input;
+    anyhow::bail!(\"day {day} part 1 isn't solved yet\")
+}
+
+pub fn part2(input: &str) -> anyhow::Result<String> {
+    let _ = input;
+    anyhow::bail!(\"day {day} part 2 isn't solved yet\")
+}
+";
+
+/// Creates a crate for the puzzles of `year` in `dir`: a runner with a table of all 25
+/// days, a stub for every day, and empty input and example files. Fails without creating
+/// anything if `dir` already exists.
+pub fn new_year(year: u16, dir: &Path) -> anyhow::Result<()> {
+    if dir.exists() {
+        anyhow::bail!("{} already exists.", dir.display());
+    }
+    let mods: String = (1..=25).map(|day| format!("mod day{};\n", day)).collect();
+    let table: String = (1..=25)
+        .map(|day| format!("    [day{0}::part1, day{0}::part2],\n", day))
+        .collect();
+    let main = YEAR_MAIN
+        .replace("{mods}", &mods)
+        .replace("{table}", &table)
+        .replace("{year}", &year.to_string());
+    let mut files = vec![
+        (
+            dir.join("Cargo.toml"),
+            YEAR_MANIFEST.replace("{year}", &year.to_string()),
+        ),
+        (dir.join(".gitignore"), "/target\n".to_string()),
+        (dir.join("src").join("main.rs"), main),
+    ];
+    let input_dir = dir.join("input");
+    for day in 1..=25 {
+        let stub = YEAR_DAY.replace("{day}", &day.to_string());
+        files.push((dir.join("src").join(format!("day{}.rs", day)), stub));
+        files.push((paths::input_file(&input_dir, day), String::new()));
+        files.push((paths::example_file(&input_dir, day), String::new()));
+    }
+    for (path, contents) in &files {
+        create(path, contents)?;
+    }
+    println!(
+        "Created {} with {} files. Run a day with `cargo run --release -- <day>` in it.",
+        dir.display(),
+        files.len()
+    );
+    Ok(())
+}
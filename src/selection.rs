@@ -0,0 +1,53 @@
+use std::str::FromStr;
+
+/// A set of days, parsed from a comma separated list of days (`3`) and day ranges
+/// (`5..10`, `5..=10`), e.g. `1,3,17` or `5..=10,20`. Days are kept in ascending order
+/// without duplicates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DaySelection(Vec<usize>);
+
+impl DaySelection {
+    pub fn days(&self) -> &[usize] {
+        &self.0
+    }
+
+    /// Keeps only the days for which `f` returns true, which can leave none.
+    pub fn retain(&mut self, f: impl FnMut(&usize) -> bool) {
+        self.0.retain(f);
+    }
+}
+
+// `max` is 26 for the exclusive end of a range, and 25 otherwise.
+fn parse_day(s: &str, max: usize) -> Result<usize, String> {
+    let day = s
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid day: {s:?}"))?;
+    if !(1..=max).contains(&day) {
+        return Err(format!("day {day} is out of range, days go from 1 to 25"));
+    }
+    Ok(day)
+}
+
+impl FromStr for DaySelection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut days = Vec::new();
+        for item in s.split(',') {
+            if let Some((start, end)) = item.split_once("..=") {
+                days.extend(parse_day(start, 25)?..=parse_day(end, 25)?);
+            } else if let Some((start, end)) = item.split_once("..") {
+                days.extend(parse_day(start, 25)?..parse_day(end, 26)?);
+            } else {
+                days.push(parse_day(item, 25)?);
+            }
+        }
+        days.sort_unstable();
+        days.dedup();
+        if days.is_empty() {
+            return Err(format!("{s:?} doesn't select any days"));
+        }
+        Ok(Self(days))
+    }
+}
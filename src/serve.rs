@@ -0,0 +1,83 @@
+//! The `serve` command: the solvers as a JSON API over HTTP. `POST /solve/{day}/{part}`
+//! with the puzzle input as the body answers with `{"answer": ..., "elapsed_ns": ...}`,
+//! or with `{"error": ...}` and a 4xx or 5xx status if the part couldn't be solved.
+//! Requests are handled one at a time.
+
+use std::io::Read;
+
+use anyhow::Context;
+use serde_json::json;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::output::{Outcome, PartResult};
+
+/// The largest input accepted, far more than any day's.
+const MAX_INPUT: u64 = 16 << 20;
+
+type JsonResponse = (u16, serde_json::Value);
+
+fn error(status: u16, message: impl Into<String>) -> JsonResponse {
+    (status, json!({ "error": message.into() }))
+}
+
+fn handle(
+    request: &mut Request,
+    run_part: &mut impl FnMut(usize, usize, &str) -> PartResult,
+) -> JsonResponse {
+    let segments: Vec<_> = request.url().trim_matches('/').split('/').collect();
+    let ["solve", day, part] = segments[..] else {
+        return error(404, "not found, try POST /solve/{day}/{part}");
+    };
+    if *request.method() != Method::Post {
+        return error(405, "only POST is allowed");
+    }
+    let day = match day.parse() {
+        Ok(day) if aoc2023::is_day(day) => day,
+        _ => return error(404, format!("there is no day {}", day)),
+    };
+    let part = match part.parse() {
+        Ok(part @ 1..=2) => part,
+        _ => return error(404, format!("there is no part {}", part)),
+    };
+    let mut input = String::new();
+    let reader = request.as_reader().take(MAX_INPUT + 1);
+    if let Err(e) = { reader }.read_to_string(&mut input) {
+        return error(400, format!("couldn't read the input: {}", e));
+    }
+    if input.len() as u64 > MAX_INPUT {
+        return error(413, "the input is too large");
+    }
+    match run_part(day, part, &input).outcome {
+        Outcome::Solved { answer, timing } => (
+            200,
+            json!({ "answer": answer, "elapsed_ns": timing.median_ns }),
+        ),
+        Outcome::Failed { error: message, .. } => error(422, message),
+        Outcome::TimedOut { .. } => error(504, "the solver timed out"),
+        // The day isn't compiled in.
+        Outcome::Missing { error: message } => error(404, message),
+    }
+}
+
+/// Serves the API on `port` of localhost until the process gets killed, solving parts
+/// with `run_part`.
+pub fn serve(
+    port: u16,
+    mut run_part: impl FnMut(usize, usize, &str) -> PartResult,
+) -> anyhow::Result<()> {
+    let server = Server::http(("127.0.0.1", port))
+        .map_err(|e| anyhow::anyhow!(e))
+        .with_context(|| format!("couldn't listen on port {}", port))?;
+    eprintln!("Listening on http://127.0.0.1:{}", port);
+    let content_type =
+        Header::from_bytes("Content-Type", "application/json").expect("the header is valid");
+    for mut request in server.incoming_requests() {
+        let (status, body) = handle(&mut request, &mut run_part);
+        let response = Response::from_string(body.to_string())
+            .with_status_code(status)
+            .with_header(content_type.clone());
+        // The client hanging up early isn't the server's problem.
+        let _ = request.respond(response);
+    }
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use std::time::Duration;
+
+/// Summary statistics over the timings of repeated solver runs.
+#[derive(Debug, Clone)]
+pub struct Stats {
+    pub runs: usize,
+    pub rejected: usize,
+    pub min: Duration,
+    pub median: Duration,
+    pub mean: Duration,
+    pub max: Duration,
+}
+
+fn median(sorted: &[Duration]) -> Duration {
+    let n = sorted.len();
+    if n.is_multiple_of(2) {
+        (sorted[n / 2 - 1] + sorted[n / 2]) / 2
+    } else {
+        sorted[n / 2]
+    }
+}
+
+impl Stats {
+    /// Computes the statistics of `samples`, which must not be empty. If `reject_outliers`
+    /// is set, samples that are more than 3 scaled median absolute deviations away from
+    /// the median get discarded first.
+    pub fn from_samples(mut samples: Vec<Duration>, reject_outliers: bool) -> Stats {
+        assert!(!samples.is_empty());
+        samples.sort_unstable();
+        let total = samples.len();
+
+        if reject_outliers {
+            let med = median(&samples);
+            let mut deviations: Vec<_> = samples.iter().map(|&s| s.abs_diff(med)).collect();
+            deviations.sort_unstable();
+            // 1.4826 scales the MAD so that it estimates the standard deviation for
+            // normally distributed samples. If more than half the samples are identical
+            // the MAD is zero, and there's nothing sensible to reject.
+            let mad = median(&deviations).mul_f64(1.4826);
+            if !mad.is_zero() {
+                samples.retain(|&s| s.abs_diff(med) <= mad * 3);
+            }
+        }
+
+        let runs = samples.len();
+        Stats {
+            runs,
+            rejected: total - runs,
+            min: samples[0],
+            median: median(&samples),
+            mean: samples.iter().sum::<Duration>() / runs as u32,
+            max: samples[runs - 1],
+        }
+    }
+}
@@ -0,0 +1,56 @@
+//! The `status` command: an overview of all 25 days, from what's on disk and what earlier
+//! runs recorded, without running anything.
+
+use std::{fs, path::Path, time::Duration};
+
+use crate::{
+    history::History,
+    output::{Outcome, PartResult},
+    paths, provenance,
+};
+
+fn describe(result: Option<&PartResult>, input_hash: Option<&str>) -> String {
+    let Some(result) = result else {
+        return "-".to_string();
+    };
+    let mut text = match &result.outcome {
+        Outcome::Solved { answer, timing } => {
+            format!(
+                "{} ({:.3?})",
+                answer,
+                Duration::from_nanos(timing.median_ns)
+            )
+        }
+        outcome => outcome.label().to_string(),
+    };
+    if result.input_hash.as_deref() != input_hash {
+        text += " *";
+    }
+    text
+}
+
+/// Prints a line per day with whether its input file exists, and the latest answer and
+/// time of each part. Results that were run on a different input than the current file
+/// are marked with a `*`.
+pub fn print(input_dir: &Path, history: &History) {
+    let rows: Vec<_> = (1..=25)
+        .map(|day| {
+            let input = fs::read_to_string(paths::input_file(input_dir, day)).ok();
+            let hash = input.as_deref().map(provenance::hash);
+            let part = |part| describe(history.latest(day, part), hash.as_deref());
+            let input = if input.is_some() { "found" } else { "missing" };
+            (day, input, part(1), part(2))
+        })
+        .collect();
+    let width = rows.iter().map(|r| r.2.len()).max().unwrap_or(0).max(6);
+    println!("Day  Input    {:width$}  Part 2", "Part 1");
+    for (day, input, part1, part2) in &rows {
+        println!("{:>3}  {:7}  {:width$}  {}", day, input, part1, part2);
+    }
+    if rows
+        .iter()
+        .any(|r| r.2.ends_with('*') || r.3.ends_with('*'))
+    {
+        println!("* run on a different input than the current file");
+    }
+}
@@ -0,0 +1,98 @@
+//! Solving inputs too large to read into memory, one line at a time. Only the days whose
+//! parts look at every line on its own, or at a few lines only, support this. Streamed
+//! inputs don't go through [`preprocess`], so [`Lines`] strips the byte order mark and
+//! `\r`s itself.
+//!
+//! [`preprocess`]: crate::preprocess
+
+use std::{io::BufRead, string::String};
+
+use anyhow::Context;
+
+use crate::{
+    parse::{self, ParseError},
+    Answer,
+};
+
+/// The days that can be streamed, with the function solving a part of them.
+static DAYS: &[(usize, fn(usize, &mut Lines<'_>) -> anyhow::Result<Answer>)] = &[
+    #[cfg(feature = "day1")]
+    (1, crate::day1::stream),
+    #[cfg(feature = "day2")]
+    (2, crate::day2::stream),
+    #[cfg(feature = "day4")]
+    (4, crate::day4::stream),
+    #[cfg(feature = "day6")]
+    (6, crate::day6::stream),
+    #[cfg(feature = "day9")]
+    (9, crate::day9::stream),
+    #[cfg(feature = "day24")]
+    (24, crate::day24::stream),
+];
+
+/// The lines of a streamed input. Only the current line is kept in memory.
+pub struct Lines<'r> {
+    reader: &'r mut dyn BufRead,
+    buf: String,
+    number: usize,
+}
+
+impl<'r> Lines<'r> {
+    pub fn new(reader: &'r mut dyn BufRead) -> Self {
+        Self {
+            reader,
+            buf: String::new(),
+            number: 0,
+        }
+    }
+
+    /// The next line without its line ending, or `None` at the end of the input.
+    pub fn next_line(&mut self) -> anyhow::Result<Option<&str>> {
+        self.buf.clear();
+        let read = self
+            .reader
+            .read_line(&mut self.buf)
+            .context("failed to read the input")?;
+        if read == 0 {
+            return Ok(None);
+        }
+        self.number += 1;
+        let mut line = self.buf.strip_suffix('\n').unwrap_or(&self.buf);
+        line = line.strip_suffix('\r').unwrap_or(line);
+        if self.number == 1 {
+            line = line.strip_prefix('\u{feff}').unwrap_or(line);
+        }
+        Ok(Some(line))
+    }
+
+    /// The 1-based number of the line last returned by [`Lines::next_line`].
+    pub fn number(&self) -> usize {
+        self.number
+    }
+
+    /// Calls `f` on every remaining non-empty line, adding the line number to its errors,
+    /// like [`parse::lines`].
+    pub fn for_each(
+        &mut self,
+        mut f: impl FnMut(&str) -> Result<(), ParseError>,
+    ) -> anyhow::Result<()> {
+        while let Some(line) = self.next_line()? {
+            if !line.trim().is_empty() {
+                f(line).map_err(|e| e.at_line(self.number))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The days that can be streamed.
+pub fn days() -> impl Iterator<Item = usize> {
+    DAYS.iter().map(|&(day, _)| day)
+}
+
+/// Solves `part` of `day` on the input read from `reader`, or returns `None` if the day
+/// can't be streamed. Panics for parts other than 1 and 2.
+pub fn solve(day: usize, part: usize, reader: &mut dyn BufRead) -> Option<anyhow::Result<Answer>> {
+    let &(_, solve) = DAYS.iter().find(|&&(d, _)| d == day)?;
+    Some(solve(part, &mut Lines::new(reader)).map_err(|e| parse::in_day(e, day)))
+}
@@ -0,0 +1,183 @@
+//! Finding any of several strings at once, like the spelled out digits of day 1. The
+//! patterns go into a trie, read forwards for matching at the start of a string and
+//! backwards for matching at its end, so that checking a position is one walk down the
+//! trie rather than a comparison with every pattern.
+
+use alloc::vec::Vec;
+use core::ops::Range;
+
+// A trie of byte strings, each of which is known by its index in the list it came from.
+#[derive(Debug, Clone)]
+struct Trie {
+    // The root is the first node.
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Node {
+    children: Vec<(u8, u32)>,
+    // The index of the pattern ending here. Where a pattern is in the list twice, it's the
+    // first of them.
+    pattern: Option<u32>,
+}
+
+impl Trie {
+    fn new() -> Self {
+        Trie {
+            nodes: vec![Node::default()],
+        }
+    }
+
+    fn insert(&mut self, bytes: impl Iterator<Item = u8>, pattern: u32) {
+        let mut node = 0;
+        for b in bytes {
+            node = match self.nodes[node].children.iter().find(|&&(c, _)| c == b) {
+                Some(&(_, child)) => child as usize,
+                None => {
+                    let child = self.nodes.len();
+                    self.nodes[node].children.push((b, child as u32));
+                    self.nodes.push(Node::default());
+                    child
+                }
+            };
+        }
+        if node != 0 {
+            self.nodes[node].pattern.get_or_insert(pattern);
+        }
+    }
+
+    // Of the patterns that `bytes` starts with, the one that's first in the list, with its
+    // length.
+    fn first_match(&self, bytes: impl Iterator<Item = u8>) -> Option<(usize, u32)> {
+        let mut node = 0;
+        let mut found: Option<(usize, u32)> = None;
+        for (depth, b) in bytes.enumerate() {
+            match self.nodes[node].children.iter().find(|&&(c, _)| c == b) {
+                Some(&(_, child)) => node = child as usize,
+                None => break,
+            }
+            if let Some(pattern) = self.nodes[node].pattern {
+                if found.is_none_or(|(_, first)| pattern < first) {
+                    found = Some((depth + 1, pattern));
+                }
+            }
+        }
+        found
+    }
+}
+
+/// A list of byte strings to look for, each with a value. Where several patterns match
+/// at the same place, the one that comes first in the list wins, whatever their lengths.
+/// Empty patterns never match.
+#[derive(Debug, Clone)]
+pub struct Patterns<T> {
+    forward: Trie,
+    backward: Trie,
+    values: Vec<T>,
+}
+
+impl<T> Patterns<T> {
+    pub fn new<P: AsRef<[u8]>>(patterns: impl IntoIterator<Item = (P, T)>) -> Self {
+        let (mut forward, mut backward) = (Trie::new(), Trie::new());
+        let mut values = Vec::new();
+        for (i, (pattern, value)) in patterns.into_iter().enumerate() {
+            let pattern = pattern.as_ref();
+            forward.insert(pattern.iter().copied(), i as u32);
+            backward.insert(pattern.iter().rev().copied(), i as u32);
+            values.push(value);
+        }
+        Patterns {
+            forward,
+            backward,
+            values,
+        }
+    }
+
+    /// The pattern that `s` starts with, as its length and value.
+    pub fn prefix(&self, s: &[u8]) -> Option<(usize, &T)> {
+        let (len, i) = self.forward.first_match(s.iter().copied())?;
+        Some((len, &self.values[i as usize]))
+    }
+
+    /// The pattern that `s` ends with, as its length and value.
+    pub fn suffix(&self, s: &[u8]) -> Option<(usize, &T)> {
+        let (len, i) = self.backward.first_match(s.iter().rev().copied())?;
+        Some((len, &self.values[i as usize]))
+    }
+
+    /// The match in `s` that starts first, as where it is and its value.
+    pub fn find(&self, s: &[u8]) -> Option<(Range<usize>, &T)> {
+        (0..s.len()).find_map(|start| {
+            let (len, value) = self.prefix(&s[start..])?;
+            Some((start..start + len, value))
+        })
+    }
+
+    /// The match in `s` that ends last, as where it is and its value.
+    pub fn rfind(&self, s: &[u8]) -> Option<(Range<usize>, &T)> {
+        (1..=s.len()).rev().find_map(|end| {
+            let (len, value) = self.suffix(&s[..end])?;
+            Some((end - len..end, value))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_in_list_wins() {
+        let patterns = Patterns::new([("ab", 1), ("abc", 2), ("a", 3), ("abc", 4)]);
+        assert_eq!(patterns.prefix(b"abcd"), Some((2, &1)));
+        assert_eq!(patterns.prefix(b"ax"), Some((1, &3)));
+        // The longer pattern only wins if it comes first.
+        let patterns = Patterns::new([("abc", 1), ("ab", 2), ("bc", 3), ("c", 4)]);
+        assert_eq!(patterns.prefix(b"abc"), Some((3, &1)));
+        assert_eq!(patterns.prefix(b"abd"), Some((2, &2)));
+        assert_eq!(patterns.suffix(b"xabc"), Some((3, &1)));
+        assert_eq!(patterns.suffix(b"xbc"), Some((2, &3)));
+        assert_eq!(patterns.suffix(b"xc"), Some((1, &4)));
+    }
+
+    #[test]
+    fn overlapping_prefixes() {
+        let patterns = Patterns::new([("one", 1), ("eight", 8), ("two", 2), ("on", 0)]);
+        assert_eq!(patterns.find(b"xxoneight"), Some((2..5, &1)));
+        assert_eq!(patterns.rfind(b"xxoneight"), Some((4..9, &8)));
+        assert_eq!(patterns.find(b"twone"), Some((0..3, &2)));
+        assert_eq!(patterns.rfind(b"twone"), Some((2..5, &1)));
+        // A pattern that's a prefix of another one still matches where the other doesn't.
+        assert_eq!(patterns.find(b"xonx"), Some((1..3, &0)));
+        assert_eq!(patterns.find(b"tw"), None);
+        assert_eq!(patterns.rfind(b"ne"), None);
+    }
+
+    #[test]
+    fn empty_patterns() {
+        let patterns = Patterns::new([("", 0), ("a", 1)]);
+        assert_eq!(patterns.prefix(b"b"), None);
+        assert_eq!(patterns.find(b"ba"), Some((1..2, &1)));
+        assert_eq!(patterns.rfind(b""), None);
+
+        let none: Patterns<u32> = Patterns::new([""; 3].map(|p| (p, 0)));
+        assert_eq!(none.find(b"abc"), None);
+        assert_eq!(none.rfind(b"abc"), None);
+        let no_patterns = Patterns::<u32>::new(core::iter::empty::<(&str, u32)>());
+        assert_eq!(no_patterns.find(b"abc"), None);
+        assert_eq!(no_patterns.suffix(b"abc"), None);
+    }
+
+    #[test]
+    fn rfind_at_the_end() {
+        let patterns = Patterns::new([("nine", 9), ("ne", 1), ("9", 9)]);
+        assert_eq!(patterns.rfind(b"nine"), Some((0..4, &9)));
+        assert_eq!(patterns.rfind(b"xnine"), Some((1..5, &9)));
+        assert_eq!(patterns.rfind(b"ninex"), Some((0..4, &9)));
+        assert_eq!(patterns.rfind(b"nin9"), Some((3..4, &9)));
+        assert_eq!(patterns.rfind(b"9ne"), Some((1..3, &1)));
+        assert_eq!(patterns.rfind(b"nin"), None);
+        // The match ending last wins, even over a longer one ending earlier.
+        assert_eq!(patterns.rfind(b"nine9"), Some((4..5, &9)));
+    }
+}
@@ -0,0 +1,201 @@
+//! The `tui` dashboard: a grid of all 25 days, where the selected day can be run and its
+//! answers and timings looked at, including those of earlier runs in the same session.
+
+use std::{thread, time::Duration};
+
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Cell, Paragraph, Row, Table},
+    DefaultTerminal, Frame,
+};
+
+use aoc2023::cancel::Token;
+
+use crate::output::{Outcome, PartResult};
+
+const COLUMNS: usize = 5;
+
+struct Dashboard {
+    selected: usize,
+    // Every result of every day, oldest first.
+    history: Vec<Vec<PartResult>>,
+    status: String,
+}
+
+fn color(outcome: &Outcome) -> Color {
+    match outcome {
+        Outcome::Solved { .. } => Color::Green,
+        Outcome::TimedOut { .. } => Color::Magenta,
+        Outcome::Failed { .. } => Color::Red,
+        Outcome::Missing { .. } => Color::Yellow,
+    }
+}
+
+fn describe(outcome: &Outcome) -> String {
+    match outcome {
+        Outcome::Solved { answer, timing } => {
+            format!(
+                "{} ({:.3?})",
+                answer,
+                Duration::from_nanos(timing.median_ns)
+            )
+        }
+        Outcome::TimedOut { timeout_ns } => {
+            format!("timed out after {:.3?}", Duration::from_nanos(*timeout_ns))
+        }
+        Outcome::Failed { error, .. } | Outcome::Missing { error } => error.clone(),
+    }
+}
+
+impl Dashboard {
+    fn latest(&self, day: usize, part: usize) -> Option<&PartResult> {
+        self.history[day - 1].iter().rev().find(|r| r.part == part)
+    }
+
+    fn grid(&self) -> Table<'_> {
+        let rows = (0..25).collect::<Vec<_>>();
+        let rows = rows.chunks(COLUMNS).map(|days| {
+            Row::new(days.iter().map(|&i| {
+                let day = i + 1;
+                let mut spans = vec![Span::raw(format!("{:>2} ", day))];
+                for part in 1..=2 {
+                    spans.push(match self.latest(day, part) {
+                        Some(r) => Span::styled("*", color(&r.outcome)),
+                        None => Span::raw("."),
+                    });
+                }
+                let cell = Cell::from(Line::from(spans));
+                if i == self.selected {
+                    cell.add_modifier(Modifier::REVERSED)
+                } else {
+                    cell
+                }
+            }))
+        });
+        Table::new(rows, [Constraint::Length(6); COLUMNS])
+            .column_spacing(1)
+            .block(Block::bordered().title(" Days "))
+    }
+
+    fn results(&self) -> Paragraph<'_> {
+        let day = self.selected + 1;
+        let mut lines = Vec::new();
+        for part in 1..=2 {
+            let line = match self.latest(day, part) {
+                Some(r) => Line::styled(describe(&r.outcome), color(&r.outcome)),
+                None => Line::raw("not run yet"),
+            };
+            lines.push(Line::from(format!("Part {}:", part)).bold());
+            lines.push(line);
+        }
+        let history = &self.history[day - 1];
+        if !history.is_empty() {
+            lines.push(Line::raw(""));
+            lines.push(Line::from("History, newest first:").bold());
+            for r in history.iter().rev() {
+                let text = format!("Part {}: {}", r.part, describe(&r.outcome));
+                lines.push(Line::styled(text, Style::new().dim()));
+            }
+        }
+        Paragraph::new(lines).block(Block::bordered().title(format!(" Day {} ", day)))
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [main, status] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        let [grid, results] =
+            Layout::horizontal([Constraint::Length(36), Constraint::Min(0)]).areas(main);
+        frame.render_widget(self.grid(), grid);
+        frame.render_widget(self.results(), results);
+        frame.render_widget(Line::raw(&self.status).dim(), status);
+    }
+
+    fn select(&mut self, dx: isize, dy: isize) {
+        let x = (self.selected % COLUMNS)
+            .saturating_add_signed(dx)
+            .min(COLUMNS - 1);
+        let y = (self.selected / COLUMNS)
+            .saturating_add_signed(dy)
+            .min(24 / COLUMNS);
+        self.selected = y * COLUMNS + x;
+    }
+}
+
+const HELP: &str = "arrows: select day, enter: run both parts, 1/2: run one part, q: quit";
+
+/// Runs the dashboard until it gets closed. `run_parts` runs the given parts of a day
+/// and returns their results, stopping early if the token gets cancelled.
+pub fn run(
+    mut run_parts: impl FnMut(usize, &[usize], &Token) -> Vec<PartResult> + Send,
+) -> anyhow::Result<()> {
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, &mut run_parts);
+    ratatui::restore();
+    result
+}
+
+fn event_loop(
+    terminal: &mut DefaultTerminal,
+    run_parts: &mut (impl FnMut(usize, &[usize], &Token) -> Vec<PartResult> + Send),
+) -> anyhow::Result<()> {
+    let mut dashboard = Dashboard {
+        selected: 0,
+        history: vec![Vec::new(); 25],
+        status: HELP.to_string(),
+    };
+    loop {
+        terminal.draw(|frame| dashboard.draw(frame))?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let parts: &[usize] = match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Enter => &[1, 2],
+            KeyCode::Char('1') => &[1],
+            KeyCode::Char('2') => &[2],
+            code => {
+                match code {
+                    KeyCode::Left | KeyCode::Char('h') => dashboard.select(-1, 0),
+                    KeyCode::Right | KeyCode::Char('l') => dashboard.select(1, 0),
+                    KeyCode::Up | KeyCode::Char('k') => dashboard.select(0, -1),
+                    KeyCode::Down | KeyCode::Char('j') => dashboard.select(0, 1),
+                    _ => {}
+                }
+                continue;
+            }
+        };
+        let day = dashboard.selected + 1;
+        dashboard.status = format!("Running day {}... (esc: cancel)", day);
+        terminal.draw(|frame| dashboard.draw(frame))?;
+        // Run the parts on another thread, to keep reacting to esc in the meantime.
+        let token = Token::default();
+        let results = thread::scope(|scope| -> anyhow::Result<_> {
+            let worker = scope.spawn(|| run_parts(day, parts, &token));
+            while !worker.is_finished() {
+                if !event::poll(Duration::from_millis(50))? {
+                    continue;
+                }
+                if let Event::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press && key.code == KeyCode::Esc {
+                        token.cancel();
+                        dashboard.status = format!("Cancelling day {}...", day);
+                        terminal.draw(|frame| dashboard.draw(frame))?;
+                    }
+                }
+            }
+            Ok(worker
+                .join()
+                .unwrap_or_else(|e| std::panic::resume_unwind(e)))
+        })?;
+        dashboard.history[day - 1].extend(results);
+        dashboard.status = HELP.to_string();
+        // Warnings printed while running would otherwise stay on the screen.
+        terminal.clear()?;
+    }
+}
@@ -0,0 +1,120 @@
+//! Pictures of what a day's solution works with (e.g. the loop of day 10 or the energized
+//! tiles of day 16), for the `viz` command. Days draw on an [`Image`] with a pixel per
+//! cell, which gets shown in the terminal or scaled up and saved as a PNG. Saving PNGs
+//! needs the `std` feature.
+
+use alloc::{string::String, vec::Vec};
+use core::fmt::Write as _;
+#[cfg(feature = "std")]
+use std::io::Write;
+
+#[cfg(feature = "std")]
+use anyhow::Context;
+
+use crate::grid::Grid;
+
+pub type Rgb = [u8; 3];
+
+/// A day that can draw its input along with what its solution found out about it.
+pub trait Visualize {
+    fn visualize(input: &[u8]) -> anyhow::Result<Image>;
+}
+
+/// A picture, stored row by row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pixels: Vec<Rgb>,
+    width: usize,
+    height: usize,
+}
+
+impl Image {
+    /// A `width` by `height` image filled with `background`.
+    pub fn new(width: usize, height: usize, background: Rgb) -> Self {
+        Self {
+            pixels: vec![background; width * height],
+            width,
+            height,
+        }
+    }
+
+    /// An image with a pixel per cell of `grid`, colored by `color`.
+    pub fn from_grid<T>(grid: &Grid<T>, mut color: impl FnMut(&T) -> Rgb) -> Self {
+        Self {
+            pixels: grid.rows().flatten().map(&mut color).collect(),
+            width: grid.width(),
+            height: grid.height(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<Rgb> {
+        (x < self.width && y < self.height).then(|| self.pixels[y * self.width + x])
+    }
+
+    /// Colors the pixel at `(x, y)`. Panics if that's off the image.
+    pub fn set(&mut self, x: usize, y: usize, color: Rgb) {
+        assert!(
+            x < self.width && y < self.height,
+            "({}, {}) is off the image",
+            x,
+            y
+        );
+        self.pixels[y * self.width + x] = color;
+    }
+
+    /// The image as 24-bit color terminal output. Every character shows two pixels on top
+    /// of each other, so that the pixels come out roughly square.
+    pub fn to_ansi(&self) -> String {
+        let mut out = String::new();
+        for y in (0..self.height).step_by(2) {
+            for x in 0..self.width {
+                let [r, g, b] = self.pixels[y * self.width + x];
+                write!(out, "\x1b[38;2;{};{};{}m", r, g, b).unwrap();
+                match self.get(x, y + 1) {
+                    Some([r, g, b]) => write!(out, "\x1b[48;2;{};{};{}m", r, g, b).unwrap(),
+                    // The odd row out only gets the top half.
+                    None => out.push_str("\x1b[49m"),
+                }
+                out.push('▀');
+            }
+            out.push_str("\x1b[0m\n");
+        }
+        out
+    }
+
+    /// Writes the image as a PNG, drawing every pixel as a `scale` by `scale` square.
+    #[cfg(feature = "std")]
+    pub fn write_png(&self, writer: impl Write, scale: usize) -> anyhow::Result<()> {
+        let (width, height) = (self.width * scale, self.height * scale);
+        let mut encoder = png::Encoder::new(
+            writer,
+            u32::try_from(width).context("image too wide")?,
+            u32::try_from(height).context("image too tall")?,
+        );
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut data = Vec::with_capacity(width * height * 3);
+        for row in self.pixels.chunks(self.width.max(1)) {
+            let scaled: Vec<u8> = row
+                .iter()
+                .flat_map(|pixel| core::iter::repeat_n(pixel, scale).flatten())
+                .copied()
+                .collect();
+            for _ in 0..scale {
+                data.extend_from_slice(&scaled);
+            }
+        }
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&data)?;
+        writer.finish()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+//! The solvers for web pages, only built with the `wasm` feature. Build the library for
+//! the browser with
+//!
+//! ```text
+//! cargo rustc --lib --release --crate-type cdylib --target wasm32-unknown-unknown \
+//!     --no-default-features --features wasm
+//! ```
+//!
+//! and run `wasm-bindgen --target web` on the resulting `.wasm` file to get a module that
+//! exports [`solve`].
+
+use std::string::{String, ToString};
+
+use wasm_bindgen::prelude::*;
+
+/// Solves `part` of `day` for a pasted `input`, which gets preprocessed like the runner's
+/// inputs. Errors get thrown with their message. This uses [`Solver::part`], which
+/// doesn't time anything, since `wasm32-unknown-unknown` doesn't have a clock.
+///
+/// [`Solver::part`]: crate::Solver::part
+#[wasm_bindgen]
+pub fn solve(day: u32, part: u32, input: &str) -> Result<String, JsError> {
+    let (day, part) = (day as usize, part as usize);
+    let Some(solver) = crate::solver(day) else {
+        return Err(JsError::new(&match crate::is_day(day) {
+            true => format!("day {} isn't compiled in", day),
+            false => format!("there is no day {}", day),
+        }));
+    };
+    if !(1..=2).contains(&part) {
+        return Err(JsError::new(&format!("there is no part {}", part)));
+    }
+    let error = |e: anyhow::Error| JsError::new(&format!("{:#}", e));
+    let input = crate::preprocess(day, input).map_err(error)?;
+    let answer = solver
+        .part(part, &input, &crate::cancel::Token::default())
+        .map_err(error)?;
+    Ok(answer.to_string())
+}
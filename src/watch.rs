@@ -0,0 +1,97 @@
+//! The `watch` command: reruns a day whenever one of the watched files changes, e.g. the
+//! input or the runner itself after a `cargo build` in another terminal, and shows how the
+//! answers and timings changed since the previous run.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use crate::output::{Outcome, PartResult};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+// How long to wait after a change before rerunning, so that whoever is writing the file
+// has time to finish.
+const SETTLE_TIME: Duration = Duration::from_millis(300);
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn describe(result: &PartResult, previous: Option<&PartResult>) -> String {
+    let (answer, timing) = match &result.outcome {
+        Outcome::Solved { answer, timing } => (answer, timing),
+        Outcome::TimedOut { timeout_ns } => {
+            let timeout = Duration::from_nanos(*timeout_ns);
+            return format!("timed out after {:.3?}", timeout);
+        }
+        Outcome::Failed { error, .. } | Outcome::Missing { error } => {
+            return format!("{}: {}", result.outcome.label(), error)
+        }
+    };
+    let median = Duration::from_nanos(timing.median_ns);
+    let Some(previous) = previous else {
+        return format!("{} in {:.3?}", answer, median);
+    };
+    let Outcome::Solved {
+        answer: old_answer,
+        timing: old_timing,
+    } = &previous.outcome
+    else {
+        return format!(
+            "{} in {:.3?} (was {})",
+            answer,
+            median,
+            previous.outcome.label()
+        );
+    };
+    let mut line = answer.clone();
+    if answer != old_answer {
+        line += &format!(" (was {})", old_answer);
+    }
+    let change = timing.median_ns as f64 / old_timing.median_ns.max(1) as f64 - 1.0;
+    let old_median = Duration::from_nanos(old_timing.median_ns);
+    line += &format!(
+        " in {:.3?} ({:+.1}% from {:.3?})",
+        median,
+        change * 100.0,
+        old_median
+    );
+    if let (Some(algorithm), Some(old_algorithm)) = (&result.algorithm, &previous.algorithm) {
+        if algorithm != old_algorithm {
+            line += &format!(", algorithm changed from {}", old_algorithm);
+        }
+    }
+    line
+}
+
+/// Runs `run` for `day`, then again every time one of `files` changes.
+pub fn watch(day: usize, files: &[PathBuf], mut run: impl FnMut() -> Vec<PartResult>) -> ! {
+    let mut stamps: Vec<_> = files.iter().map(|f| modified(f)).collect();
+    let mut previous = Vec::new();
+    let mut reason = "first run".to_string();
+    loop {
+        let results = run();
+        println!("===== Day {} ({}) =====", day, reason);
+        for result in &results {
+            let old = previous
+                .iter()
+                .find(|r: &&PartResult| r.part == result.part);
+            println!("Part {}: {}", result.part, describe(result, old));
+        }
+        previous = results;
+
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            let new_stamps: Vec<_> = files.iter().map(|f| modified(f)).collect();
+            if let Some(i) = (0..files.len()).find(|&i| new_stamps[i] != stamps[i]) {
+                reason = format!("{} changed", files[i].display());
+                break;
+            }
+        }
+        thread::sleep(SETTLE_TIME);
+        stamps = files.iter().map(|f| modified(f)).collect();
+    }
+}